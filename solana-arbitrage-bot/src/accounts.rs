@@ -0,0 +1,164 @@
+use crate::config::RpcConfig;
+use crate::constants::sol_mint;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::warn;
+
+// getMultipleAccounts 单次最多查询 100 个账户
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// 批量获取账户时的并发与重试策略
+#[derive(Debug, Clone, Copy)]
+pub struct FetchPolicy {
+    pub max_concurrency: usize,
+    pub max_retries: usize,
+    // 第 n 次重试等待 retry_delay * 2^n
+    pub retry_delay: Duration,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 4,
+            max_retries: 3,
+            retry_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl FetchPolicy {
+    pub fn from_config(rpc_config: &RpcConfig) -> Self {
+        let default = Self::default();
+        Self {
+            max_concurrency: rpc_config
+                .fetch_concurrency
+                .unwrap_or(default.max_concurrency)
+                .max(1),
+            max_retries: rpc_config.fetch_retries.unwrap_or(default.max_retries),
+            retry_delay: rpc_config
+                .fetch_retry_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.retry_delay),
+        }
+    }
+}
+
+// 单个分片的请求，失败后按指数退避重试；结果附带查询时的 slot
+async fn fetch_chunk(
+    rpc_client: &NonblockingRpcClient,
+    chunk: &[Pubkey],
+    policy: &FetchPolicy,
+) -> anyhow::Result<Vec<(Pubkey, (u64, Account))>> {
+    let mut attempt = 0;
+    loop {
+        match rpc_client
+            .get_multiple_accounts_with_commitment(chunk, rpc_client.commitment())
+            .await
+        {
+            Ok(response) => {
+                let slot = response.context.slot;
+                return Ok(chunk
+                    .iter()
+                    .zip(response.value)
+                    .filter_map(|(pubkey, account)| account.map(|account| (*pubkey, (slot, account))))
+                    .collect());
+            }
+            Err(e) if attempt < policy.max_retries => {
+                let delay = policy.retry_delay * 2u32.pow(attempt as u32);
+                warn!(
+                    "getMultipleAccounts for {} accounts failed (attempt {}): {}, retrying in {:?}",
+                    chunk.len(),
+                    attempt + 1,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "getMultipleAccounts for {} accounts failed after {} attempts: {}",
+                    chunk.len(),
+                    attempt + 1,
+                    e
+                ));
+            }
+        }
+    }
+}
+
+// 去重后按 100 个一组并发获取账户，不存在的账户不会出现在结果中
+pub async fn fetch_multiple_accounts(
+    rpc_client: &NonblockingRpcClient,
+    pubkeys: &[Pubkey],
+    policy: &FetchPolicy,
+) -> anyhow::Result<HashMap<Pubkey, Account>> {
+    Ok(fetch_multiple_accounts_with_slot(rpc_client, pubkeys, policy)
+        .await?
+        .into_iter()
+        .map(|(pubkey, (_, account))| (pubkey, account))
+        .collect())
+}
+
+// 同 fetch_multiple_accounts，额外返回每个账户所在分片查询时的 slot
+pub async fn fetch_multiple_accounts_with_slot(
+    rpc_client: &NonblockingRpcClient,
+    pubkeys: &[Pubkey],
+    policy: &FetchPolicy,
+) -> anyhow::Result<HashMap<Pubkey, (u64, Account)>> {
+    let mut seen = HashSet::new();
+    let unique: Vec<Pubkey> = pubkeys
+        .iter()
+        .filter(|pubkey| seen.insert(**pubkey))
+        .copied()
+        .collect();
+
+    // 分片拷贝成 Vec，避免 future 借用分片引用，否则放进 tokio::spawn 的任务无法证明 Send
+    let chunks: Vec<Vec<Pubkey>> = unique
+        .chunks(MAX_MULTIPLE_ACCOUNTS)
+        .map(<[Pubkey]>::to_vec)
+        .collect();
+    let chunks: Vec<Vec<(Pubkey, (u64, Account))>> = stream::iter(chunks)
+        .map(|chunk| async move { fetch_chunk(rpc_client, &chunk, policy).await })
+        .buffer_unordered(policy.max_concurrency)
+        .try_collect()
+        .await?;
+
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+// mint 账户的 owner 即该 mint 的 token program，Token-2022 的 mint 由 Token-2022 程序持有
+pub async fn fetch_token_program(rpc_client: &NonblockingRpcClient, mint: &Pubkey) -> anyhow::Result<Pubkey> {
+    let account = rpc_client
+        .get_account(mint)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch mint account {}: {}", mint, e))?;
+    Ok(account.owner)
+}
+
+// 钱包持有某个 mint 的 ATA，地址随 token program 不同而不同
+pub fn wallet_token_account(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(wallet, mint, token_program)
+}
+
+// 钱包中可用于套利的 base token 余额：SOL 取钱包 lamports，其他代币取 ATA 余额
+pub async fn fetch_base_balance(
+    rpc_client: &NonblockingRpcClient,
+    wallet: &Pubkey,
+    base_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> anyhow::Result<u64> {
+    if *base_mint == sol_mint() {
+        return Ok(rpc_client.get_balance(wallet).await?);
+    }
+    let token_account = wallet_token_account(wallet, base_mint, token_program);
+    let balance = rpc_client.get_token_account_balance(&token_account).await?;
+    balance
+        .amount
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid token balance for {}: {}", token_account, e))
+}
@@ -1,26 +1,75 @@
-use create::config::Config;
-use create::refresh::intialize_pool_data;
-use create::transaction::build_and_send_transaction;
+use create::accounts::{fetch_base_balance, fetch_multiple_accounts, fetch_token_program, FetchPolicy};
+use create::compute_budget::{ComputeBudget, MAX_COMPUTE_UNIT_LIMIT};
+use create::config::{Config, MintConfig};
+use create::confirmation::{ConfirmationTracker, SentTransaction};
+use create::journal::{unix_timestamp, Journal, JournalEntry, OpportunityEntry, SentEntry};
+use create::pools::MintPoolData;
+use create::priority_fee::{
+    route_fee_samples, spawn_priority_fee_refresher, static_compute_unit_price, FeeSamples,
+    PriorityFeeCache, PriorityFeeEstimator,
+};
+use create::dex::collect_pool_accounts;
+use create::jito::JitoSender;
+use create::flash_loan::{
+    load_providers, provider_configs, spawn_liquidity_refresher, FlashLoanProvider, FlashLoanProviders,
+};
+use create::quote::graph::{
+    cycle_params, find_profitable_cycle, RouteGraph, DEFAULT_MAX_HOPS, EXECUTABLE_MAX_HOPS,
+};
+use create::quote::{base_in_lamports, find_profitable_route, profitable_route, QuoteParams};
+use create::refresh::{initialize_pool_data, update_pool_data};
+use create::reload::{non_reloadable_changes, pools_changed, settings_changed, LoadedConfig, ReloadTrigger, RoutingChanges};
+use create::risk::{run_risk_monitor, RiskGuard, RiskLimits};
+use create::stream::{account_source, spawn_account_stream, AccountUpdateSource, LiveSnapshot};
+use create::supervisor::{wait_for_signal, Shutdown, Supervisor};
+use create::window::{spawn_array_window_refresher, ArrayWindow};
+use create::simulate::simulation_gate;
+use create::transaction::{build_transaction, route_accounts, send_transaction, TransactionParams};
 use anyhow::Context;
 
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
 use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
 use solana_sdk::address_lookup_table::state::AddressLookupTable;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::io::Write;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tracing::{error, info, warn};
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{debug, error, info, warn};
+
+// 项目默认添加的ALT地址
+const DEFAULT_LOOKUP_TABLE: &str = "CCXTHFHXar7fTEQWrcC7iAWcJSJRbYBjwgtGxBKSz6rt";
+// 落地率统计中 Jito bundle 的发送通道
+const JITO_LABEL: &str = "jito";
 
 pub async fn run(config_path: &str) -> anyhow::Result<()> {
-    let config = Config::load(config_path)?;
+    let LoadedConfig {
+        config,
+        raw: raw_config,
+    } = LoadedConfig::load(config_path)?;
     info!("Starting bot with config: {:?}", config);
 
     let rpc_clients = Arc::new(RpcClient::new(config.rpc.url.clone()));
+    // 批量获取账户和报价使用非阻塞客户端
+    let nonblocking_rpc_client = Arc::new(NonblockingRpcClient::new(config.rpc.url.clone()));
+    let fetch_policy = FetchPolicy::from_config(&config.rpc);
+    // 开启 stream 时，池子状态由账户订阅推送，报价不再每轮拉取
+    let stream_source = config
+        .stream
+        .as_ref()
+        .filter(|stream_config| stream_config.enabled)
+        .map(account_source);
+    let array_window = ArrayWindow::from_config(config.array_window.as_ref());
+    let compute_budget = ComputeBudget::from_config(&config);
     let sending_rpc_clients = if let Some(spam_config) = &config.spam{
         if spam_config.enabled{
             spam_config
@@ -34,6 +83,25 @@ pub async fn run(config_path: &str) -> anyhow::Result<()> {
         }
     };
 
+    // 与 sending_rpc_clients 一一对应，用于按 RPC 统计落地率
+    let sending_rpc_labels = match &config.spam {
+        Some(spam_config) if spam_config.enabled => spam_config.sending_rpc_urls.clone(),
+        _ => vec![config.rpc.url.clone()],
+    };
+
+    // 开启 Jito 时，交易和小费转账打包成 bundle 发送到 block engine，不再经过 sending RPC
+    if let Some(jito_config) = config.jito.as_ref().filter(|jito_config| jito_config.enabled) {
+        info!(
+            "Jito bundle submission enabled via {} block engines, tip {} lamports",
+            jito_config.block_engine_urls.len(),
+            jito_config.tip_lamports
+        );
+    }
+
+    if config.simulation.as_ref().map_or(false, |simulation_config| simulation_config.enabled) {
+        info!("Simulation gate enabled, unprofitable transactions will be dropped before sending");
+    }
+
     let wallet_kp = 
         load_keypair(&config.wallet.private_key).context("Failed to load wallet keypair")?;
     // 打印钱包公钥
@@ -46,159 +114,1339 @@ pub async fn run(config_path: &str) -> anyhow::Result<()> {
     * 为了共享可变的最后一个blockhash，使用Arc和Mutex来包装它
     */
     let cached_blockhash = Arc::new(Mutex::new(initial_blockhash));
-    tokio::spawn(async move{
-        blockhash_refresher(blockhash_client, blockhash_cache, refresh_interval).await;
-    })
     // 每个block的刷新时间大概是150s，这个刷新间隔较为合理
     let refresh_interval = Duration::from_secs(10);
 
+    // 持有所有后台任务，失败的任务按退避间隔重启，收到关闭信号后统一停止
+    let mut supervisor = Supervisor::new(config.supervisor.as_ref());
+
     // 创建一个异步任务用于后台刷新
     let blockhash_client = rpc_client.clone();
     let blockhash_cache = cached_blockhash.clone();
-    tokio::spawn(async move {
-        blockhash_refresher(blockhash_client, blockhash_cache, refresh_interval).await;
+    supervisor.supervise("blockhash refresher", move |shutdown| {
+        blockhash_refresher(
+            blockhash_client.clone(),
+            blockhash_cache.clone(),
+            refresh_interval,
+            shutdown,
+        )
     });
 
+    // 机会、发送和确认结果写入 journal，供 report 子命令汇总
+    let (journal, journal_writer) =
+        match config.journal.as_ref().filter(|journal_config| journal_config.enabled) {
+            Some(journal_config) => {
+                let (journal, journal_writer) = Journal::open(&journal_config.path).await?;
+                (Some(journal), Some(journal_writer))
+            }
+            None => (None, None),
+        };
+    // 记录到 journal 的小费：开启 Jito 时为 bundle 的固定小费
+    let tip_lamports = match &config.jito {
+        Some(jito_config) if jito_config.enabled => Some(jito_config.tip_lamports),
+        _ => config.quote.as_ref().and_then(|quote_config| quote_config.tip_lamports),
+    };
+
+    // 风控：亏损、手续费、连续失败、SOL 余额或 kill switch 触发时暂停发送
+    let risk = match config.risk.as_ref().filter(|risk_config| risk_config.enabled) {
+        Some(risk_config) => {
+            let guard = RiskGuard::new(RiskLimits::from_config(risk_config, base_per_sol(&config)?));
+            let monitor_guard = guard.clone();
+            let monitor_client = nonblocking_rpc_client.clone();
+            let wallet = wallet_kp.pubkey();
+            let risk_config = risk_config.clone();
+            supervisor.supervise("risk monitor", move |shutdown| {
+                run_risk_monitor(
+                    monitor_guard.clone(),
+                    monitor_client.clone(),
+                    wallet,
+                    risk_config.clone(),
+                    shutdown,
+                )
+            });
+            Some(guard)
+        }
+        None => None,
+    };
+
+    // 跟踪已发送交易的落地情况和实际损益
+    let (confirmation_tracker, mut confirmation_handle) = ConfirmationTracker::spawn(
+        nonblocking_rpc_client.clone(),
+        wallet_kp.pubkey(),
+        None,
+        journal.clone(),
+        risk.clone(),
+    );
+
+    let context = Arc::new(WorkerContext {
+        config: config.clone(),
+        wallet: Keypair::from_bytes(&wallet_kp.to_bytes()).unwrap(),
+        quote_rpc_client: nonblocking_rpc_client.clone(),
+        sending_rpc_clients,
+        sending_rpc_labels,
+        cached_blockhash: cached_blockhash.clone(),
+        fetch_policy,
+        confirmation_tracker,
+        journal,
+        risk: risk.clone(),
+        tip_lamports,
+        jito: config
+            .jito
+            .as_ref()
+            .filter(|jito_config| jito_config.enabled)
+            .map(JitoSender::new),
+        bundles: Mutex::new(HashMap::new()),
+    });
+
+    // 一次性批量拉取所有 mint 用到的 ALT
+    let lookup_table_pubkeys = config
+        .routing
+        .mint_config_list
+        .iter()
+        .flat_map(lookup_table_pubkeys)
+        .collect::<Vec<_>>();
+    let lookup_table_data =
+        fetch_multiple_accounts(&nonblocking_rpc_client, &lookup_table_pubkeys, &fetch_policy).await?;
+
+    // 开启闪电贷时，启动时解析所有闪电贷来源，后台定时刷新各来源的可借数量
+    let base_mints = config
+        .routing
+        .mint_config_list
+        .iter()
+        .map(MintConfig::base_mint)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let flash_loan_provider_configs = provider_configs(&config, &base_mints)?;
+    let flash_loans = if flash_loan_provider_configs.is_empty() {
+        None
+    } else {
+        let providers = Arc::new(
+            load_providers(&nonblocking_rpc_client, &fetch_policy, &flash_loan_provider_configs)
+                .await?,
+        );
+        for base_mint in &base_mints {
+            if providers.select(base_mint, 0).is_none() {
+                warn!("No flash loan provider configured for base mint {}", base_mint);
+            }
+        }
+        supervisor.track(spawn_liquidity_refresher(
+            nonblocking_rpc_client.clone(),
+            providers.clone(),
+            fetch_policy,
+            None,
+        ));
+        Some(providers)
+    };
+
+    // 启动和热加载新 mint 时共用的参数
+    let mint_setup = MintSetup {
+        context: context.clone(),
+        stream_source,
+        array_window,
+        compute_budget,
+        flash_loans: flash_loans.clone(),
+    };
+
+    let mut running_mints: HashMap<String, RunningMint> = HashMap::new();
+
     for mint_config in &config.routing.mint_config_list{
+        let running = mint_setup
+            .start(mint_config, &lookup_table_data, &mut supervisor)
+            .await?;
+        running_mints.insert(mint_config.key(), running);
+    }
+    // 环路套利需要所有 mint 的池子数据和 ALT，热加载后按运行中的 mint 重建
+    let cycle_members: CycleMembers = Arc::new(RwLock::new(cycle_members_of(&running_mints)));
+
+    // 环路套利：所有 mint 的池子构成一张图，从 base token 出发寻找环路
+    if let Some(cycle_config) = config
+        .routing
+        .cycle
+        .as_ref()
+        .filter(|cycle_config| cycle_config.enabled)
+    {
+        let max_hops = cycle_config.max_hops.unwrap_or(DEFAULT_MAX_HOPS);
+        if max_hops > EXECUTABLE_MAX_HOPS {
+            warn!(
+                "Cycle max_hops {} is not supported by the executor yet, searching {}-hop cycles only",
+                max_hops, EXECUTABLE_MAX_HOPS
+            );
+        }
+        let params = cycle_params(&config, cycle_config)?;
+        let base_token_program = fetch_token_program(&nonblocking_rpc_client, &params.base_mint).await?;
+        let worker = Arc::new(CycleWorker {
+            context: context.clone(),
+            params,
+            base_token_program,
+            compute_budget: ComputeBudget::from_config(&config),
+            max_hops: max_hops.min(EXECUTABLE_MAX_HOPS),
+            process_delay: Duration::from_millis(cycle_config.process_delay),
+            // 环路没有单独的固定 CU 价格，按环路上池子的样本估计
+            priority_fee: config
+                .priority_fee
+                .as_ref()
+                .filter(|fee_config| fee_config.enabled)
+                .map(|fee_config| PriorityFeeEstimator::new(fee_config, None)),
+            flash_loans,
+            members: cycle_members.clone(),
+        });
+        supervisor.supervise("cycles", move |shutdown| worker.clone().run(shutdown));
+    }
+
+    // 收到 SIGHUP 或配置文件变化时热加载 mint_config_list，其余部分变化时只提示需要重启
+    let mut reload_trigger = ReloadTrigger::new(config_path, config.reload.as_ref())?;
+    let mut current_raw = raw_config;
+    // 收到 SIGINT / SIGTERM 后停止发送新交易，等待在途交易确认、journal 写完再退出
+    let signal = wait_for_signal();
+    tokio::pin!(signal);
+    let signal = loop {
+        tokio::select! {
+            signal = &mut signal => break signal?,
+            source = reload_trigger.wait() => {
+                info!("Reloading config after {}", source);
+                let loaded = match LoadedConfig::load(config_path) {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        error!("Keeping the current config: {}", e);
+                        continue;
+                    }
+                };
+                let changes = mint_setup
+                    .reload(&mut running_mints, &loaded.config.routing.mint_config_list, &mut supervisor)
+                    .await;
+                // 环路图随之加入新增的 mint、去掉删除的 mint，下一轮报价生效
+                if !changes.is_empty() {
+                    *cycle_members.write().await = cycle_members_of(&running_mints);
+                }
+                for section in non_reloadable_changes(&current_raw, &loaded.raw) {
+                    warn!("[{}] changed but is not reloadable, restart the bot to apply it", section);
+                }
+                current_raw = loaded.raw;
+            }
+        }
+    };
+    info!("Received {}, shutting down", signal);
+    let shutdown_timeout = supervisor.shutdown_timeout();
+    supervisor.stop().await;
+    // 后台 bundle 持有 context，结束后才能丢弃最后一份 ConfirmationTracker
+    context.drain_bundles(shutdown_timeout).await;
+
+    // 任务都已结束，丢弃最后一份 ConfirmationTracker 后 tracker 处理完在途签名即退出
+    drop(running_mints);
+    drop(mint_setup);
+    drop(context);
+    info!("Waiting for in-flight transactions to confirm");
+    if tokio::time::timeout(shutdown_timeout, &mut confirmation_handle).await.is_err() {
+        warn!("Timed out waiting for in-flight transactions, giving up on them");
+        confirmation_handle.abort();
+    }
+
+    // tracker 退出后 journal 的发送端全部被丢弃，写入任务写完剩余记录后结束
+    if let Some(journal_writer) = journal_writer {
+        if let Err(e) = journal_writer.await {
+            error!("Journal writer failed: {}", e);
+        }
+    }
+    if let Some(risk) = &risk {
+        info!("Risk status: {}", risk.status().await);
+    }
+    let _ = std::io::stdout().flush();
+    info!("Bot stopped");
+    Ok(())
+}
+
+// mint 任务和环路任务共用的状态，任务重启时直接复用
+struct WorkerContext {
+    config: Config,
+    wallet: Keypair,
+    quote_rpc_client: Arc<NonblockingRpcClient>,
+    sending_rpc_clients: Vec<Arc<RpcClient>>,
+    // 与 sending_rpc_clients 一一对应
+    sending_rpc_labels: Vec<String>,
+    cached_blockhash: Arc<Mutex<Hash>>,
+    fetch_policy: FetchPolicy,
+    confirmation_tracker: ConfirmationTracker,
+    journal: Option<Journal>,
+    risk: Option<RiskGuard>,
+    tip_lamports: Option<u64>,
+    jito: Option<JitoSender>,
+    // 后台发送中的 bundle，按 (mint, base mint) 索引；同一个 mint 同时只有一个，关闭时等待它们结束
+    bundles: Mutex<HashMap<(String, Pubkey), JoinHandle<()>>>,
+}
+
+impl WorkerContext {
+    // 风控触发时本轮不报价也不发送
+    async fn is_halted(&self) -> bool {
+        match &self.risk {
+            Some(risk) => risk.is_halted().await,
+            None => false,
+        }
+    }
+
+    fn rpc_label(&self, index: usize) -> String {
+        self.sending_rpc_labels
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| format!("rpc {}", index))
+    }
+
+    async fn bundle_in_flight(&self, mint: &str, base_mint: &Pubkey) -> bool {
+        self.bundles
+            .lock()
+            .await
+            .get(&(mint.to_string(), *base_mint))
+            .map_or(false, |handle| !handle.is_finished())
+    }
+
+    // 关闭时等待后台的 bundle 拿到结果并交给确认跟踪，超时后终止
+    async fn drain_bundles(&self, timeout: Duration) {
+        let bundles: Vec<JoinHandle<()>> =
+            self.bundles.lock().await.drain().map(|(_, handle)| handle).collect();
+        if bundles.is_empty() {
+            return;
+        }
+        info!("Waiting for {} in-flight bundles", bundles.len());
+        let deadline = tokio::time::Instant::now() + timeout;
+        for mut handle in bundles {
+            if tokio::time::timeout_at(deadline, &mut handle).await.is_err() {
+                warn!("Bundle did not finish in time, aborting");
+                handle.abort();
+            }
+        }
+    }
+
+    fn simulation_enabled(&self) -> bool {
+        self.config
+            .simulation
+            .as_ref()
+            .map_or(false, |simulation_config| simulation_config.enabled)
+    }
+
+    // 构建交易；开启模拟时先以最大 CU limit 模拟，失败或余额没有增加的交易直接丢弃，
+    // 通过后按模拟消耗重新设置 CU limit。发送后按签名写入 journal 并交给确认跟踪，返回是否发送了交易
+    // 开启 Jito 时在后台发送 bundle 并等待结果，不阻塞下一轮报价；该 mint 上一个 bundle 还没有结果时跳过
+    async fn submit(
+        self: &Arc<Self>,
+        mut params: TransactionParams<'_>,
+        compute_budget: &ComputeBudget,
+        attempt: &SentEntry,
+        base_mint: &Pubkey,
+        blockhash: Hash,
+    ) -> anyhow::Result<bool> {
+        if self.jito.is_some() && self.bundle_in_flight(&attempt.mint, base_mint).await {
+            debug!("Bundle for mint {} is still in flight, skipping", attempt.mint);
+            return Ok(false);
+        }
+        let simulation = if self.simulation_enabled() {
+            params.compute_unit_limit = MAX_COMPUTE_UNIT_LIMIT;
+            let transaction = build_transaction(&self.wallet, &params, blockhash)?;
+            let report = simulation_gate(
+                &self.quote_rpc_client,
+                &transaction,
+                &self.wallet.pubkey(),
+                base_mint,
+                &params.base_token_program,
+            )
+            .await?;
+            if !report.passed() {
+                return Ok(false);
+            }
+            Some(report)
+        } else {
+            None
+        };
+        params.compute_unit_limit = compute_budget.limit(simulation.as_ref(), &params.dexes);
+        let transaction = build_transaction(&self.wallet, &params, blockhash)?;
+        let attempt = SentEntry {
+            compute_unit_limit: Some(params.compute_unit_limit),
+            compute_unit_price: Some(params.compute_unit_price),
+            ..attempt.clone()
+        };
+
+        if self.jito.is_some() {
+            let context = self.clone();
+            let key = (attempt.mint.clone(), *base_mint);
+            let base_mint = *base_mint;
+            let handle = tokio::spawn(async move {
+                context.send_bundle(transaction, attempt, base_mint, blockhash).await
+            });
+            let mut bundles = self.bundles.lock().await;
+            bundles.retain(|_, handle| !handle.is_finished());
+            bundles.insert(key, handle);
+            return Ok(true);
+        }
+
+        let (signature, accepted) =
+            send_transaction(&self.config, &transaction, &self.sending_rpc_clients).await?;
+        let rpcs: Vec<String> = accepted.into_iter().map(|index| self.rpc_label(index)).collect();
+        info!("  Signature: {} via {}", signature, rpcs.join(", "));
+        if let Some(journal) = &self.journal {
+            journal.record(JournalEntry::Sent(SentEntry {
+                timestamp: unix_timestamp(),
+                signature: signature.to_string(),
+                rpc: rpcs.join(","),
+                ..attempt.clone()
+            }));
+        }
+        self.confirmation_tracker
+            .track(SentTransaction {
+                signature,
+                rpcs,
+                route: attempt.route.clone(),
+                mint: attempt.mint.clone(),
+                base_mint: *base_mint,
+                expected_profit: attempt.expected_profit,
+                blockhash,
+            })
+            .await;
+        Ok(true)
+    }
+
+    // 通过 Jito 发送 bundle 并等待 block engine 的结果，结果写入 journal 并交给确认跟踪
+    async fn send_bundle(
+        &self,
+        transaction: VersionedTransaction,
+        attempt: SentEntry,
+        base_mint: Pubkey,
+        blockhash: Hash,
+    ) {
+        let Some(jito) = &self.jito else {
+            return;
+        };
+        let signature = transaction.signatures[0];
+        let tip = attempt.tip.unwrap_or_default();
+        let outcome = match jito.send_and_wait(&self.wallet, transaction, tip, blockhash).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                error!("Error sending bundle for mint {}: {}", attempt.mint, e);
+                return;
+            }
+        };
+        info!("  Signature: {} (bundle {})", signature, outcome);
+        if let Some(journal) = &self.journal {
+            journal.record(JournalEntry::Sent(SentEntry {
+                timestamp: unix_timestamp(),
+                signature: signature.to_string(),
+                rpc: JITO_LABEL.to_string(),
+                ..attempt.clone()
+            }));
+        }
+        self.confirmation_tracker
+            .track_bundle(
+                SentTransaction {
+                    signature,
+                    rpcs: vec![JITO_LABEL.to_string()],
+                    route: attempt.route,
+                    mint: attempt.mint,
+                    base_mint,
+                    expected_profit: attempt.expected_profit,
+                    blockhash,
+                },
+                &outcome,
+            )
+            .await;
+    }
+}
+
+// 启动 mint 和热加载时共用的参数
+struct MintSetup {
+    context: Arc<WorkerContext>,
+    stream_source: Option<Arc<dyn AccountUpdateSource>>,
+    array_window: ArrayWindow,
+    compute_budget: ComputeBudget,
+    flash_loans: Option<Arc<FlashLoanProviders>>,
+}
+
+impl MintSetup {
+    // 初始化池子、启动该 mint 的后台任务和报价任务
+    async fn start(
+        &self,
+        mint_config: &MintConfig,
+        lookup_table_data: &HashMap<Pubkey, Account>,
+        supervisor: &mut Supervisor,
+    ) -> anyhow::Result<RunningMint> {
+        let context = &self.context;
+        let config = &context.config;
         info!("Processing mint: {:?}", mint_config.mint);
 
         // 初始化代币池子信息
-        let pool_data = initialize_pool_data(
+        let (pool_data, snapshot) = initialize_pool_data(
             &mint_config.mint,
-            &wallet_kp.pubkey().to_string(),
-            mint_config.raydium_pool_list.as_ref(),
-            mint_config.raydium_cp_pool_list.as_ref(),
-            mint_config.pump_pool_list.as_ref(),
-            mint_config.meteora_dlmm_pool_list.as_ref(),
-            mint_config.whirlpool_pool_list.as_ref(),
-            mint_config.raydium_clmm_pool_list.as_ref(),
-            rpc_client.clone(),
+            &context.wallet.pubkey().to_string(),
+            mint_config,
+            &context.quote_rpc_client,
+            &context.fetch_policy,
+            &self.array_window,
         )
         .await?;
+        let mut background = Vec::new();
+
+        // 订阅池子、vault、tick array、bin array 等报价用到的全部账户
+        // array 窗口移动或热加载更新池子后通过 subscriptions 通知 stream 重新订阅
+        let (live_snapshot, subscriptions) = match &self.stream_source {
+            Some(source) => {
+                let (subscription_sender, subscription_receiver) =
+                    watch::channel(collect_pool_accounts(&pool_data));
+                let live_snapshot = Arc::new(RwLock::new(snapshot));
+                background.push(supervisor.track(spawn_account_stream(
+                    source.clone(),
+                    live_snapshot.clone(),
+                    subscription_receiver,
+                    config
+                        .stream
+                        .as_ref()
+                        .and_then(|stream_config| stream_config.reconnect_delay_ms)
+                        .map(Duration::from_millis),
+                )));
+                (Some(live_snapshot), Some(Arc::new(subscription_sender)))
+            }
+            None => (None, None),
+        };
 
         // 将池子数据包装成Arc和Mutex
         let mint_pool_data = Arc::new(Mutex::new(pool_data));
-        
-        let config_clone = config.clone();
-        let mint_config_clone = mint_config.clone();
-        let sending_rpc_clients_clone = sending_rpc_clients.clone();
-        let cache_blockhash_clone = cached_blockhash.clone();
-        let wallet_bytes = wallet_kp.to_bytes();
-        let wallet_kp_clone = Keypair::from_bytes(&wallet_bytes).unwrap();
-
-        let mut lookup_table_accounts = mint_config_clone.lookup_table_accounts.unwrap_or_default();
-
-        // 添加ALT地址
-        lookup_table_accounts.push("CCXTHFHXar7fTEQWrcC7iAWcJSJRbYBjwgtGxBKSz6rt".to_string());
-        
-        // 将ALT中存储的的地址反序列化出来后，添加到lookup_table_accounts_list中
-        let mut lookup_table_accounts_list = vec![];
-
-        for lookup_table_account in lookup_table_accounts {
-            match Pubkey::from_str(&lookup_table_account) {
-                Ok(pubkey) => {
-                    match rpc_client.get_account(&pubkey) {
-                        Ok(account) => {
-                            match AddressLookupTable::deserialize(&account.data) {
-                                Ok(lookup_table) => {
-                                    let lookup_table_account = AddressLookupTableAccount {
-                                        key: pubkey,
-                                        addresses: lookup_table.addresses.into_owned(),
-                                    };
-                                    lookup_table_accounts_list.push(lookup_table_account);
-                                    info!("   Successfully loaded lookup table: {}", pubkey);
+
+        // 价格跨过 tick array / bin array 边界时，后台替换池子数据中的 array
+        background.push(supervisor.track(spawn_array_window_refresher(
+            context.quote_rpc_client.clone(),
+            mint_pool_data.clone(),
+            live_snapshot.clone(),
+            subscriptions.clone(),
+            self.array_window,
+            context.fetch_policy,
+        )));
+
+        // 按该 mint 池子的可写账户在后台刷新优先费样本
+        let priority_fee_cache = match config.priority_fee.as_ref().filter(|fee_config| fee_config.enabled) {
+            Some(fee_config) => {
+                let cache = Arc::new(RwLock::new(Default::default()));
+                background.push(supervisor.track(spawn_priority_fee_refresher(
+                    context.quote_rpc_client.clone(),
+                    mint_pool_data.clone(),
+                    cache.clone(),
+                    fee_config.refresh_interval_ms.map(Duration::from_millis),
+                    supervisor.shutdown(),
+                )));
+                Some(cache)
+            }
+            None => None,
+        };
+
+        let running = RunningMint {
+            mint_config: mint_config.clone(),
+            base_mint: mint_config.base_mint()?,
+            pool_data: mint_pool_data,
+            live_snapshot,
+            subscriptions,
+            priority_fee_cache,
+            lookup_table_accounts_list: load_lookup_tables(mint_config, lookup_table_data),
+            background,
+        };
+
+        // 创建一个异步任务用于后台处理交易，失败或 panic 后由 supervisor 重启
+        match self.worker(&running, mint_config, running.lookup_table_accounts_list.clone()) {
+            Ok(worker) => {
+                supervisor.supervise_stoppable(running.worker_name(), move |shutdown| {
+                    worker.clone().run(shutdown)
+                });
+                Ok(running)
+            }
+            Err(e) => {
+                running.abort_background();
+                Err(e)
+            }
+        }
+    }
+
+    fn worker(
+        &self,
+        running: &RunningMint,
+        mint_config: &MintConfig,
+        lookup_table_accounts_list: Vec<AddressLookupTableAccount>,
+    ) -> anyhow::Result<Arc<MintWorker>> {
+        let config = &self.context.config;
+        // 报价参数按 mint 的 base token 解析
+        let quote_params = match &config.quote {
+            Some(quote_config) if quote_config.enabled => {
+                Some(QuoteParams::new(config, quote_config, mint_config)?)
+            }
+            _ => None,
+        };
+        let priority_fee = match (
+            config.priority_fee.as_ref().filter(|fee_config| fee_config.enabled),
+            &running.priority_fee_cache,
+        ) {
+            (Some(fee_config), Some(cache)) => Some((
+                PriorityFeeEstimator::new(fee_config, mint_config.compute_unit_price),
+                cache.clone(),
+            )),
+            _ => None,
+        };
+        Ok(Arc::new(MintWorker {
+            context: self.context.clone(),
+            mint_config: mint_config.clone(),
+            base_mint: running.base_mint,
+            quote_params,
+            compute_budget: self.compute_budget.clone(),
+            pool_data: running.pool_data.clone(),
+            live_snapshot: running.live_snapshot.clone(),
+            priority_fee,
+            flash_loans: self.flash_loans.clone(),
+            lookup_table_accounts_list,
+        }))
+    }
+
+    // 按新的 mint_config_list 启动新增的 mint、停止删除的 mint、更新有变化的 mint
+    // 单个 mint 失败时保留它原来的状态，下次热加载时重试
+    async fn reload(
+        &self,
+        running_mints: &mut HashMap<String, RunningMint>,
+        mint_config_list: &[MintConfig],
+        supervisor: &mut Supervisor,
+    ) -> RoutingChanges {
+        let current: Vec<MintConfig> = running_mints
+            .values()
+            .map(|running| running.mint_config.clone())
+            .collect();
+        let changes = RoutingChanges::diff(&current, mint_config_list);
+        if changes.is_empty() {
+            info!("mint_config_list is unchanged");
+            return changes;
+        }
+
+        for mint_config in &changes.removed {
+            if let Some(running) = running_mints.remove(&mint_config.key()) {
+                running.stop(supervisor).await;
+                info!("Stopped mint {}", mint_config.key());
+            }
+        }
+
+        let lookup_table_pubkeys = changes
+            .added
+            .iter()
+            .chain(&changes.changed)
+            .flat_map(lookup_table_pubkeys)
+            .collect::<Vec<_>>();
+        let lookup_table_data = match fetch_multiple_accounts(
+            &self.context.quote_rpc_client,
+            &lookup_table_pubkeys,
+            &self.context.fetch_policy,
+        )
+        .await
+        {
+            Ok(lookup_table_data) => lookup_table_data,
+            Err(e) => {
+                error!("Failed to fetch lookup tables, skipping added and changed mints: {}", e);
+                return changes;
+            }
+        };
+
+        for mint_config in &changes.added {
+            match self.start(mint_config, &lookup_table_data, supervisor).await {
+                Ok(running) => {
+                    info!("Started mint {}", mint_config.key());
+                    running_mints.insert(mint_config.key(), running);
+                }
+                Err(e) => error!("Failed to start mint {}: {}", mint_config.key(), e),
+            }
+        }
+        for mint_config in &changes.changed {
+            let Some(running) = running_mints.get_mut(&mint_config.key()) else {
+                continue;
+            };
+            match self.update(running, mint_config, &lookup_table_data, supervisor).await {
+                Ok(()) => info!("Updated mint {}", mint_config.key()),
+                Err(e) => error!("Failed to update mint {}: {}", mint_config.key(), e),
+            }
+        }
+        changes
+    }
+
+    // 池子列表变化时只初始化新增的池子；其他字段变化时用新配置重启报价任务，池子数据和订阅保持不变
+    async fn update(
+        &self,
+        running: &mut RunningMint,
+        mint_config: &MintConfig,
+        lookup_table_data: &HashMap<Pubkey, Account>,
+        supervisor: &mut Supervisor,
+    ) -> anyhow::Result<()> {
+        let context = &self.context;
+        let worker = if settings_changed(&running.mint_config, mint_config) {
+            let lookup_table_accounts_list = load_lookup_tables(mint_config, lookup_table_data);
+            Some(self.worker(running, mint_config, lookup_table_accounts_list)?)
+        } else {
+            None
+        };
+
+        if pools_changed(&running.mint_config, mint_config) {
+            let current = running.pool_data.lock().await.clone();
+            let (pool_data, snapshot) = update_pool_data(
+                &current,
+                &running.mint_config,
+                mint_config,
+                &context.quote_rpc_client,
+                &context.fetch_policy,
+                &self.array_window,
+            )
+            .await?;
+            // 与 array 窗口移动相同：先补齐快照，再替换池子数据，最后重新订阅
+            let accounts = collect_pool_accounts(&pool_data);
+            if let Some(live_snapshot) = &running.live_snapshot {
+                live_snapshot.write().await.merge(snapshot);
+            }
+            *running.pool_data.lock().await = pool_data;
+            if let Some(subscriptions) = &running.subscriptions {
+                let _ = subscriptions.send(accounts);
+            }
+        }
+
+        if let Some(worker) = worker {
+            supervisor.stop_task(&running.worker_name()).await;
+            running.lookup_table_accounts_list = worker.lookup_table_accounts_list.clone();
+            supervisor.supervise_stoppable(running.worker_name(), move |shutdown| {
+                worker.clone().run(shutdown)
+            });
+        }
+        running.mint_config = mint_config.clone();
+        Ok(())
+    }
+}
+
+// 运行中的 mint，热加载时据此停止任务或原地更新池子
+struct RunningMint {
+    mint_config: MintConfig,
+    base_mint: Pubkey,
+    pool_data: Arc<Mutex<MintPoolData>>,
+    live_snapshot: Option<LiveSnapshot>,
+    subscriptions: Option<Arc<watch::Sender<Vec<Pubkey>>>>,
+    priority_fee_cache: Option<PriorityFeeCache>,
+    lookup_table_accounts_list: Vec<AddressLookupTableAccount>,
+    // 账户订阅、array 窗口和优先费刷新任务
+    background: Vec<AbortHandle>,
+}
+
+impl RunningMint {
+    fn worker_name(&self) -> String {
+        format!("mint {}", self.mint_config.key())
+    }
+
+    fn cycle_member(&self) -> CycleMember {
+        CycleMember {
+            base_mint: self.base_mint,
+            pool_data: self.pool_data.clone(),
+            lookup_table_accounts_list: self.lookup_table_accounts_list.clone(),
+            priority_fee_cache: self.priority_fee_cache.clone(),
+        }
+    }
+
+    fn abort_background(&self) {
+        for handle in &self.background {
+            handle.abort();
+        }
+    }
+
+    async fn stop(self, supervisor: &mut Supervisor) {
+        supervisor.stop_task(&self.worker_name()).await;
+        self.abort_background();
+    }
+}
+
+// 单个 mint 的报价和发送循环
+struct MintWorker {
+    context: Arc<WorkerContext>,
+    mint_config: MintConfig,
+    base_mint: Pubkey,
+    quote_params: Option<QuoteParams>,
+    compute_budget: ComputeBudget,
+    pool_data: Arc<Mutex<MintPoolData>>,
+    live_snapshot: Option<LiveSnapshot>,
+    priority_fee: Option<(PriorityFeeEstimator, PriorityFeeCache)>,
+    // 开启闪电贷时整笔投入从能覆盖规模且手续费最低的来源借入
+    flash_loans: Option<Arc<FlashLoanProviders>>,
+    lookup_table_accounts_list: Vec<AddressLookupTableAccount>,
+}
+
+impl MintWorker {
+    async fn run(self: Arc<Self>, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        let context = &self.context;
+        // 从设置中获取定义的毫秒级别的时间间隔
+        let process_delay = Duration::from_millis(self.mint_config.process_delay);
+        // 钱包余额决定交易规模上限，启动时和每次发送交易后重新获取
+        let mut wallet_balance = 0;
+        let mut balance_stale = true;
+
+        // 每轮开始前检查关闭信号，已经在发送的交易照常完成
+        while !shutdown.is_triggered() {
+            if context.is_halted().await {
+                shutdown.sleep(process_delay).await;
+                continue;
+            }
+
+            if let Some(params) = &self.quote_params {
+                if balance_stale {
+                    let base_token_program = self.pool_data.lock().await.base_token_program;
+                    let balance = fetch_base_balance(
+                        &context.quote_rpc_client,
+                        &context.wallet.pubkey(),
+                        &params.base_mint,
+                        &base_token_program,
+                    )
+                    .await;
+                    match balance {
+                        Ok(balance) => {
+                            wallet_balance = balance;
+                            balance_stale = false;
+                        }
+                        Err(e) => error!(
+                            "Error fetching wallet balance for mint {}: {}",
+                            self.mint_config.mint, e
+                        ),
+                    }
+                }
+            }
+
+            // 获取最新的blockhash
+            let latest_blockhash = {
+                let guard = context.cached_blockhash.lock().await;
+                *guard
+            };
+
+            // 获取池子数据；复制一份后立即释放锁，报价期间的 RPC 请求不阻塞 array 窗口和热加载
+            let pool_data = self.pool_data.lock().await.clone();
+
+            // 开启报价时，只把净利润达到阈值的路径交给交易构建
+            let route_pool_data;
+            let mut flash_loan = None;
+            // 本次尝试的信息，发送后按签名写入 journal
+            let mut attempt = SentEntry {
+                timestamp: 0,
+                signature: String::new(),
+                mint: self.mint_config.mint.clone(),
+                route: format!("mint {}", self.mint_config.mint),
+                rpc: String::new(),
+                amount_in: None,
+                expected_profit: None,
+                tip: context.tip_lamports,
+                compute_unit_limit: None,
+                compute_unit_price: None,
+                slot: None,
+            };
+            let pool_data_to_send = match &self.quote_params {
+                Some(params) => {
+                    let sizing = params.sizing(wallet_balance);
+                    let quoted = match &self.live_snapshot {
+                        Some(snapshot) => {
+                            Ok(profitable_route(&pool_data, &*snapshot.read().await, params, &sizing))
+                        }
+                        None => {
+                            find_profitable_route(
+                                &context.quote_rpc_client,
+                                &pool_data,
+                                &context.fetch_policy,
+                                params,
+                                &sizing,
+                            )
+                            .await
+                        }
+                    };
+                    match quoted {
+                        Ok(Some(mut route)) => {
+                            info!(
+                                "Profitable route for mint {} at slot {}: size {} ({}), expected profit {}: {}",
+                                self.mint_config.mint,
+                                route.slot,
+                                route.buy.amount_in,
+                                route.sizing,
+                                route.net_profit,
+                                route
+                            );
+                            let compute_unit_limit = self.compute_budget.default_limit(&route.dexes());
+                            debug!("Default compute unit limit for route: {}", compute_unit_limit);
+                            let compute_unit_price = match &self.priority_fee {
+                                Some((estimator, cache)) => {
+                                    let samples = route_fee_samples(
+                                        &*cache.read().await,
+                                        &[route.buy.pool, route.sell.pool],
+                                    );
+                                    // 估计器按 lamports 计算利润比例
+                                    let compute_unit_price = estimator.compute_unit_price(
+                                        &samples,
+                                        base_in_lamports(route.net_profit, &self.base_mint, params.base_per_sol),
+                                        compute_unit_limit,
+                                    );
+                                    info!(
+                                        "Priority fee for mint {}: {} micro-lamports per CU",
+                                        self.mint_config.mint, compute_unit_price
+                                    );
+                                    compute_unit_price
+                                }
+                                None => self
+                                    .mint_config
+                                    .compute_unit_price
+                                    .unwrap_or_else(|| static_compute_unit_price(&context.config)),
+                            };
+                            attempt.compute_unit_price = Some(compute_unit_price);
+                            // 报价按固定 CU 价格和固定小费计入成本，换成本次的价格和小费后重新检查利润
+                            if let Some(tip) = params.jito_tip(&context.config, Some(route.net_profit)) {
+                                attempt.tip = Some(tip);
+                            }
+                            route.net_profit = match reprice(&context.config, params, compute_unit_price, route.net_profit) {
+                                Ok(Some(net_profit)) => net_profit,
+                                Ok(None) => {
+                                    shutdown.sleep(process_delay).await;
+                                    continue;
                                 }
                                 Err(e) => {
-                                    error!(
-                                        "   Failed to deserialize lookup table {}: {}",
-                                        pubkey, e
-                                    );
-                                    continue; // Skip this lookup table but continue processing others
+                                    error!("Error pricing route for mint {}: {}", self.mint_config.mint, e);
+                                    shutdown.sleep(process_delay).await;
+                                    continue;
                                 }
+                            };
+                            if let Some(journal) = &context.journal {
+                                journal.record(JournalEntry::Opportunity(OpportunityEntry {
+                                    timestamp: unix_timestamp(),
+                                    mint: self.mint_config.mint.clone(),
+                                    base_mint: self.base_mint.to_string(),
+                                    route: route.label(),
+                                    pools: vec![route.buy.pool.to_string(), route.sell.pool.to_string()],
+                                    dexes: route.dexes().iter().map(|dex| dex.to_string()).collect(),
+                                    amount_in: route.buy.amount_in,
+                                    expected_profit: route.net_profit,
+                                    slot: route.slot,
+                                }));
                             }
+                            attempt.route = route.label();
+                            attempt.amount_in = Some(route.buy.amount_in);
+                            attempt.expected_profit = Some(route.net_profit);
+                            attempt.compute_unit_limit = Some(compute_unit_limit);
+                            attempt.slot = Some(route.slot);
+                            flash_loan = match select_flash_loan(
+                                self.flash_loans.as_deref(),
+                                &self.base_mint,
+                                route.buy.amount_in,
+                            ) {
+                                Ok(flash_loan) => flash_loan,
+                                Err(e) => {
+                                    warn!("{}", e);
+                                    shutdown.sleep(process_delay).await;
+                                    continue;
+                                }
+                            };
+                            route_pool_data = route.restrict(&pool_data);
+                            &route_pool_data
+                        }
+                        Ok(None) => {
+                            shutdown.sleep(process_delay).await;
+                            continue;
                         }
                         Err(e) => {
-                            error!("   Failed to fetch lookup table account {}: {}", pubkey, e);
-                            continue; // Skip this lookup table but continue processing others
+                            error!("Error quoting routes for mint {}: {}", self.mint_config.mint, e);
+                            shutdown.sleep(process_delay).await;
+                            continue;
                         }
                     }
                 }
+                None => &pool_data,
+            };
+
+            // 构建交易，模拟通过后发送
+            let mut params = TransactionParams::new(
+                &context.config,
+                &self.compute_budget,
+                self.base_mint,
+                pool_data_to_send.base_token_program,
+                route_accounts(pool_data_to_send),
+                pool_data_to_send.pools.iter().map(|keys| keys.dex).collect(),
+                &self.lookup_table_accounts_list,
+            );
+            if let Some(compute_unit_price) = attempt.compute_unit_price {
+                params.compute_unit_price = compute_unit_price;
+            }
+            params.flash_loan = flash_loan;
+            match context
+                .submit(params, &self.compute_budget, &attempt, &self.base_mint, latest_blockhash)
+                .await
+            {
+                Ok(true) => {
+                    balance_stale = true;
+                    info!(
+                        "Transactions sent successfully for mint {}",
+                        self.mint_config.mint
+                    );
+                }
+                Ok(false) => {}
                 Err(e) => {
                     error!(
-                        "   Invalid lookup table pubkey string {}: {}",
-                        lookup_table_account, e
+                        "Error sending transaction for mint {}: {}",
+                        self.mint_config.mint, e
                     );
-                    continue; // Skip this lookup table but continue processing others
                 }
             }
+
+            shutdown.sleep(process_delay).await;
         }
-        if lookup_table_accounts_list.is_empty() {
-            warn!("   Warning: No valid lookup tables were loaded");
-        } else {
-            info!(
-                "   Loaded {} lookup tables successfully",
-                lookup_table_accounts_list.len()
-            );
-        }
+        Ok(())
+    }
+}
 
-        // 创建一个异步任务用于后台处理交易
-        tokio::spawn(async move {
-            // 从设置中获取定义的毫秒级别的时间间隔
-            let process_delay = Duration::from_millis(mint_config_clone.process_delay);
+// 跨 mint 环路的报价和发送循环
+struct CycleWorker {
+    context: Arc<WorkerContext>,
+    params: QuoteParams,
+    base_token_program: Pubkey,
+    compute_budget: ComputeBudget,
+    max_hops: usize,
+    process_delay: Duration,
+    flash_loans: Option<Arc<FlashLoanProviders>>,
+    priority_fee: Option<PriorityFeeEstimator>,
+    members: CycleMembers,
+}
 
-            loop {
-                // 获取最新的blockhash
-                let latest_blockhash = {
-                    let guard = cached_blockhash_clone.lock().await;
-                    *guard
-                };
+// 环路图中的一个 mint：base token、池子数据、ALT 和该 mint 池子的优先费样本
+#[derive(Clone)]
+struct CycleMember {
+    base_mint: Pubkey,
+    pool_data: Arc<Mutex<MintPoolData>>,
+    lookup_table_accounts_list: Vec<AddressLookupTableAccount>,
+    priority_fee_cache: Option<PriorityFeeCache>,
+}
 
-                // 获取池子数据
-                let guard = mint_pool_data.lock().await;
+// 环路图的全部 mint，热加载时整体替换
+type CycleMembers = Arc<RwLock<Vec<CycleMember>>>;
 
-                // 构建并发送交易
-                match build_and_send_transaction(
-                    &wallet_kp_clone,
-                    &config_clone,
-                    &*guard, // Dereference the guard here
-                    &sending_rpc_clients_clone,
-                    latest_blockhash,
-                    &lookup_table_accounts_list,
+fn cycle_members_of(running_mints: &HashMap<String, RunningMint>) -> Vec<CycleMember> {
+    running_mints.values().map(RunningMint::cycle_member).collect()
+}
+
+impl CycleWorker {
+    async fn run(self: Arc<Self>, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        let context = &self.context;
+        let params = &self.params;
+        let process_delay = self.process_delay;
+        let mut wallet_balance = 0;
+        let mut balance_stale = true;
+
+        while !shutdown.is_triggered() {
+            if context.is_halted().await {
+                shutdown.sleep(process_delay).await;
+                continue;
+            }
+
+            if balance_stale {
+                let balance = fetch_base_balance(
+                    &context.quote_rpc_client,
+                    &context.wallet.pubkey(),
+                    &params.base_mint,
+                    &self.base_token_program,
                 )
-                .await
-                {
-                    Ok(signatures) => {
-                        info!(
-                            "Transactions sent successfully for mint {}",
-                            mint_config_clone.mint
-                        );
-                        for signature in signatures {
-                            info!("  Signature: {}", signature);
-                        }
+                .await;
+                match balance {
+                    Ok(balance) => {
+                        wallet_balance = balance;
+                        balance_stale = false;
                     }
-                    Err(e) => {
-                        error!(
-                            "Error sending transaction for mint {}: {}",
-                            mint_config_clone.mint, e
-                        );
+                    Err(e) => error!("Error fetching wallet balance for cycles: {}", e),
+                }
+            }
+
+            // 本轮使用的 mint，热加载替换列表不影响进行中的一轮
+            let members = self.members.read().await.clone();
+            let mut pool_data_list = Vec::with_capacity(members.len());
+            for member in &members {
+                pool_data_list.push((member.base_mint, member.pool_data.lock().await.clone()));
+            }
+            let graph = RouteGraph::new(
+                pool_data_list
+                    .iter()
+                    .map(|(base_mint, pool_data)| (*base_mint, pool_data)),
+            );
+
+            let cycle = match find_profitable_cycle(
+                &context.quote_rpc_client,
+                &graph,
+                self.max_hops,
+                &context.fetch_policy,
+                params,
+                &params.sizing(wallet_balance),
+            )
+            .await
+            {
+                Ok(Some(cycle)) => cycle,
+                Ok(None) => {
+                    shutdown.sleep(process_delay).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("Error quoting cycles: {}", e);
+                    shutdown.sleep(process_delay).await;
+                    continue;
+                }
+            };
+            info!(
+                "Profitable {}-hop cycle at slot {}: size {} ({}), expected profit {}: {}",
+                cycle.hops.len(),
+                cycle.slot,
+                cycle.amount_in(),
+                cycle.sizing,
+                cycle.net_profit,
+                cycle
+            );
+            let cycle_mint = cycle
+                .single_mint()
+                .map_or_else(|| params.base_mint.to_string(), |mint| mint.to_string());
+            if let Some(journal) = &context.journal {
+                journal.record(JournalEntry::Opportunity(OpportunityEntry {
+                    timestamp: unix_timestamp(),
+                    mint: cycle_mint.clone(),
+                    base_mint: params.base_mint.to_string(),
+                    route: cycle.label(),
+                    pools: cycle.pools().iter().map(|pool| pool.to_string()).collect(),
+                    dexes: cycle.dexes().iter().map(|dex| dex.to_string()).collect(),
+                    amount_in: cycle.amount_in(),
+                    expected_profit: cycle.net_profit,
+                    slot: cycle.slot,
+                }));
+            }
+
+            // 两跳环路按单 mint 的账户排列构建；ALT 取自环路中每个池子所属的 mint
+            let pools = cycle.pools();
+            let mut lookup_table_accounts = Vec::new();
+            let mut route_accounts_list = None;
+            let mut fee_samples = Vec::new();
+            for (member, (_, pool_data)) in members.iter().zip(&pool_data_list) {
+                if !pool_data.pools.iter().any(|keys| pools.contains(&keys.pool)) {
+                    continue;
+                }
+                if member.base_mint == params.base_mint && cycle.single_mint() == Some(pool_data.mint) {
+                    route_accounts_list = Some(route_accounts(&cycle.restrict(pool_data)));
+                }
+                if let Some(cache) = &member.priority_fee_cache {
+                    fee_samples.push(route_fee_samples(&*cache.read().await, &pools));
+                }
+                for lookup_table in &member.lookup_table_accounts_list {
+                    if !lookup_table_accounts
+                        .iter()
+                        .any(|existing: &AddressLookupTableAccount| existing.key == lookup_table.key)
+                    {
+                        lookup_table_accounts.push(lookup_table.clone());
                     }
                 }
+            }
+            let Some(route_accounts_list) = route_accounts_list else {
+                debug!("Cycle {} has no single-mint layout the executor accepts, skipping", cycle.label());
+                shutdown.sleep(process_delay).await;
+                continue;
+            };
 
-                tokio::time::sleep(process_delay).await;
+            // 合并环路上各池子的优先费样本，与 MintWorker 一样估计 CU 价格
+            let compute_unit_price = match &self.priority_fee {
+                Some(estimator) => {
+                    let compute_unit_price = estimator.compute_unit_price(
+                        &FeeSamples::merge(&fee_samples),
+                        base_in_lamports(cycle.net_profit, &params.base_mint, params.base_per_sol),
+                        self.compute_budget.default_limit(&cycle.dexes()),
+                    );
+                    info!("Priority fee for cycle: {} micro-lamports per CU", compute_unit_price);
+                    compute_unit_price
+                }
+                None => static_compute_unit_price(&context.config),
+            };
+            let tip = params
+                .jito_tip(&context.config, Some(cycle.net_profit))
+                .or(context.tip_lamports);
+            let net_profit = match reprice(&context.config, params, compute_unit_price, cycle.net_profit) {
+                Ok(Some(net_profit)) => net_profit,
+                Ok(None) => {
+                    shutdown.sleep(process_delay).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!("Error pricing cycle: {}", e);
+                    shutdown.sleep(process_delay).await;
+                    continue;
+                }
+            };
+
+            let latest_blockhash = *context.cached_blockhash.lock().await;
+            let attempt = SentEntry {
+                timestamp: 0,
+                signature: String::new(),
+                mint: cycle_mint.clone(),
+                route: cycle.label(),
+                rpc: String::new(),
+                amount_in: Some(cycle.amount_in()),
+                expected_profit: Some(net_profit),
+                tip,
+                compute_unit_limit: None,
+                compute_unit_price: Some(compute_unit_price),
+                slot: Some(cycle.slot),
+            };
+            let flash_loan = match select_flash_loan(
+                self.flash_loans.as_deref(),
+                &params.base_mint,
+                cycle.amount_in(),
+            ) {
+                Ok(flash_loan) => flash_loan,
+                Err(e) => {
+                    warn!("{}", e);
+                    shutdown.sleep(process_delay).await;
+                    continue;
+                }
+            };
+            let mut transaction_params = TransactionParams::new(
+                &context.config,
+                &self.compute_budget,
+                params.base_mint,
+                self.base_token_program,
+                route_accounts_list,
+                cycle.dexes(),
+                &lookup_table_accounts,
+            );
+            transaction_params.compute_unit_price = compute_unit_price;
+            transaction_params.flash_loan = flash_loan;
+            match context
+                .submit(
+                    transaction_params,
+                    &self.compute_budget,
+                    &attempt,
+                    &params.base_mint,
+                    latest_blockhash,
+                )
+                .await
+            {
+                Ok(true) => {
+                    balance_stale = true;
+                    info!("Cycle transactions sent successfully");
+                }
+                Ok(false) => {}
+                Err(e) => error!("Error sending cycle transaction: {}", e),
             }
-        });
+
+            shutdown.sleep(process_delay).await;
+        }
+        Ok(())
+    }
+}
+
+// 开启闪电贷时报价按可借数量定规模，整笔投入从能覆盖规模且手续费最低的来源借入；
+// 没有来源能借出时返回错误，调用方放弃本次路径
+fn select_flash_loan(
+    flash_loans: Option<&FlashLoanProviders>,
+    base_mint: &Pubkey,
+    amount: u64,
+) -> anyhow::Result<Option<(Arc<dyn FlashLoanProvider>, u64)>> {
+    let Some(flash_loans) = flash_loans else {
+        return Ok(None);
+    };
+    let provider = flash_loans.select(base_mint, amount).ok_or_else(|| {
+        anyhow::anyhow!("No flash loan provider can lend {} of {}", amount, base_mint)
+    })?;
+    debug!(
+        "Flash loan {} from {}, fee {}",
+        amount,
+        provider.name(),
+        provider.fee(amount)
+    );
+    Ok(Some((provider, amount)))
+}
+
+// 各 base mint 的 base_per_sol，风控用它把不同 base token 的损益换算成 lamports
+fn base_per_sol(config: &Config) -> anyhow::Result<HashMap<Pubkey, u64>> {
+    let mut base_per_sol = HashMap::new();
+    for mint_config in &config.routing.mint_config_list {
+        if let Some(value) = mint_config.base_per_sol {
+            base_per_sol.insert(mint_config.base_mint()?, value);
+        }
+    }
+    if let Some(cycle_config) = &config.routing.cycle {
+        if let Some(value) = cycle_config.base_per_sol {
+            base_per_sol.insert(cycle_config.base_mint()?, value);
+        }
+    }
+    Ok(base_per_sol)
+}
+
+// 报价按 [spam] 的固定 CU 价格和固定小费计入成本，发送前换成本次的 CU 价格和按利润比例的小费
+// 重新计算净利润，低于 min_profit 时返回 None
+fn reprice(
+    config: &Config,
+    params: &QuoteParams,
+    compute_unit_price: u64,
+    net_profit: i64,
+) -> anyhow::Result<Option<i64>> {
+    let priced = params.with_costs(config, compute_unit_price, Some(net_profit))?;
+    let repriced = net_profit + params.cost.fixed as i64 - priced.cost.fixed as i64;
+    if repriced < params.min_profit as i64 {
+        info!(
+            "Dropping route, profit {} after priority fee and tip is below {}",
+            repriced, params.min_profit
+        );
+        return Ok(None);
     }
-    // 防止主线程退出，保持tokio runtime持续运行
-    loop {
-        tokio::time::sleep(Duration::from_secs(1)).await;
+    Ok(Some(repriced))
+}
+
+// mint 配置的 ALT 加上默认 ALT，非法地址直接跳过
+fn lookup_table_pubkeys(mint_config: &MintConfig) -> Vec<Pubkey> {
+    let mut lookup_tables = mint_config.lookup_table_list.clone().unwrap_or_default();
+    lookup_tables.push(DEFAULT_LOOKUP_TABLE.to_string());
 
+    lookup_tables
+        .iter()
+        .filter_map(|lookup_table| match Pubkey::from_str(lookup_table) {
+            Ok(pubkey) => Some(pubkey),
+            Err(e) => {
+                error!("   Invalid lookup table pubkey string {}: {}", lookup_table, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// 将ALT中存储的的地址反序列化出来
+fn load_lookup_tables(
+    mint_config: &MintConfig,
+    lookup_table_data: &HashMap<Pubkey, Account>,
+) -> Vec<AddressLookupTableAccount> {
+    let mut lookup_table_accounts_list = vec![];
+
+    for pubkey in lookup_table_pubkeys(mint_config) {
+        let Some(account) = lookup_table_data.get(&pubkey) else {
+            error!("   Failed to fetch lookup table account {}", pubkey);
+            continue;
+        };
+        match AddressLookupTable::deserialize(&account.data) {
+            Ok(lookup_table) => {
+                lookup_table_accounts_list.push(AddressLookupTableAccount {
+                    key: pubkey,
+                    addresses: lookup_table.addresses.into_owned(),
+                });
+                info!("   Successfully loaded lookup table: {}", pubkey);
+            }
+            Err(e) => {
+                error!("   Failed to deserialize lookup table {}: {}", pubkey, e);
+            }
+        }
     }
 
+    if lookup_table_accounts_list.is_empty() {
+        warn!("   Warning: No valid lookup tables were loaded");
+    } else {
+        info!(
+            "   Loaded {} lookup tables successfully",
+            lookup_table_accounts_list.len()
+        );
+    }
+    lookup_table_accounts_list
 }
 
 // blockhash refresher 方法实现
-async fn blockhash_refresher(rpc_client: Arc<RpcClient>, cached_blockhash: Arc<Mutex<Hash>>, refresh_interval: Duration) {
-    loop{
+async fn blockhash_refresher(
+    rpc_client: Arc<RpcClient>,
+    cached_blockhash: Arc<Mutex<Hash>>,
+    refresh_interval: Duration,
+    mut shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    while !shutdown.is_triggered() {
         match rpc_client.get_latest_blockhash(){
             Ok(blockhash) => {
                 let mut guard = cached_blockhash.lock().await;
@@ -209,8 +1457,9 @@ async fn blockhash_refresher(rpc_client: Arc<RpcClient>, cached_blockhash: Arc<M
                 error!("Failed to get latest blockhash: {}", e);
             }
         }
-        tokio::time::sleep(refresh_interval).await;
+        shutdown.sleep(refresh_interval).await;
     }
+    Ok(())
 }
 
 // 加载钱包私钥
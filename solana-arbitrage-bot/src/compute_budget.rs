@@ -0,0 +1,175 @@
+use crate::config::Config;
+use crate::dex::{adapter, DexType};
+use crate::simulate::SimulationReport;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use std::collections::HashMap;
+
+// 单笔交易的 CU 上限，模拟时也用它避免因 CU 不足失败
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+// 套利程序自身的开销：WSOL 账户处理、利润检查等
+const DEFAULT_BASE_COMPUTE_UNITS: u32 = 50_000;
+// 闪电贷借入和归还两条指令的开销
+const FLASH_LOAN_COMPUTE_UNITS: u32 = 80_000;
+const DEFAULT_MARGIN_PERCENT: u32 = 10;
+
+// 按模拟消耗或 DEX 组合决定每笔交易的 CU limit
+#[derive(Debug, Clone)]
+pub struct ComputeBudget {
+    pub base_units: u32,
+    pub margin_percent: u32,
+    // 覆盖 DexPool::default_compute_units，键为去掉 _pool_list 后缀的池子列表名，如 raydium_clmm
+    pub dex_units: HashMap<String, u32>,
+}
+
+impl ComputeBudget {
+    pub fn from_config(config: &Config) -> Self {
+        let budget_config = config.compute_budget.as_ref();
+        let flash_loan_units = match &config.kamino_flashloan {
+            Some(flashloan_config) if flashloan_config.enabled => FLASH_LOAN_COMPUTE_UNITS,
+            _ => 0,
+        };
+        Self {
+            base_units: budget_config
+                .and_then(|budget_config| budget_config.base_units)
+                .unwrap_or(DEFAULT_BASE_COMPUTE_UNITS)
+                + flash_loan_units,
+            margin_percent: budget_config
+                .and_then(|budget_config| budget_config.margin_percent)
+                .unwrap_or(DEFAULT_MARGIN_PERCENT),
+            dex_units: budget_config
+                .and_then(|budget_config| budget_config.dex_units.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn dex_units(&self, dex: DexType) -> u32 {
+        let dex = adapter(dex);
+        let key = dex.pool_list_key().trim_end_matches("_pool_list");
+        self.dex_units
+            .get(key)
+            .copied()
+            .unwrap_or_else(|| dex.default_compute_units())
+    }
+
+    // 没有模拟结果时，按路径经过的 DEX 逐跳累加
+    pub fn default_limit(&self, dexes: &[DexType]) -> u32 {
+        let units = dexes
+            .iter()
+            .fold(self.base_units, |units, dex| units.saturating_add(self.dex_units(*dex)));
+        units.min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    // 模拟消耗加上安全余量
+    pub fn limit_from_simulation(&self, units_consumed: u64) -> u32 {
+        let units = units_consumed * (100 + self.margin_percent as u64) / 100;
+        units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+
+    // 优先使用模拟得到的 CU，模拟关闭或没有返回 CU 时退回默认表
+    pub fn limit(&self, simulation: Option<&SimulationReport>, dexes: &[DexType]) -> u32 {
+        match simulation.and_then(|report| report.units_consumed) {
+            Some(units_consumed) => self.limit_from_simulation(units_consumed),
+            None => self.default_limit(dexes),
+        }
+    }
+
+    pub fn limit_instruction(
+        &self,
+        simulation: Option<&SimulationReport>,
+        dexes: &[DexType],
+    ) -> Instruction {
+        ComputeBudgetInstruction::set_compute_unit_limit(self.limit(simulation, dexes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extra: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+[bot]
+compute_unit_limit = 200000
+
+[rpc]
+url = "http://127.0.0.1:8899"
+
+[wallet]
+private_key = "test-key"
+
+[routing]
+mint_config_list = []
+{}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn simulation(units_consumed: Option<u64>) -> SimulationReport {
+        SimulationReport {
+            error: None,
+            units_consumed,
+            profit: Some(1),
+            logs: vec![],
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_a_compute_budget_section() {
+        let budget = ComputeBudget::from_config(&config(""));
+        assert_eq!(budget.base_units, DEFAULT_BASE_COMPUTE_UNITS);
+        assert_eq!(budget.margin_percent, DEFAULT_MARGIN_PERCENT);
+        assert!(budget.dex_units.is_empty());
+        assert_eq!(budget.default_limit(&[DexType::RaydiumAmm, DexType::Dlmm]), 50_000 + 45_000 + 100_000);
+    }
+
+    #[test]
+    fn flash_loans_add_their_instructions_to_the_base_units() {
+        let config = config(
+            r#"
+[kamino_flashloan]
+enabled = true
+
+[compute_budget]
+base_units = 30000
+"#,
+        );
+        assert_eq!(ComputeBudget::from_config(&config).base_units, 30_000 + FLASH_LOAN_COMPUTE_UNITS);
+    }
+
+    #[test]
+    fn dex_units_override_the_adapter_defaults() {
+        let config = config(
+            r#"
+[compute_budget]
+dex_units = { raydium_clmm = 150000, meteora_dlmm = 70000 }
+"#,
+        );
+        let budget = ComputeBudget::from_config(&config);
+        assert_eq!(budget.dex_units(DexType::RaydiumClmm), 150_000);
+        assert_eq!(budget.dex_units(DexType::Dlmm), 70_000);
+        assert_eq!(budget.dex_units(DexType::Whirlpool), 90_000);
+        assert_eq!(
+            budget.default_limit(&[DexType::RaydiumClmm, DexType::Whirlpool]),
+            50_000 + 150_000 + 90_000
+        );
+    }
+
+    #[test]
+    fn simulated_units_get_the_margin_and_are_capped() {
+        let budget = ComputeBudget::from_config(&config("[compute_budget]\nmargin_percent = 20"));
+        assert_eq!(budget.limit(Some(&simulation(Some(100_000))), &[DexType::Pump]), 120_000);
+        // 模拟没有返回 CU 时退回默认表
+        assert_eq!(budget.limit(Some(&simulation(None)), &[DexType::Pump]), 50_000 + 80_000);
+        assert_eq!(budget.limit(None, &[DexType::Pump]), 50_000 + 80_000);
+        assert_eq!(budget.limit_from_simulation(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        let budget = ComputeBudget {
+            base_units: u32::MAX,
+            ..budget
+        };
+        assert_eq!(budget.default_limit(&[DexType::Pump]), MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
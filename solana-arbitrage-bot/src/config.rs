@@ -1,5 +1,11 @@
+use crate::constants::sol_mint;
+use crate::validate;
+use serde::de::Error;
 use serde::{Deserialize, Deserializer};
-use std::{env, fs::File, io::Read};
+use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::env;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -8,32 +14,107 @@ pub struct Config {
     pub rpc: RpcConfig,
     pub spam: Option<SpamConfig>,
     pub wallet: WalletConfig,
-    pub kamino_flashload: Option<KaminoFlashloadConfig>,
+    // 旧配置中的拼写 kamino_flashload 仍然可用
+    #[serde(alias = "kamino_flashload")]
+    pub kamino_flashloan: Option<KaminoFlashloanConfig>,
+    pub flash_loan_providers: Option<Vec<FlashLoanProviderConfig>>,
+    pub quote: Option<QuoteConfig>,
+    pub stream: Option<StreamConfig>,
+    pub array_window: Option<ArrayWindowConfig>,
+    pub jito: Option<JitoConfig>,
+    pub simulation: Option<SimulationConfig>,
+    pub compute_budget: Option<ComputeBudgetConfig>,
+    pub priority_fee: Option<PriorityFeeConfig>,
+    pub journal: Option<JournalConfig>,
+    pub supervisor: Option<SupervisorConfig>,
+    pub risk: Option<RiskConfig>,
+    pub reload: Option<ReloadConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct BotConfig {
+    // 静态 CU limit，报价时用于估算优先费；每笔交易实际的 limit 由 [compute_budget] 决定
     pub compute_unit_limit: u32,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RoutingConfig {
     pub mint_config_list: Vec<MintConfig>,
+    pub cycle: Option<CycleConfig>,
 }
 
+// 环路套利：所有 MintConfig 的池子构成一张图，从 base_mint 出发枚举环路
 #[derive(Debug, Deserialize, Clone)]
+pub struct CycleConfig {
+    pub enabled: bool,
+    // 环路起止的代币，不填默认为 SOL
+    pub base_mint: Option<String>,
+    // 最多几跳，默认 2；套利合约暂不支持跨 mint 的三跳环路，大于 2 时按 2 处理
+    pub max_hops: Option<usize>,
+    // 单位是 base token 的最小单位
+    pub amount_in: u64,
+    pub min_profit: u64,
+    // 单笔环路的最大投入，不填则只受钱包余额和闪电贷额度限制
+    pub max_amount_in: Option<u64>,
+    // 含义与 MintConfig 的 base_per_sol 相同，base_mint 不是 SOL 时必填
+    pub base_per_sol: Option<u64>,
+    pub process_delay: u64,
+}
+
+impl CycleConfig {
+    pub fn base_mint(&self) -> anyhow::Result<Pubkey> {
+        match &self.base_mint {
+            Some(base_mint) => Pubkey::from_str(base_mint)
+                .map_err(|e| anyhow::anyhow!("Invalid cycle base mint {}: {}", base_mint, e)),
+            None => Ok(sol_mint()),
+        }
+    }
+}
+
+// 未知字段直接报错，拼错的字段名不会被静默忽略
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct MintConfig {
     // mint代币类型定义
     pub mint: String,
-    pub raydium_pool_list: Option<Vec<String>>,
-    pub meteora_dlmm_pool_list: Option<Vec<String>>,
-    pub raydium_cp_pool_list: Option<Vec<String>>,
-    pub pump_pool_list: Option<Vec<String>>,
-    pub whirlpool_pool_list: Option<Vec<String>>,
-    pub raydium_clmm_pool_list: Option<Vec<String>>,
+    // 套利路径起止的代币，如 USDC、USDT、JitoSOL，不填默认为 SOL
+    pub base_mint: Option<String>,
+    // 1 SOL 折合多少 base token 最小单位，用于把以 lamports 计的交易成本换算成 base token
+    // base_mint 不是 SOL 时必填，如 USDC 按 150 USDC/SOL 填 150000000
+    pub base_per_sol: Option<u64>,
+    // 覆盖 [quote] 中的 amount_in / min_profit_lamports，单位是 base token 的最小单位
+    pub amount_in: Option<u64>,
+    pub min_profit: Option<u64>,
+    // 单笔交易的最大投入，不填则只受钱包余额和闪电贷额度限制
+    pub max_amount_in: Option<u64>,
+    // 该 mint 单笔闪电贷最多借入的数量，覆盖 [kamino_flashloan] 的 max_borrow
+    pub max_flash_loan: Option<u64>,
+    // 固定该 mint 的 CU 价格（micro-lamports），不使用 [priority_fee] 的估计
+    pub compute_unit_price: Option<u64>,
     pub lookup_table_list: Option<Vec<String>>,
     // 处理延迟、限制套利路径执行频率、定时更新某个mint的dex信息、异步任务处理节流
-    pub process_delay: u64
+    pub process_delay: u64,
+    // 各 DEX 的池子列表，按 DexPool::pool_list_key 索引，如 [routing.mint_config_list.pools] 中的 raydium_pool_list
+    #[serde(default)]
+    pub pools: HashMap<String, Vec<String>>,
+}
+
+impl MintConfig {
+    pub fn base_mint(&self) -> anyhow::Result<Pubkey> {
+        match &self.base_mint {
+            Some(base_mint) => Pubkey::from_str(base_mint)
+                .map_err(|e| anyhow::anyhow!("Invalid base mint {}: {}", base_mint, e)),
+            None => Ok(sol_mint()),
+        }
+    }
+
+    // 区分 mint_config_list 中的条目，同一个 mint 可以搭配不同的 base mint
+    pub fn key(&self) -> String {
+        match &self.base_mint {
+            Some(base_mint) => format!("{}/{}", self.mint, base_mint),
+            None => self.mint.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,6 +122,10 @@ pub struct RpcConfig {
     // 提高兼容性，可以在文件中写入，也可以在环境中配置
     #[serde(deserialize_with = "serde_string_or_env")]
     pub url: String,
+    // 批量获取账户的并发数、每个分片的重试次数和初始重试间隔
+    pub fetch_concurrency: Option<usize>,
+    pub fetch_retries: Option<usize>,
+    pub fetch_retry_delay_ms: Option<u64>,
 }
 
 // 用多个 RPC 发 spam 式套利交易”的参数
@@ -64,6 +149,175 @@ pub struct WalletConfig {
 #[derive(Debug, Deserialize, Clone)]
 pub struct KaminoFlashloanConfig {
     pub enabled: bool,
+    // 单笔闪电贷最多借入的 base token 数量，即开启闪电贷时的交易规模上限
+    pub max_borrow: Option<u64>,
+    // 闪电贷手续费率（百万分之几），计入报价成本，默认 10（0.001%）
+    pub fee_ppm: Option<u64>,
+    // base mint -> Kamino reserve 地址，SOL 默认使用主市场的 SOL reserve
+    pub reserves: Option<HashMap<String, String>>,
+}
+
+// 闪电贷来源，按优先级排列；开关、max_borrow 仍在 [kamino_flashloan] 中
+// 不填时使用 [kamino_flashloan] 中各 base mint 的 reserve
+#[derive(Debug, Deserialize, Clone)]
+pub struct FlashLoanProviderConfig {
+    pub protocol: FlashLoanProtocol,
+    // 借贷协议中保存流动性的账户，Kamino 为 reserve 地址
+    pub reserve: String,
+    // 手续费率（百万分之几），不填使用 [kamino_flashloan] 的 fee_ppm
+    pub fee_ppm: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLoanProtocol {
+    Kamino,
+}
+
+// 链下报价：只有预期净利润达到阈值的路径才会发送交易
+#[derive(Debug, Deserialize, Clone)]
+pub struct QuoteConfig {
+    pub enabled: bool,
+    // 每次尝试投入的 base token 数量，base 为 SOL 时即 lamports
+    pub amount_in: u64,
+    // 在规模上限内搜索净利润最大的投入，默认开启；关闭时固定投入 amount_in
+    pub optimize_size: Option<bool>,
+    // 扣除手续费和小费后的最小净利润（lamports）
+    pub min_profit_lamports: u64,
+    // 预留给 Jito 等的小费（lamports），计入成本
+    pub tip_lamports: Option<u64>,
+}
+
+// 通过 websocket 订阅池子相关账户，报价直接读取本地持续更新的快照
+#[derive(Debug, Deserialize, Clone)]
+pub struct StreamConfig {
+    pub enabled: bool,
+    #[serde(deserialize_with = "serde_string_or_env")]
+    pub ws_url: String,
+    // 订阅断开后的重连间隔（毫秒）
+    pub reconnect_delay_ms: Option<u64>,
+}
+
+// tick array / bin array 窗口：当前 array 两侧各保留多少个，价格跨过 array 边界时自动重新计算
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ArrayWindowConfig {
+    pub raydium_clmm: Option<u16>,
+    pub whirlpool: Option<u16>,
+    pub dlmm: Option<u16>,
+    // 检查当前 tick / active bin 的间隔（毫秒）
+    pub refresh_interval_ms: Option<u64>,
+}
+
+// Jito bundle：套利交易和小费转账打包发送到 block engine
+#[derive(Debug, Deserialize, Clone)]
+pub struct JitoConfig {
+    pub enabled: bool,
+    // block engine 地址，如 https://mainnet.block-engine.jito.wtf，配置多个时同时发送
+    pub block_engine_urls: Vec<String>,
+    // 放在 x-jito-auth 请求头中的 UUID，可以用 $ENV_NAME 从环境变量读取
+    #[serde(default, deserialize_with = "serde_option_string_or_env")]
+    pub uuid: Option<String>,
+    // 固定小费（lamports），按利润比例给小费时作为下限
+    pub tip_lamports: u64,
+    // 按预期净利润的百分比给小费
+    pub tip_percent: Option<u64>,
+    pub max_tip_lamports: Option<u64>,
+    // 轮询 bundle 状态的间隔和超时（毫秒）
+    pub status_poll_interval_ms: Option<u64>,
+    pub status_timeout_ms: Option<u64>,
+}
+
+// 发送前先 simulateTransaction，模拟失败或 base token 余额没有增加时丢弃交易
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimulationConfig {
+    pub enabled: bool,
+}
+
+// 每笔交易的 CU limit：开启模拟时为模拟消耗加 margin_percent，否则按路径上的 DEX 查默认表
+#[derive(Debug, Deserialize, Clone)]
+pub struct ComputeBudgetConfig {
+    // 模拟消耗之上的安全余量（百分比），默认 10
+    pub margin_percent: Option<u32>,
+    // 套利程序自身的开销，默认 50000
+    pub base_units: Option<u32>,
+    // 覆盖单个 DEX 的默认 CU，如 raydium_clmm = 150000
+    pub dex_units: Option<HashMap<String, u32>>,
+}
+
+// 根据 getRecentPrioritizationFees 估计 CU 价格，替代 [spam] 中固定的 compute_unit_price
+#[derive(Debug, Deserialize, Clone)]
+pub struct PriorityFeeConfig {
+    pub enabled: bool,
+    pub strategy: PriorityFeeStrategy,
+    // 使用的分位数，默认 75
+    pub percentile: Option<u8>,
+    // cap 策略的 CU 价格上限（micro-lamports）
+    pub max_compute_unit_price: Option<u64>,
+    // profit_fraction 策略下，总优先费占预期利润的百分比
+    pub profit_fraction_percent: Option<u64>,
+    pub refresh_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityFeeStrategy {
+    // 直接使用最近优先费的分位数
+    Percentile,
+    // 分位数，但不超过 max_compute_unit_price
+    Cap,
+    // 按预期利润的比例出价，不高于分位数
+    ProfitFraction,
+}
+
+// 把机会、发送的交易和确认结果追加写入 JSONL 文件，report 子命令读取它汇总盈亏
+#[derive(Debug, Deserialize, Clone)]
+pub struct JournalConfig {
+    pub enabled: bool,
+    pub path: String,
+}
+
+// 任务监管：失败的 mint 任务按指数退避重启；收到 SIGINT / SIGTERM 后等待在途交易确认再退出
+#[derive(Debug, Deserialize, Clone)]
+pub struct SupervisorConfig {
+    // 第一次重启前的等待（毫秒），之后每次翻倍，默认 1000
+    pub initial_backoff_ms: Option<u64>,
+    // 重启间隔上限（毫秒），默认 60000
+    pub max_backoff_ms: Option<u64>,
+    // 关闭时等待任务结束和交易确认的总时长（毫秒），默认 90000
+    pub shutdown_timeout_ms: Option<u64>,
+}
+
+// 风控：触发任一限制时各 mint 任务暂停发送交易（不退出），条件解除后自动恢复，连续失败除外（见下）
+#[derive(Debug, Deserialize, Clone)]
+pub struct RiskConfig {
+    pub enabled: bool,
+    // 最近一小时 / 一天的累计亏损上限（lamports），base 不是 SOL 的交易按 base_per_sol 换算后合计
+    pub max_hourly_loss: Option<u64>,
+    pub max_daily_loss: Option<u64>,
+    // 连续失败的交易数上限，触发后一直暂停，直到收到 SIGUSR2 才清零重新计数
+    // 设置 failure_cooldown_ms 时改为暂停该时长后自动恢复
+    pub max_consecutive_failures: Option<u32>,
+    pub failure_cooldown_ms: Option<u64>,
+    // 钱包 SOL 余额低于该值（lamports）时暂停，保证有足够的手续费
+    pub min_sol_balance: Option<u64>,
+    // fee_window_ms（默认 3600000）内交易费合计上限（lamports）
+    pub max_fees_per_window: Option<u64>,
+    pub fee_window_ms: Option<u64>,
+    // 该文件存在时暂停；也可以用 SIGUSR1 暂停、SIGUSR2 恢复
+    pub kill_switch_file: Option<String>,
+    // 检查余额和 kill switch 文件的间隔（毫秒），默认 5000
+    pub check_interval_ms: Option<u64>,
+    // 输出风控状态的间隔（毫秒），默认 60000
+    pub status_interval_ms: Option<u64>,
+}
+
+// 热加载 [routing].mint_config_list：收到 SIGHUP 时重新读取配置文件，开启 watch 时同时轮询文件的修改时间
+// 其余部分（钱包、RPC 等）不会热加载，变化时只输出提示
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReloadConfig {
+    pub watch: bool,
+    // 检查配置文件修改时间的间隔（毫秒），默认 2000
+    pub poll_interval_ms: Option<u64>,
 }
 
 // 自定义反序列化函数
@@ -73,22 +327,31 @@ where
 {
     // 从deserializer中读取反序列化值
     let value_or_env = String::deserialize(deserializer)?;
-    // 判断数据的第一个字符是否为$
-    // 闭包，直接抛出错误，而不返回具体的error信息
-    let value = match value_or_env.chars().next() {
-        Some('$') => env::var(&value_or_env[1..])
-        .unwrap_or_else(|_| panic!("{} is not a valid environment variable", &value_or_env[1...])),
-        _ => value_or_env,
-    };
-    Ok(value)
+    // 判断数据的第一个字符是否为$，环境变量不存在时返回反序列化错误
+    match value_or_env.strip_prefix('$') {
+        Some(name) => env::var(name)
+            .map_err(|_| D::Error::custom(format!("environment variable {} is not set", name))),
+        None => Ok(value_or_env),
+    }
+}
+
+pub fn serde_option_string_or_env<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    serde_string_or_env(deserializer).map(Some)
 }
 
-imp Config {
+impl Config {
+    // 解析后立即检查所有字段，有问题时一次性列出，而不是在初始化池子时才报错
     pub fn load(path: &str) -> anyhow::Result<Self> {
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let config: Config = toml::from_str(&contents)?;
-        Ok(config)
+        let (config, issues) = validate::load(path)?;
+        match config {
+            Some(config) if issues.is_empty() => Ok(config),
+            _ => Err(anyhow::anyhow!(
+                "Invalid config:\n{}",
+                validate::format_issues(path, &issues)
+            )),
+        }
     }
-}
\ No newline at end of file
+}
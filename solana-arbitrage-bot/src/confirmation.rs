@@ -0,0 +1,636 @@
+use crate::constants::sol_mint;
+use crate::jito::BundleOutcome;
+use crate::journal::{Journal, JournalEntry, OutcomeEntry};
+use crate::risk::RiskGuard;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    TransactionStatus, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+// getSignatureStatuses 单次最多查询 256 个签名
+const MAX_SIGNATURE_STATUSES: usize = 256;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// 已发送、等待确认的交易
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub signature: Signature,
+    // 接受了这笔交易的 RPC，同一笔交易发送到多个 RPC 时签名相同，只跟踪一次
+    pub rpcs: Vec<String>,
+    // 路径标识，用于按路径统计落地率
+    pub route: String,
+    pub mint: String,
+    pub base_mint: Pubkey,
+    pub expected_profit: Option<i64>,
+    // 交易使用的 blockhash，过期后仍未上链视为丢弃
+    pub blockhash: Hash,
+}
+
+// 从已上链交易的 meta 中读取的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LandedDetails {
+    // 钱包 base token 余额的实际变化（SOL 已扣除交易费）
+    pub profit: i64,
+    pub fee: u64,
+    pub compute_units_consumed: Option<u64>,
+}
+
+// details 读取失败时为 None；Dropped 为 block engine 判定没有上链的 bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    Landed { slot: u64, details: Option<LandedDetails> },
+    Failed { slot: u64, error: String, details: Option<LandedDetails> },
+    Expired,
+    Dropped { reason: String },
+}
+
+impl TransactionOutcome {
+    pub fn details(&self) -> Option<&LandedDetails> {
+        match self {
+            TransactionOutcome::Landed { details, .. }
+            | TransactionOutcome::Failed { details, .. } => details.as_ref(),
+            TransactionOutcome::Expired | TransactionOutcome::Dropped { .. } => None,
+        }
+    }
+
+    pub fn profit(&self) -> Option<i64> {
+        self.details().map(|details| details.profit)
+    }
+
+    pub fn slot(&self) -> Option<u64> {
+        match self {
+            TransactionOutcome::Landed { slot, .. } | TransactionOutcome::Failed { slot, .. } => {
+                Some(*slot)
+            }
+            TransactionOutcome::Expired | TransactionOutcome::Dropped { .. } => None,
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        match self {
+            TransactionOutcome::Landed { .. } => "landed",
+            TransactionOutcome::Failed { .. } => "failed",
+            TransactionOutcome::Expired => "expired",
+            TransactionOutcome::Dropped { .. } => "dropped",
+        }
+    }
+}
+
+impl fmt::Display for TransactionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionOutcome::Landed { slot, .. } => {
+                write!(f, "landed at slot {}, realized profit {:?}", slot, self.profit())
+            }
+            TransactionOutcome::Failed { slot, error, .. } => {
+                write!(
+                    f,
+                    "failed at slot {} ({}), realized profit {:?}",
+                    slot,
+                    error,
+                    self.profit()
+                )
+            }
+            TransactionOutcome::Expired => write!(f, "expired with its blockhash"),
+            TransactionOutcome::Dropped { reason } => write!(f, "bundle dropped ({})", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutcomeRecord {
+    pub transaction: SentTransaction,
+    pub outcome: TransactionOutcome,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LandingCounts {
+    pub sent: u64,
+    pub landed: u64,
+    pub failed: u64,
+    pub expired: u64,
+    pub realized_profit: i64,
+}
+
+impl LandingCounts {
+    pub fn landing_rate(&self) -> f64 {
+        let resolved = self.landed + self.failed + self.expired;
+        if resolved == 0 {
+            0.0
+        } else {
+            self.landed as f64 / resolved as f64
+        }
+    }
+
+    fn record(&mut self, outcome: &TransactionOutcome) {
+        match outcome {
+            TransactionOutcome::Landed { .. } => self.landed += 1,
+            TransactionOutcome::Failed { .. } => self.failed += 1,
+            // 没有上链的 bundle 与过期的交易一样不产生费用
+            TransactionOutcome::Expired | TransactionOutcome::Dropped { .. } => self.expired += 1,
+        }
+        self.realized_profit += outcome.profit().unwrap_or(0);
+    }
+}
+
+impl fmt::Display for LandingCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent {}, landed {}, failed {}, expired {}, landing rate {:.1}%, realized profit {}",
+            self.sent,
+            self.landed,
+            self.failed,
+            self.expired,
+            self.landing_rate() * 100.0,
+            self.realized_profit
+        )
+    }
+}
+
+// 按 RPC 和路径分别统计落地情况
+#[derive(Debug, Clone, Default)]
+pub struct LandingStats {
+    pub by_rpc: HashMap<String, LandingCounts>,
+    pub by_route: HashMap<String, LandingCounts>,
+}
+
+impl LandingStats {
+    // 每个接受了交易的 RPC 各计一次，路径只计一次
+    fn record_sent(&mut self, transaction: &SentTransaction) {
+        for rpc in &transaction.rpcs {
+            self.by_rpc.entry(rpc.clone()).or_default().sent += 1;
+        }
+        self.by_route.entry(transaction.route.clone()).or_default().sent += 1;
+    }
+
+    fn record_outcome(&mut self, record: &OutcomeRecord) {
+        for rpc in &record.transaction.rpcs {
+            self.by_rpc.entry(rpc.clone()).or_default().record(&record.outcome);
+        }
+        self.by_route
+            .entry(record.transaction.route.clone())
+            .or_default()
+            .record(&record.outcome);
+    }
+}
+
+fn token_amount(balances: &[UiTransactionTokenBalance], owner: &str, mint: &str) -> i128 {
+    balances
+        .iter()
+        .filter(|balance| {
+            balance.mint == mint
+                && Option::<String>::from(balance.owner.clone()).as_deref() == Some(owner)
+        })
+        .filter_map(|balance| balance.ui_token_amount.amount.parse::<i128>().ok())
+        .sum()
+}
+
+// 读取已上链交易的 meta，计算钱包 base token 余额的变化
+pub async fn landed_details(
+    rpc_client: &NonblockingRpcClient,
+    signature: &Signature,
+    wallet: &Pubkey,
+    base_mint: &Pubkey,
+) -> anyhow::Result<LandedDetails> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let transaction = rpc_client.get_transaction_with_config(signature, config).await?;
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} has no meta", signature))?;
+    Ok(meta_details(meta, wallet, base_mint))
+}
+
+// base 为 SOL 时钱包 lamports（手续费付款人，索引 0）与 WSOL 账户的变化合计
+fn meta_details(meta: UiTransactionStatusMeta, wallet: &Pubkey, base_mint: &Pubkey) -> LandedDetails {
+    let owner = wallet.to_string();
+    let mint = base_mint.to_string();
+    let pre_token_balances = Option::<Vec<_>>::from(meta.pre_token_balances).unwrap_or_default();
+    let post_token_balances = Option::<Vec<_>>::from(meta.post_token_balances).unwrap_or_default();
+    let mut profit = token_amount(&post_token_balances, &owner, &mint)
+        - token_amount(&pre_token_balances, &owner, &mint);
+    if *base_mint == sol_mint() {
+        let pre_lamports = *meta.pre_balances.first().unwrap_or(&0) as i128;
+        let post_lamports = *meta.post_balances.first().unwrap_or(&0) as i128;
+        profit += post_lamports - pre_lamports;
+    }
+    LandedDetails {
+        profit: profit as i64,
+        fee: meta.fee,
+        compute_units_consumed: meta.compute_units_consumed.into(),
+    }
+}
+
+// 失败的交易同样扣除了手续费，确认后也计算实际损益；只到 processed 时下一轮再看
+fn is_resolved(status: &TransactionStatus) -> bool {
+    status.err.is_some() || status.satisfies_commitment(CommitmentConfig::confirmed())
+}
+
+fn resolved_outcome(status: TransactionStatus, details: Option<LandedDetails>) -> TransactionOutcome {
+    match status.err {
+        Some(err) => TransactionOutcome::Failed {
+            slot: status.slot,
+            error: format!("{:?}", err),
+            details,
+        },
+        None => TransactionOutcome::Landed {
+            slot: status.slot,
+            details,
+        },
+    }
+}
+
+// 交给后台任务的交易：普通交易轮询签名状态，block engine 已判定丢弃的 bundle 直接记录结果
+enum Tracked {
+    Pending(SentTransaction),
+    Resolved(OutcomeRecord),
+}
+
+// 后台轮询 getSignatureStatuses，直到交易上链、失败或随 blockhash 过期
+#[derive(Clone)]
+pub struct ConfirmationTracker {
+    sender: mpsc::UnboundedSender<Tracked>,
+    stats: Arc<Mutex<LandingStats>>,
+}
+
+impl ConfirmationTracker {
+    pub fn spawn(
+        rpc_client: Arc<NonblockingRpcClient>,
+        wallet: Pubkey,
+        poll_interval: Option<Duration>,
+        journal: Option<Journal>,
+        risk: Option<RiskGuard>,
+    ) -> (Self, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let stats = Arc::new(Mutex::new(LandingStats::default()));
+        let handle = tokio::spawn(run_tracker(
+            rpc_client,
+            wallet,
+            poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL),
+            receiver,
+            stats.clone(),
+            journal,
+            risk,
+        ));
+        (Self { sender, stats }, handle)
+    }
+
+    pub async fn track(&self, transaction: SentTransaction) {
+        self.stats.lock().await.record_sent(&transaction);
+        self.send(Tracked::Pending(transaction));
+    }
+
+    // Jito bundle 的结果：上链的 bundle 仍按签名读取实际损益，丢弃的直接记录
+    pub async fn track_bundle(&self, transaction: SentTransaction, outcome: &BundleOutcome) {
+        self.stats.lock().await.record_sent(&transaction);
+        match outcome {
+            BundleOutcome::Landed { .. } => self.send(Tracked::Pending(transaction)),
+            BundleOutcome::Dropped { reason } => self.send(Tracked::Resolved(OutcomeRecord {
+                transaction,
+                outcome: TransactionOutcome::Dropped {
+                    reason: reason.clone(),
+                },
+            })),
+        }
+    }
+
+    fn send(&self, tracked: Tracked) {
+        if self.sender.send(tracked).is_err() {
+            warn!("Confirmation tracker has stopped, signature will not be tracked");
+        }
+    }
+
+    pub async fn stats(&self) -> LandingStats {
+        self.stats.lock().await.clone()
+    }
+}
+
+async fn resolve(
+    rpc_client: &NonblockingRpcClient,
+    pending: &[SentTransaction],
+    wallet: &Pubkey,
+) -> anyhow::Result<Vec<Option<TransactionOutcome>>> {
+    let signatures: Vec<Signature> =
+        pending.iter().map(|transaction| transaction.signature).collect();
+    let statuses = rpc_client.get_signature_statuses(&signatures).await?.value;
+
+    let mut blockhash_valid = HashMap::new();
+    let mut outcomes = Vec::with_capacity(pending.len());
+    for (transaction, status) in pending.iter().zip(statuses) {
+        let outcome = match status {
+            Some(status) if is_resolved(&status) => {
+                let details = match landed_details(
+                    rpc_client,
+                    &transaction.signature,
+                    wallet,
+                    &transaction.base_mint,
+                )
+                .await
+                {
+                    Ok(details) => Some(details),
+                    Err(e) => {
+                        warn!("Failed to read meta of {}: {}", transaction.signature, e);
+                        None
+                    }
+                };
+                Some(resolved_outcome(status, details))
+            }
+            Some(_) => None,
+            None => {
+                let valid = match blockhash_valid.get(&transaction.blockhash) {
+                    Some(valid) => *valid,
+                    None => {
+                        let valid = rpc_client
+                            .is_blockhash_valid(&transaction.blockhash, CommitmentConfig::processed())
+                            .await?;
+                        blockhash_valid.insert(transaction.blockhash, valid);
+                        valid
+                    }
+                };
+                (!valid).then_some(TransactionOutcome::Expired)
+            }
+        };
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+// 记录一笔交易的最终结果：写入 journal，交给风控，并更新落地率统计
+async fn finish(
+    record: &OutcomeRecord,
+    stats: &Mutex<LandingStats>,
+    journal: Option<&Journal>,
+    risk: Option<&RiskGuard>,
+) {
+    let transaction = &record.transaction;
+    if let Some(journal) = journal {
+        journal.record(JournalEntry::Outcome(OutcomeEntry::from_record(record)));
+    }
+    if let Some(risk) = risk {
+        risk.record_outcome(record).await;
+    }
+    let mut stats = stats.lock().await;
+    stats.record_outcome(record);
+    info!(
+        "Transaction {} for mint {} via {} {} (expected profit {:?})",
+        transaction.signature,
+        transaction.mint,
+        transaction.rpcs.join(", "),
+        record.outcome,
+        transaction.expected_profit
+    );
+    for rpc in &transaction.rpcs {
+        info!("  RPC {}: {}", rpc, stats.by_rpc[rpc]);
+    }
+    info!("  Route {}: {}", transaction.route, stats.by_route[&transaction.route]);
+}
+
+async fn run_tracker(
+    rpc_client: Arc<NonblockingRpcClient>,
+    wallet: Pubkey,
+    poll_interval: Duration,
+    mut receiver: mpsc::UnboundedReceiver<Tracked>,
+    stats: Arc<Mutex<LandingStats>>,
+    journal: Option<Journal>,
+    risk: Option<RiskGuard>,
+) {
+    let mut pending: Vec<SentTransaction> = Vec::new();
+    // 所有 ConfirmationTracker 都被丢弃后，处理完剩余的签名再退出
+    let mut closed = false;
+    loop {
+        tokio::time::sleep(poll_interval).await;
+        loop {
+            match receiver.try_recv() {
+                Ok(Tracked::Pending(transaction)) => pending.push(transaction),
+                Ok(Tracked::Resolved(record)) => {
+                    finish(&record, &stats, journal.as_ref(), risk.as_ref()).await
+                }
+                Err(TryRecvError::Disconnected) => {
+                    closed = true;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        if pending.is_empty() {
+            if closed {
+                break;
+            }
+            continue;
+        }
+
+        let mut still_pending = Vec::with_capacity(pending.len());
+        for chunk in pending.chunks(MAX_SIGNATURE_STATUSES) {
+            let outcomes = match resolve(&rpc_client, chunk, &wallet).await {
+                Ok(outcomes) => outcomes,
+                Err(e) => {
+                    error!("Failed to get signature statuses: {}", e);
+                    still_pending.extend(chunk.iter().cloned());
+                    continue;
+                }
+            };
+            for (transaction, outcome) in chunk.iter().zip(outcomes) {
+                let Some(outcome) = outcome else {
+                    still_pending.push(transaction.clone());
+                    continue;
+                };
+                let record = OutcomeRecord {
+                    transaction: transaction.clone(),
+                    outcome,
+                };
+                finish(&record, &stats, journal.as_ref(), risk.as_ref()).await;
+            }
+        }
+        pending = still_pending;
+    }
+    info!("Confirmation tracker stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn status(
+        err: Option<TransactionError>,
+        confirmation_status: TransactionConfirmationStatus,
+    ) -> TransactionStatus {
+        TransactionStatus {
+            slot: 42,
+            confirmations: Some(1),
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(confirmation_status),
+        }
+    }
+
+    fn details() -> LandedDetails {
+        LandedDetails {
+            profit: 1_000,
+            fee: 5_000,
+            compute_units_consumed: Some(150_000),
+        }
+    }
+
+    fn sent(rpcs: &[&str], route: &str) -> SentTransaction {
+        SentTransaction {
+            signature: Signature::default(),
+            rpcs: rpcs.iter().map(|rpc| rpc.to_string()).collect(),
+            route: route.to_string(),
+            mint: Pubkey::new_unique().to_string(),
+            base_mint: sol_mint(),
+            expected_profit: None,
+            blockhash: Hash::default(),
+        }
+    }
+
+    fn token_balance(owner: &Pubkey, mint: &Pubkey, amount: u64) -> serde_json::Value {
+        json!({
+            "accountIndex": 1,
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "programId": spl_token::id().to_string(),
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": amount.to_string(),
+                "uiAmountString": amount.to_string(),
+            },
+        })
+    }
+
+    fn meta(
+        balances: (u64, u64),
+        pre_token_balances: Vec<serde_json::Value>,
+        post_token_balances: Vec<serde_json::Value>,
+    ) -> UiTransactionStatusMeta {
+        serde_json::from_value(json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [balances.0, 1],
+            "postBalances": [balances.1, 1],
+            "preTokenBalances": pre_token_balances,
+            "postTokenBalances": post_token_balances,
+            "computeUnitsConsumed": 150_000,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_confirmed_or_failed_statuses_are_resolved() {
+        let error = TransactionError::InstructionError(2, InstructionError::Custom(1));
+        assert!(!is_resolved(&status(None, TransactionConfirmationStatus::Processed)));
+        assert!(is_resolved(&status(None, TransactionConfirmationStatus::Confirmed)));
+        assert!(is_resolved(&status(None, TransactionConfirmationStatus::Finalized)));
+        // 失败的交易在 processed 时就可以确定结果
+        assert!(is_resolved(&status(Some(error), TransactionConfirmationStatus::Processed)));
+    }
+
+    #[test]
+    fn statuses_map_to_landed_and_failed_outcomes() {
+        let outcome = resolved_outcome(status(None, TransactionConfirmationStatus::Confirmed), Some(details()));
+        assert_eq!(
+            outcome,
+            TransactionOutcome::Landed {
+                slot: 42,
+                details: Some(details())
+            }
+        );
+        assert_eq!(outcome.status(), "landed");
+        assert_eq!(outcome.profit(), Some(1_000));
+
+        let error = TransactionError::InstructionError(2, InstructionError::Custom(1));
+        let outcome = resolved_outcome(status(Some(error), TransactionConfirmationStatus::Confirmed), None);
+        assert_eq!(
+            outcome,
+            TransactionOutcome::Failed {
+                slot: 42,
+                error: "InstructionError(2, Custom(1))".to_string(),
+                details: None,
+            }
+        );
+        assert_eq!(outcome.status(), "failed");
+        assert_eq!(outcome.slot(), Some(42));
+        assert_eq!(outcome.profit(), None);
+    }
+
+    #[test]
+    fn meta_details_add_wallet_lamports_for_sol() {
+        let wallet = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let meta = meta(
+            (10_000_000, 9_990_000),
+            vec![token_balance(&wallet, &sol_mint(), 1_000_000), token_balance(&other, &sol_mint(), 500)],
+            vec![token_balance(&wallet, &sol_mint(), 1_020_000), token_balance(&other, &sol_mint(), 0)],
+        );
+        let details = meta_details(meta, &wallet, &sol_mint());
+        // WSOL +20_000，lamports -10_000（含手续费）
+        assert_eq!(details.profit, 10_000);
+        assert_eq!(details.fee, 5_000);
+        assert_eq!(details.compute_units_consumed, Some(150_000));
+    }
+
+    #[test]
+    fn meta_details_only_count_the_base_token_for_other_mints() {
+        let wallet = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let meta = meta(
+            (10_000_000, 9_995_000),
+            vec![token_balance(&wallet, &usdc, 2_000_000)],
+            vec![token_balance(&wallet, &usdc, 1_990_000), token_balance(&wallet, &sol_mint(), 7)],
+        );
+        assert_eq!(meta_details(meta, &wallet, &usdc).profit, -10_000);
+    }
+
+    #[test]
+    fn landing_stats_count_each_rpc_and_the_route_once() {
+        let mut stats = LandingStats::default();
+        let transaction = sent(&["rpc-a", "rpc-b"], "pool-1 -> pool-2");
+        stats.record_sent(&transaction);
+        stats.record_sent(&sent(&["rpc-a"], "pool-1 -> pool-2"));
+        stats.record_outcome(&OutcomeRecord {
+            transaction,
+            outcome: TransactionOutcome::Landed {
+                slot: 42,
+                details: Some(details()),
+            },
+        });
+        stats.record_outcome(&OutcomeRecord {
+            transaction: sent(&["rpc-a"], "pool-1 -> pool-2"),
+            outcome: TransactionOutcome::Dropped {
+                reason: "simulation failure".to_string(),
+            },
+        });
+
+        let rpc_a = stats.by_rpc["rpc-a"];
+        assert_eq!((rpc_a.sent, rpc_a.landed, rpc_a.expired), (2, 1, 1));
+        assert_eq!(rpc_a.landing_rate(), 0.5);
+        assert_eq!(stats.by_rpc["rpc-b"].landing_rate(), 1.0);
+        let route = stats.by_route["pool-1 -> pool-2"];
+        assert_eq!((route.sent, route.landed, route.failed, route.expired), (2, 1, 0, 1));
+        assert_eq!(route.realized_profit, 1_000);
+        assert_eq!(LandingCounts::default().landing_rate(), 0.0);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::dex::adapters::{readonly, writable};
+use crate::dex::dlmm::{constants::dlmm_program_id, dlmm_info::DlmmInfo};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::dlmm::{bin_array_address, bin_id_to_bin_array_index, DlmmPairState, DlmmQuoteState};
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use crate::window::ArrayWindow;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{info, warn};
+
+pub struct DlmmDex;
+
+impl DexPool for DlmmDex {
+    fn dex_type(&self) -> DexType {
+        DexType::Dlmm
+    }
+
+    fn name(&self) -> &'static str {
+        "DLMM"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "meteora_dlmm_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        dlmm_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        100_000
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let amm_info = DlmmInfo::load_checked(&account.data)?;
+        if amm_info.token_x_mint != pool_data.mint && amm_info.token_y_mint != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the dlmm pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        let (token_vault, base_vault) = amm_info.get_token_and_sol_vaults(pool_data.mint, *base_mint);
+        let bin_arrays = amm_info.calculate_bin_arrays(pool)?;
+
+        // extra_accounts 只放 bin array，refresh_arrays 整体替换
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::Dlmm,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts: bin_arrays.clone(),
+            instruction_accounts: vec![amm_info.oracle],
+        })?;
+
+        info!("DLMM pool added: {}", pool);
+        info!("    Token X Mint: {}", amm_info.token_x_mint);
+        info!("    Token Y Mint: {}", amm_info.token_y_mint);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}", base_vault);
+        info!("    Oracle: {}", amm_info.oracle);
+        info!("    Active ID: {}", amm_info.active_id);
+        // dlmm 会有多个bin array，所以需要打印出来
+        for (i, array) in bin_arrays.iter().enumerate() {
+            info!("    Bin array {}: {}", i, array);
+        }
+        info!("");
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        let state = DlmmQuoteState::load(keys, snapshot)?;
+        let swap_for_y = state.swap_for_y(keys, direction);
+        let result = state.quote_exact_in(swap_for_y, amount_in)?;
+        if result.exceeds_bin_array_window {
+            return Err(anyhow::anyhow!(
+                "Swap of {} exceeds the fetched bin arrays of DLMM pool {}",
+                amount_in,
+                keys.pool
+            ));
+        }
+        Ok(result.amount_out)
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let mut accounts = readonly(&[dlmm_program_id()]);
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts.extend(writable(&keys.instruction_accounts));
+        accounts.extend(writable(&keys.extra_accounts));
+        accounts
+    }
+
+    fn refresh_arrays(
+        &self,
+        pool_data: &mut MintPoolData,
+        snapshot: &AccountSnapshot,
+        window: &ArrayWindow,
+    ) -> bool {
+        let offsets = ArrayWindow::offsets(window.dlmm);
+        let mut changed = false;
+        for keys in pool_data.pools_of_mut(DexType::Dlmm) {
+            let state = match snapshot.data(&keys.pool).and_then(DlmmPairState::load) {
+                Ok(state) => state,
+                Err(e) => {
+                    warn!("Failed to refresh bin arrays of DLMM pool {}: {}", keys.pool, e);
+                    continue;
+                }
+            };
+            let index = bin_id_to_bin_array_index(state.active_id);
+            let bin_arrays: Vec<Pubkey> = offsets
+                .iter()
+                .map(|offset| bin_array_address(&keys.pool, index + *offset as i64))
+                .collect();
+            if bin_arrays != keys.extra_accounts {
+                info!("DLMM pool {} bin arrays moved to active id {}", keys.pool, state.active_id);
+                keys.extra_accounts = bin_arrays;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
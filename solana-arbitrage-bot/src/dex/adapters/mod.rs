@@ -0,0 +1,44 @@
+mod dlmm;
+mod pump;
+mod raydium_amm;
+mod raydium_clmm;
+mod raydium_cp;
+mod whirlpool;
+
+pub use dlmm::DlmmDex;
+pub use pump::PumpDex;
+pub use raydium_amm::RaydiumAmmDex;
+pub use raydium_clmm::RaydiumClmmDex;
+pub use raydium_cp::RaydiumCpDex;
+pub use whirlpool::WhirlpoolDex;
+
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+
+// 判断池子哪一侧是 base token，返回 (base_vault, token_vault)
+pub(crate) fn base_and_token_vaults(
+    base_mint: &Pubkey,
+    mint_a: &Pubkey,
+    vault_a: &Pubkey,
+    mint_b: &Pubkey,
+    vault_b: &Pubkey,
+) -> Option<(Pubkey, Pubkey)> {
+    if mint_a == base_mint {
+        Some((*vault_a, *vault_b))
+    } else if mint_b == base_mint {
+        Some((*vault_b, *vault_a))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn writable(pubkeys: &[Pubkey]) -> Vec<AccountMeta> {
+    pubkeys.iter().map(|pubkey| AccountMeta::new(*pubkey, false)).collect()
+}
+
+pub(crate) fn readonly(pubkeys: &[Pubkey]) -> Vec<AccountMeta> {
+    pubkeys
+        .iter()
+        .map(|pubkey| AccountMeta::new_readonly(*pubkey, false))
+        .collect()
+}
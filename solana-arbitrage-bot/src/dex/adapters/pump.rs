@@ -0,0 +1,117 @@
+use crate::dex::adapters::{base_and_token_vaults, readonly, writable};
+use crate::dex::pump::{pump_fee_wallet, pump_program_id, PumpAmmInfo};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::pump::{pump_global_config, PumpQuoteState};
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::info;
+
+pub struct PumpDex;
+
+impl DexPool for PumpDex {
+    fn dex_type(&self) -> DexType {
+        DexType::Pump
+    }
+
+    fn name(&self) -> &'static str {
+        "Pump"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "pump_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        pump_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        80_000
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let amm_info = PumpAmmInfo::load_checked(&account.data)?;
+        if amm_info.base_mint != pool_data.mint && amm_info.quote_mint != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the pump pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        // 判断池子哪边是 base token 哪边是 token
+        let (base_vault, token_vault) = base_and_token_vaults(
+            base_mint,
+            &amm_info.quote_mint,
+            &amm_info.pool_quote_token_account,
+            &amm_info.base_mint,
+            &amm_info.pool_base_token_account,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Base mint {} is not present in the pump pool {}",
+                base_mint,
+                pool
+            )
+        })?;
+        // 构建手续费地址 Pump 官方的收款钱包 + quote token 的 ATA 地址。
+        let fee_token_wallet = spl_associated_token_account::get_associated_token_address(
+            &pump_fee_wallet(),
+            &amm_info.quote_mint,
+        );
+
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::Pump,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts: vec![pump_global_config()],
+            instruction_accounts: vec![fee_token_wallet],
+        })?;
+
+        info!("Pump pool added: {}", pool);
+        info!("    Pool base mint: {}", amm_info.base_mint);
+        info!("    Pool quote mint: {}", amm_info.quote_mint);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}", base_vault);
+        info!("    Fee token wallet: {}\n", fee_token_wallet);
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        PumpQuoteState::load(keys, snapshot)?.quote_exact_in(direction, amount_in)
+    }
+
+    fn constant_product(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+    ) -> Option<ConstantProduct> {
+        PumpQuoteState::load(keys, snapshot)
+            .ok()
+            .map(|state| state.constant_product(direction))
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let mut accounts = readonly(&[pump_program_id(), pump_global_config()]);
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts.extend(writable(&keys.instruction_accounts));
+        accounts
+    }
+}
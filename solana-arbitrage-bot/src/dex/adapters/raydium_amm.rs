@@ -0,0 +1,110 @@
+use crate::dex::adapters::{base_and_token_vaults, readonly, writable};
+use crate::dex::raydium::{raydium_program_id, RaydiumAmmInfo};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::raydium::RaydiumAmmQuoteState;
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::info;
+
+pub struct RaydiumAmmDex;
+
+impl DexPool for RaydiumAmmDex {
+    fn dex_type(&self) -> DexType {
+        DexType::RaydiumAmm
+    }
+
+    fn name(&self) -> &'static str {
+        "Raydium"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "raydium_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        raydium_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        45_000
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let amm_info = RaydiumAmmInfo::load_checked(&account.data)?;
+        if amm_info.coin_mint != pool_data.mint && amm_info.pc_mint != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the raydium pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        // 检查池子，如果不包含 base token 那么直接抛出错误
+        let (base_vault, token_vault) = base_and_token_vaults(
+            base_mint,
+            &amm_info.coin_mint,
+            &amm_info.coin_vault,
+            &amm_info.pc_mint,
+            &amm_info.pc_vault,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Base mint {} is not present in the raydium pool {}",
+                base_mint,
+                pool
+            )
+        })?;
+
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::RaydiumAmm,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts: vec![],
+            instruction_accounts: vec![],
+        })?;
+
+        info!("Raydium pool added: {}", pool);
+        info!("    Coin mint: {}", amm_info.coin_mint);
+        info!("    PC mint: {}", amm_info.pc_mint);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}\n", base_vault);
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        RaydiumAmmQuoteState::load(keys, snapshot)?.quote_exact_in(direction, amount_in)
+    }
+
+    fn constant_product(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+    ) -> Option<ConstantProduct> {
+        RaydiumAmmQuoteState::load(keys, snapshot)
+            .ok()
+            .map(|state| state.constant_product(direction))
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let mut accounts = readonly(&[raydium_program_id()]);
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts
+    }
+}
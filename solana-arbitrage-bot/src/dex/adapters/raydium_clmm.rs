@@ -0,0 +1,164 @@
+use crate::dex::adapters::{base_and_token_vaults, readonly, writable};
+use crate::dex::raydium::{get_tick_array_pubkeys, raydium_clmm_program_id, PoolState};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::raydium_clmm::{ClmmPoolState, RaydiumClmmQuoteState};
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use crate::window::ArrayWindow;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{info, warn};
+
+pub struct RaydiumClmmDex;
+
+impl DexPool for RaydiumClmmDex {
+    fn dex_type(&self) -> DexType {
+        DexType::RaydiumClmm
+    }
+
+    fn name(&self) -> &'static str {
+        "Raydium CLMM"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "raydium_clmm_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        raydium_clmm_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        100_000
+    }
+
+    // CLMM 池子不合法时跳过，不中断初始化
+    fn skip_invalid_pool(&self) -> bool {
+        true
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let raydium_clmm = PoolState::load_checked(&account.data)?;
+        if raydium_clmm.token_mint_0 != pool_data.mint && raydium_clmm.token_mint_1 != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the raydium clmm pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        let (base_vault, token_vault) = base_and_token_vaults(
+            base_mint,
+            &raydium_clmm.token_mint_0,
+            &raydium_clmm.token_vault_0,
+            &raydium_clmm.token_mint_1,
+            &raydium_clmm.token_vault_1,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Base mint {} is not present in Raydium CLMM pool {}",
+                base_mint,
+                pool
+            )
+        })?;
+
+        let tick_array_pubkeys = get_tick_array_pubkeys(
+            pool,
+            raydium_clmm.tick_current,
+            raydium_clmm.tick_spacing,
+            &[-1, 0, 1],
+            &raydium_clmm_program_id(),
+        )?;
+        // extra_accounts 第一个是 amm_config，后面是 tick array，swap_accounts 和 refresh_arrays 按此读取
+        let mut extra_accounts = vec![raydium_clmm.amm_config];
+        extra_accounts.extend(tick_array_pubkeys.iter().copied());
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::RaydiumClmm,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts,
+            instruction_accounts: vec![raydium_clmm.observation_key],
+        })?;
+
+        info!("Raydium CLMM pool added: {}", pool);
+        info!("    Token mint 0: {}", raydium_clmm.token_mint_0);
+        info!("    Token mint 1: {}", raydium_clmm.token_mint_1);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}", base_vault);
+        info!("    AMM config: {}", raydium_clmm.amm_config);
+        info!("    Observation key: {}", raydium_clmm.observation_key);
+        for (i, array) in tick_array_pubkeys.iter().enumerate() {
+            info!("    Tick Array {}: {}", i, array);
+        }
+        info!("");
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        let state = RaydiumClmmQuoteState::load(keys, snapshot)?;
+        let zero_for_one = state.zero_for_one(keys, direction);
+        Ok(state.simulate_swap(zero_for_one, amount_in, true)?.amount_out)
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let (amm_config, tick_arrays) = match keys.extra_accounts.split_first() {
+            Some((amm_config, tick_arrays)) => (vec![*amm_config], tick_arrays),
+            None => (vec![], &[][..]),
+        };
+        let mut accounts = readonly(&[raydium_clmm_program_id()]);
+        accounts.extend(readonly(&amm_config));
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts.extend(writable(&keys.instruction_accounts));
+        accounts.extend(writable(tick_arrays));
+        accounts
+    }
+
+    fn refresh_arrays(
+        &self,
+        pool_data: &mut MintPoolData,
+        snapshot: &AccountSnapshot,
+        window: &ArrayWindow,
+    ) -> bool {
+        let offsets = ArrayWindow::offsets(window.raydium_clmm);
+        let mut changed = false;
+        for keys in pool_data.pools_of_mut(DexType::RaydiumClmm) {
+            let tick_arrays = snapshot
+                .data(&keys.pool)
+                .and_then(ClmmPoolState::load)
+                .and_then(|state| {
+                    get_tick_array_pubkeys(
+                        &keys.pool,
+                        state.tick_current,
+                        state.tick_spacing,
+                        &offsets,
+                        &raydium_clmm_program_id(),
+                    )
+                });
+            match tick_arrays {
+                Ok(tick_arrays) if keys.extra_accounts.get(1..) != Some(&tick_arrays[..]) => {
+                    info!("Raydium CLMM pool {} tick arrays moved", keys.pool);
+                    // 保留第一个 amm_config，只替换后面的 tick array
+                    keys.extra_accounts.truncate(1);
+                    keys.extra_accounts.extend(tick_arrays);
+                    changed = true;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to refresh tick arrays of Raydium CLMM pool {}: {}", keys.pool, e),
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,114 @@
+use crate::dex::adapters::{base_and_token_vaults, readonly, writable};
+use crate::dex::raydium::{raydium_cp_program_id, RaydiumCpAmmInfo};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::raydium::RaydiumCpQuoteState;
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::info;
+
+pub struct RaydiumCpDex;
+
+impl DexPool for RaydiumCpDex {
+    fn dex_type(&self) -> DexType {
+        DexType::RaydiumCp
+    }
+
+    fn name(&self) -> &'static str {
+        "Raydium CP"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "raydium_cp_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        raydium_cp_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        60_000
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let amm_info = RaydiumCpAmmInfo::load_checked(&account.data)?;
+        if amm_info.token_0_mint != pool_data.mint && amm_info.token_1_mint != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the raydium cp pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        // 经过上面判断可以考虑符合要求了，判断左侧还是右侧是 base token
+        let (base_vault, token_vault) = base_and_token_vaults(
+            base_mint,
+            &amm_info.token_0_mint,
+            &amm_info.token_0_vault,
+            &amm_info.token_1_mint,
+            &amm_info.token_1_vault,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Base mint {} is not present in the raydium cp pool {}",
+                base_mint,
+                pool
+            )
+        })?;
+
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::RaydiumCp,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts: vec![amm_info.amm_config],
+            instruction_accounts: vec![amm_info.abservation_key],
+        })?;
+
+        info!("Raydium CP pool added: {}", pool);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}", base_vault);
+        info!("    Amm config: {}", amm_info.amm_config);
+        info!("    Observation key: {}\n", amm_info.abservation_key);
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        Ok(RaydiumCpQuoteState::load(keys, snapshot)?
+            .quote_exact_in(direction, amount_in)?
+            .amount_out)
+    }
+
+    fn constant_product(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+    ) -> Option<ConstantProduct> {
+        RaydiumCpQuoteState::load(keys, snapshot)
+            .ok()
+            .map(|state| state.constant_product(direction))
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let mut accounts = readonly(&[raydium_cp_program_id()]);
+        accounts.extend(readonly(&keys.extra_accounts));
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts.extend(writable(&keys.instruction_accounts));
+        accounts
+    }
+}
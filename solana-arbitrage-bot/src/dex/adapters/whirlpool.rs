@@ -0,0 +1,186 @@
+use crate::dex::adapters::{base_and_token_vaults, readonly, writable};
+use crate::dex::whirlpool::{
+    constants::whirlpool_program_id, state::Whirlpool, update_tick_array_accounts_for_onchain,
+};
+use crate::dex::{DexPool, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::whirlpool::{
+    tick_array_address, tick_array_start_index, WhirlpoolQuoteState, WhirlpoolState, TICK_ARRAY_SIZE,
+};
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use crate::window::ArrayWindow;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{info, warn};
+
+pub struct WhirlpoolDex;
+
+impl DexPool for WhirlpoolDex {
+    fn dex_type(&self) -> DexType {
+        DexType::Whirlpool
+    }
+
+    fn name(&self) -> &'static str {
+        "Whirlpool"
+    }
+
+    fn pool_list_key(&self) -> &'static str {
+        "whirlpool_pool_list"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        whirlpool_program_id()
+    }
+
+    fn default_compute_units(&self) -> u32 {
+        90_000
+    }
+
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        let whirlpool = Whirlpool::load_checked(&account.data)?;
+        if whirlpool.token_mint_a != pool_data.mint && whirlpool.token_mint_b != pool_data.mint {
+            return Err(anyhow::anyhow!(
+                "Mint {} is not present in the whirlpool pool {}",
+                pool_data.mint,
+                pool
+            ));
+        }
+        let (base_vault, token_vault) = base_and_token_vaults(
+            base_mint,
+            &whirlpool.token_mint_a,
+            &whirlpool.token_vault_a,
+            &whirlpool.token_mint_b,
+            &whirlpool.token_vault_b,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Base mint {} is not present in the whirlpool pool {}",
+                base_mint,
+                pool
+            )
+        })?;
+
+        // 通过种子和程序派生出Whirlpool池子的Oracle地址
+        let whirlpool_oracle =
+            Pubkey::find_program_address(&[b"oracle", pool.as_ref()], &whirlpool_program_id()).0;
+
+        // 从链上获取当前的whirlpool池子的tick array 地址
+        let whirlpool_tick_arrays =
+            update_tick_array_accounts_for_onchain(&whirlpool, pool, &whirlpool_program_id());
+        let tick_arrays: Vec<Pubkey> = whirlpool_tick_arrays.iter().map(|meta| meta.pubkey).collect();
+
+        // extra_accounts 只放 tick array，refresh_arrays 整体替换
+        pool_data.add_pool(PoolKeys {
+            dex: DexType::Whirlpool,
+            pool: *pool,
+            token_vault,
+            base_vault,
+            extra_accounts: tick_arrays.clone(),
+            instruction_accounts: vec![whirlpool_oracle],
+        })?;
+
+        info!("Whirlpool pool added: {}", pool);
+        info!("    Token mint A: {}", whirlpool.token_mint_a);
+        info!("    Token mint B: {}", whirlpool.token_mint_b);
+        info!("    Token vault: {}", token_vault);
+        info!("    Base vault: {}", base_vault);
+        info!("    Oracle: {}", whirlpool_oracle);
+        for (i, array) in tick_arrays.iter().enumerate() {
+            info!("    Tick Array {}: {}", i, array);
+        }
+        info!("");
+        Ok(())
+    }
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64> {
+        let state = WhirlpoolQuoteState::load(keys, snapshot)?;
+        let a_to_b = state.a_to_b(keys, direction);
+        Ok(state.quote_exact_in(a_to_b, amount_in)?.amount_out)
+    }
+
+    // 链上 swap 只接受 3 个 tick array，按交易方向从当前 array 开始排列
+    fn swap_keys(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<PoolKeys> {
+        let state = WhirlpoolQuoteState::load(keys, snapshot)?;
+        let a_to_b = state.a_to_b(keys, direction);
+        let result = state.quote_exact_in(a_to_b, amount_in)?;
+        Ok(PoolKeys {
+            extra_accounts: result.tick_arrays.to_vec(),
+            ..keys.clone()
+        })
+    }
+
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta> {
+        let mut accounts = readonly(&[whirlpool_program_id()]);
+        accounts.extend(writable(&[keys.pool, keys.token_vault, keys.base_vault]));
+        accounts.extend(writable(&keys.instruction_accounts));
+        accounts.extend(writable(&keys.extra_accounts));
+        accounts
+    }
+
+    fn refresh_arrays(
+        &self,
+        pool_data: &mut MintPoolData,
+        snapshot: &AccountSnapshot,
+        window: &ArrayWindow,
+    ) -> bool {
+        let offsets = ArrayWindow::offsets(window.whirlpool);
+        let mut changed = false;
+        for keys in pool_data.pools_of_mut(DexType::Whirlpool) {
+            let state = match snapshot.data(&keys.pool).and_then(WhirlpoolState::load) {
+                Ok(state) => state,
+                Err(e) => {
+                    warn!("Failed to refresh tick arrays of whirlpool pool {}: {}", keys.pool, e);
+                    continue;
+                }
+            };
+            let ticks_in_array = TICK_ARRAY_SIZE * state.tick_spacing as i32;
+            let start = tick_array_start_index(state.tick_current_index, state.tick_spacing);
+            let mut starts: Vec<i32> = offsets.iter().map(|offset| start + offset * ticks_in_array).collect();
+            // b -> a 方向的第一个 array 可能在当前 array 之后，两个方向 swap 需要的 array 都要在窗口内
+            let quote_state = WhirlpoolQuoteState {
+                address: keys.pool,
+                whirlpool: state,
+                tick_arrays: vec![],
+            };
+            for swap_start in quote_state
+                .swap_tick_array_starts(true)
+                .into_iter()
+                .chain(quote_state.swap_tick_array_starts(false))
+            {
+                if !starts.contains(&swap_start) {
+                    starts.push(swap_start);
+                }
+            }
+            starts.sort_unstable();
+            let tick_arrays: Vec<Pubkey> = starts
+                .iter()
+                .map(|start| tick_array_address(&keys.pool, *start))
+                .collect();
+            if tick_arrays != keys.extra_accounts {
+                info!("Whirlpool pool {} tick arrays moved", keys.pool);
+                keys.extra_accounts = tick_arrays;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
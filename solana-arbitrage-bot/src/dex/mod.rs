@@ -1,4 +1,145 @@
-pub mod pump;
-pub mod raydium;
-pub mod dlmm;
-pub mod whirlpool;
\ No newline at end of file
+pub mod adapters;
+pub mod pump;
+pub mod raydium;
+pub mod dlmm;
+pub mod whirlpool;
+
+use crate::pools::MintPoolData;
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{AccountSnapshot, PoolKeys, SwapDirection};
+use crate::window::ArrayWindow;
+use adapters::{DlmmDex, PumpDex, RaydiumAmmDex, RaydiumClmmDex, RaydiumCpDex, WhirlpoolDex};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DexType {
+    Pump,
+    RaydiumAmm,
+    RaydiumCp,
+    RaydiumClmm,
+    Whirlpool,
+    Dlmm,
+}
+
+impl fmt::Display for DexType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", adapter(*self).name())
+    }
+}
+
+// 每个 DEX 需要实现的接口，新增 DEX 只需要新增一个实现并加入 REGISTRY
+pub trait DexPool: Send + Sync {
+    fn dex_type(&self) -> DexType;
+
+    fn name(&self) -> &'static str;
+
+    // MintConfig.pools 中对应的键，如 raydium_pool_list
+    fn pool_list_key(&self) -> &'static str;
+
+    fn program_id(&self) -> Pubkey;
+
+    // 没有模拟结果时单次 swap 预估消耗的 CU，集中流动性池子按跨越多个 tick / bin 估计
+    fn default_compute_units(&self) -> u32;
+
+    // 池子不合法时是跳过还是中断整个初始化
+    fn skip_invalid_pool(&self) -> bool {
+        false
+    }
+
+    // 解析池子账户，确定 base/token vault，计算所需的附加账户，以 PoolKeys 的形式写入 MintPoolData
+    // extra_accounts / instruction_accounts 的排列由各 DEX 自己决定，swap_accounts 和 refresh_arrays 按同样的排列读取
+    fn add_pool(
+        &self,
+        pool_data: &mut MintPoolData,
+        pool: &Pubkey,
+        account: &Account,
+        base_mint: &Pubkey,
+    ) -> anyhow::Result<()>;
+
+    fn quote_exact_in(
+        &self,
+        keys: &PoolKeys,
+        snapshot: &AccountSnapshot,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<u64>;
+
+    // 某个方向、某个投入的 swap 实际使用的账户。报价时 keys 中是 array 窗口内的全部 array，
+    // 链上指令只接受固定数量、按交易方向排列的 array 时在这里选取；默认与报价相同
+    fn swap_keys(
+        &self,
+        keys: &PoolKeys,
+        _snapshot: &AccountSnapshot,
+        _direction: SwapDirection,
+        _amount_in: u64,
+    ) -> anyhow::Result<PoolKeys> {
+        Ok(keys.clone())
+    }
+
+    // 套利指令中该池子需要附加的账户，按链上程序要求的顺序排列；keys 为 swap_keys 的结果
+    fn swap_accounts(&self, keys: &PoolKeys) -> Vec<AccountMeta>;
+
+    // 按快照中池子当前的 tick / active bin 重新计算 tick array、bin array，有变化时返回 true
+    fn refresh_arrays(
+        &self,
+        _pool_data: &mut MintPoolData,
+        _snapshot: &AccountSnapshot,
+        _window: &ArrayWindow,
+    ) -> bool {
+        false
+    }
+
+    // 恒定乘积池子返回该方向的储备与费率，用于闭式求解最优投入；集中流动性池子返回 None
+    fn constant_product(
+        &self,
+        _keys: &PoolKeys,
+        _snapshot: &AccountSnapshot,
+        _direction: SwapDirection,
+    ) -> Option<ConstantProduct> {
+        None
+    }
+}
+
+static REGISTRY: [&dyn DexPool; 6] = [
+    &PumpDex,
+    &RaydiumAmmDex,
+    &RaydiumCpDex,
+    &DlmmDex,
+    &WhirlpoolDex,
+    &RaydiumClmmDex,
+];
+
+pub fn registry() -> &'static [&'static dyn DexPool] {
+    &REGISTRY
+}
+
+pub fn adapter(dex: DexType) -> &'static dyn DexPool {
+    *REGISTRY
+        .iter()
+        .find(|adapter| adapter.dex_type() == dex)
+        .expect("every DexType has a registered adapter")
+}
+
+// 所有 DEX 的池子
+pub fn collect_pool_keys(pool_data: &MintPoolData) -> Vec<PoolKeys> {
+    pool_data.pools.clone()
+}
+
+// 只保留指定池子的 MintPoolData 副本
+pub fn restrict_pools(pool_data: &MintPoolData, pools: &[Pubkey]) -> MintPoolData {
+    let mut restricted = pool_data.clone();
+    restricted.retain_pools(pools);
+    restricted
+}
+
+// 报价和订阅需要的全部账户
+pub fn collect_pool_accounts(pool_data: &MintPoolData) -> Vec<Pubkey> {
+    pool_data
+        .pools
+        .iter()
+        .flat_map(|keys| keys.accounts())
+        .collect()
+}
@@ -0,0 +1,401 @@
+use crate::accounts::{fetch_multiple_accounts, FetchPolicy};
+use crate::config::{Config, FlashLoanProtocol, FlashLoanProviderConfig};
+use crate::kamino::{reserve_address, KaminoReserve};
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::compute_budget;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+const PARTS_PER_MILLION: u64 = 1_000_000;
+const DEFAULT_LIQUIDITY_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+// 每个借贷协议需要实现的闪电贷接口，新增协议只需要新增一个实现并在 load_provider 中构造
+pub trait FlashLoanProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    // 可借入的代币
+    fn mint(&self) -> Pubkey;
+
+    // 保存可借数量的账户，后台定时拉取
+    fn liquidity_account(&self) -> Pubkey;
+
+    fn available_liquidity(&self, account: &Account) -> anyhow::Result<u64>;
+
+    // 借入 amount 需要支付的手续费
+    fn fee(&self, amount: u64) -> u64;
+
+    fn borrow_instruction(&self, wallet: &Pubkey, amount: u64) -> Instruction;
+
+    // borrow_instruction_index 是借款指令在交易中的位置
+    fn repay_instruction(
+        &self,
+        wallet: &Pubkey,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Instruction;
+
+    // 在交易指令的前后加上借款和还款：借款放在 compute budget 指令之后，还款放在最后
+    fn wrap_instructions(
+        &self,
+        wallet: &Pubkey,
+        amount: u64,
+        instructions: Vec<Instruction>,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        let borrow_index = instructions
+            .iter()
+            .take_while(|instruction| instruction.program_id == compute_budget::id())
+            .count();
+        let borrow_instruction_index = u8::try_from(borrow_index)
+            .map_err(|_| anyhow::anyhow!("Too many instructions before the flash loan"))?;
+
+        let mut wrapped = Vec::with_capacity(instructions.len() + 2);
+        let mut instructions = instructions.into_iter();
+        wrapped.extend(instructions.by_ref().take(borrow_index));
+        wrapped.push(self.borrow_instruction(wallet, amount));
+        wrapped.extend(instructions);
+        wrapped.push(self.repay_instruction(wallet, amount, borrow_instruction_index));
+        Ok(wrapped)
+    }
+}
+
+// 按手续费率收费，向上取整
+pub fn fee_from_ppm(amount: u64, fee_ppm: u64) -> u64 {
+    let fee = (amount as u128 * fee_ppm as u128).div_ceil(PARTS_PER_MILLION as u128);
+    fee.min(u64::MAX as u128) as u64
+}
+
+struct ProviderEntry {
+    provider: Arc<dyn FlashLoanProvider>,
+    available: AtomicU64,
+}
+
+// 按配置顺序排列的闪电贷来源，可借数量由后台任务刷新
+pub struct FlashLoanProviders {
+    entries: Vec<ProviderEntry>,
+}
+
+impl FlashLoanProviders {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // 能借出 amount 的来源中手续费最低的一个，手续费相同时按配置顺序优先
+    pub fn select(&self, mint: &Pubkey, amount: u64) -> Option<Arc<dyn FlashLoanProvider>> {
+        let mut best: Option<(&ProviderEntry, u64)> = None;
+        for entry in &self.entries {
+            if entry.provider.mint() != *mint || entry.available.load(Ordering::Relaxed) < amount {
+                continue;
+            }
+            let fee = entry.provider.fee(amount);
+            if best.map_or(true, |(_, best_fee)| fee < best_fee) {
+                best = Some((entry, fee));
+            }
+        }
+        best.map(|(entry, _)| entry.provider.clone())
+    }
+
+    pub async fn refresh(
+        &self,
+        rpc_client: &NonblockingRpcClient,
+        fetch_policy: &FetchPolicy,
+    ) -> anyhow::Result<()> {
+        let accounts: Vec<Pubkey> = self
+            .entries
+            .iter()
+            .map(|entry| entry.provider.liquidity_account())
+            .collect();
+        let fetched = fetch_multiple_accounts(rpc_client, &accounts, fetch_policy).await?;
+        for (entry, pubkey) in self.entries.iter().zip(&accounts) {
+            let Some(account) = fetched.get(pubkey) else {
+                error!("Failed to fetch liquidity account {} of {}", pubkey, entry.provider.name());
+                continue;
+            };
+            match entry.provider.available_liquidity(account) {
+                Ok(available) => {
+                    entry.available.store(available, Ordering::Relaxed);
+                    debug!("{} has {} available", entry.provider.name(), available);
+                }
+                Err(e) => error!("Failed to read liquidity of {}: {}", entry.provider.name(), e),
+            }
+        }
+        Ok(())
+    }
+}
+
+// [[flash_loan_providers]] 按优先级列出来源；不填时使用 [kamino_flashloan] 中各 base mint 的 reserve
+pub fn provider_configs(config: &Config, base_mints: &[Pubkey]) -> anyhow::Result<Vec<FlashLoanProviderConfig>> {
+    let Some(flashloan_config) = config
+        .kamino_flashloan
+        .as_ref()
+        .filter(|flashloan_config| flashloan_config.enabled)
+    else {
+        return Ok(Vec::new());
+    };
+    if let Some(providers) = &config.flash_loan_providers {
+        return Ok(providers
+            .iter()
+            .map(|provider_config| FlashLoanProviderConfig {
+                fee_ppm: provider_config.fee_ppm.or(flashloan_config.fee_ppm),
+                ..provider_config.clone()
+            })
+            .collect());
+    }
+
+    let mut configs: Vec<FlashLoanProviderConfig> = Vec::new();
+    for base_mint in base_mints {
+        let reserve = reserve_address(flashloan_config, base_mint)?.to_string();
+        if configs.iter().all(|provider_config| provider_config.reserve != reserve) {
+            configs.push(FlashLoanProviderConfig {
+                protocol: FlashLoanProtocol::Kamino,
+                reserve,
+                fee_ppm: flashloan_config.fee_ppm,
+            });
+        }
+    }
+    Ok(configs)
+}
+
+async fn load_provider(
+    rpc_client: &NonblockingRpcClient,
+    provider_config: &FlashLoanProviderConfig,
+) -> anyhow::Result<Arc<dyn FlashLoanProvider>> {
+    let reserve = Pubkey::from_str(&provider_config.reserve).map_err(|e| {
+        anyhow::anyhow!("Invalid flash loan reserve {}: {}", provider_config.reserve, e)
+    })?;
+    match provider_config.protocol {
+        FlashLoanProtocol::Kamino => {
+            let mut reserve = KaminoReserve::load(rpc_client, &reserve).await?;
+            if let Some(fee_ppm) = provider_config.fee_ppm {
+                reserve.fee_ppm = fee_ppm;
+            }
+            Ok(Arc::new(reserve))
+        }
+    }
+}
+
+// 启动时解析所有来源，并拉取一次可借数量
+pub async fn load_providers(
+    rpc_client: &NonblockingRpcClient,
+    fetch_policy: &FetchPolicy,
+    provider_configs: &[FlashLoanProviderConfig],
+) -> anyhow::Result<FlashLoanProviders> {
+    let mut entries = Vec::with_capacity(provider_configs.len());
+    for provider_config in provider_configs {
+        let provider = load_provider(rpc_client, provider_config).await?;
+        info!("Loaded flash loan provider {} for {}", provider.name(), provider.mint());
+        entries.push(ProviderEntry {
+            provider,
+            available: AtomicU64::new(0),
+        });
+    }
+    let providers = FlashLoanProviders { entries };
+    providers.refresh(rpc_client, fetch_policy).await?;
+    Ok(providers)
+}
+
+// 与 blockhash_refresher 类似，定时刷新各来源的可借数量
+pub fn spawn_liquidity_refresher(
+    rpc_client: Arc<NonblockingRpcClient>,
+    providers: Arc<FlashLoanProviders>,
+    fetch_policy: FetchPolicy,
+    refresh_interval: Option<Duration>,
+) -> JoinHandle<()> {
+    let refresh_interval = refresh_interval.unwrap_or(DEFAULT_LIQUIDITY_REFRESH_INTERVAL);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(refresh_interval).await;
+            if let Err(e) = providers.refresh(&rpc_client, &fetch_policy).await {
+                error!("Failed to refresh flash loan liquidity: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::sol_mint;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+    struct TestProvider {
+        name: &'static str,
+        mint: Pubkey,
+        fee_ppm: u64,
+    }
+
+    impl FlashLoanProvider for TestProvider {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn mint(&self) -> Pubkey {
+            self.mint
+        }
+
+        fn liquidity_account(&self) -> Pubkey {
+            Pubkey::default()
+        }
+
+        fn available_liquidity(&self, _account: &Account) -> anyhow::Result<u64> {
+            Ok(0)
+        }
+
+        fn fee(&self, amount: u64) -> u64 {
+            fee_from_ppm(amount, self.fee_ppm)
+        }
+
+        fn borrow_instruction(&self, _wallet: &Pubkey, amount: u64) -> Instruction {
+            Instruction::new_with_bytes(Pubkey::default(), &amount.to_le_bytes(), vec![])
+        }
+
+        fn repay_instruction(&self, _wallet: &Pubkey, _amount: u64, borrow_instruction_index: u8) -> Instruction {
+            Instruction::new_with_bytes(Pubkey::default(), &[borrow_instruction_index], vec![])
+        }
+    }
+
+    fn providers(entries: &[(&'static str, Pubkey, u64, u64)]) -> FlashLoanProviders {
+        FlashLoanProviders {
+            entries: entries
+                .iter()
+                .map(|(name, mint, fee_ppm, available)| ProviderEntry {
+                    provider: Arc::new(TestProvider {
+                        name,
+                        mint: *mint,
+                        fee_ppm: *fee_ppm,
+                    }),
+                    available: AtomicU64::new(*available),
+                })
+                .collect(),
+        }
+    }
+
+    fn config(extra: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+[bot]
+compute_unit_limit = 200000
+
+[rpc]
+url = "http://127.0.0.1:8899"
+
+[wallet]
+private_key = "test-key"
+
+[routing]
+mint_config_list = []
+{}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn fee_from_ppm_rounds_up() {
+        assert_eq!(fee_from_ppm(1_000_000_000, 10), 10_000);
+        assert_eq!(fee_from_ppm(1, 10), 1);
+        assert_eq!(fee_from_ppm(0, 10), 0);
+        assert_eq!(fee_from_ppm(u64::MAX, 2_000_000), u64::MAX);
+    }
+
+    #[test]
+    fn select_prefers_the_cheapest_provider_with_enough_liquidity() {
+        let usdc = Pubkey::new_unique();
+        let providers = providers(&[
+            ("expensive", sol_mint(), 50, 10_000_000_000),
+            ("cheap but small", sol_mint(), 5, 1_000_000_000),
+            ("other mint", usdc, 0, 10_000_000_000),
+            ("cheap", sol_mint(), 9, 10_000_000_000),
+            ("cheap too", sol_mint(), 9, 10_000_000_000),
+        ]);
+        let name = |mint: &Pubkey, amount: u64| providers.select(mint, amount).map(|provider| provider.name());
+
+        assert_eq!(name(&sol_mint(), 1_000_000_000).as_deref(), Some("cheap but small"));
+        // 手续费相同时按配置顺序
+        assert_eq!(name(&sol_mint(), 2_000_000_000).as_deref(), Some("cheap"));
+        assert_eq!(name(&usdc, 2_000_000_000).as_deref(), Some("other mint"));
+        assert_eq!(name(&sol_mint(), 20_000_000_000), None);
+        assert_eq!(name(&Pubkey::new_unique(), 1), None);
+    }
+
+    #[test]
+    fn wrap_instructions_borrows_after_the_compute_budget() {
+        let provider = TestProvider {
+            name: "test",
+            mint: sol_mint(),
+            fee_ppm: 0,
+        };
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[7], vec![]);
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            swap.clone(),
+        ];
+        let wrapped = provider.wrap_instructions(&Pubkey::new_unique(), 42, instructions.clone()).unwrap();
+
+        assert_eq!(wrapped.len(), 5);
+        assert_eq!(wrapped[..2], instructions[..2]);
+        assert_eq!(wrapped[2], provider.borrow_instruction(&Pubkey::default(), 42));
+        assert_eq!(wrapped[3], swap);
+        // 还款指令记录借款指令的位置
+        assert_eq!(wrapped[4].data, vec![2]);
+    }
+
+    #[test]
+    fn provider_configs_fall_back_to_kamino_reserves() {
+        assert!(provider_configs(&config(""), &[sol_mint()]).unwrap().is_empty());
+        let disabled = config("[kamino_flashloan]\nenabled = false");
+        assert!(provider_configs(&disabled, &[sol_mint()]).unwrap().is_empty());
+
+        let usdc = Pubkey::new_unique();
+        let reserve = Pubkey::new_unique();
+        let config = config(&format!(
+            "[kamino_flashloan]\nenabled = true\nfee_ppm = 20\nreserves = {{ {} = \"{}\" }}",
+            usdc, reserve
+        ));
+        let configs = provider_configs(&config, &[sol_mint(), usdc, sol_mint()]).unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].reserve, reserve.to_string());
+        for provider_config in &configs {
+            assert_eq!(provider_config.protocol, FlashLoanProtocol::Kamino);
+            assert_eq!(provider_config.fee_ppm, Some(20));
+        }
+        // 没有配置 reserve 的 base mint
+        assert!(provider_configs(&config, &[Pubkey::new_unique()]).is_err());
+    }
+
+    #[test]
+    fn explicit_providers_keep_their_order_and_inherit_the_fee() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let config = config(&format!(
+            r#"
+[kamino_flashloan]
+enabled = true
+fee_ppm = 20
+
+[[flash_loan_providers]]
+protocol = "kamino"
+reserve = "{}"
+fee_ppm = 5
+
+[[flash_loan_providers]]
+protocol = "kamino"
+reserve = "{}"
+"#,
+            first, second
+        ));
+        let configs = provider_configs(&config, &[Pubkey::new_unique()]).unwrap();
+        let configs: Vec<(String, Option<u64>)> = configs
+            .into_iter()
+            .map(|provider_config| (provider_config.reserve, provider_config.fee_ppm))
+            .collect();
+        assert_eq!(configs, vec![(first.to_string(), Some(5)), (second.to_string(), Some(20))]);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::config::JitoConfig;
+use futures::future::select_ok;
+use futures::FutureExt;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const BUNDLES_PATH: &str = "/api/v1/bundles";
+const DEFAULT_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// bundle 只在 blockhash 有效期内可能上链，超过后视为丢弃
+const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(30);
+// block engine 单个 bundle 最多 5 笔交易
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+// Jito 主网的小费账户，每次随机选一个以减少写锁竞争
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+pub fn random_tip_account() -> Pubkey {
+    let tip_account = JITO_TIP_ACCOUNTS
+        .choose(&mut rand::thread_rng())
+        .expect("tip account list is not empty");
+    Pubkey::from_str(tip_account).unwrap()
+}
+
+// 固定小费，或按预期净利润的百分比给小费（不低于固定小费，不超过上限）
+pub fn tip_amount(jito_config: &JitoConfig, expected_profit: Option<i64>) -> u64 {
+    let tip = match (jito_config.tip_percent, expected_profit) {
+        (Some(percent), Some(profit)) if profit > 0 => {
+            (profit as u64 * percent / 100).max(jito_config.tip_lamports)
+        }
+        _ => jito_config.tip_lamports,
+    };
+    jito_config.max_tip_lamports.map_or(tip, |max_tip| tip.min(max_tip))
+}
+
+// bundle 中单独的小费转账交易，放在套利交易之后，套利失败时整个 bundle 不会上链
+pub fn build_tip_transaction(payer: &Keypair, lamports: u64, blockhash: Hash) -> VersionedTransaction {
+    let instruction = system_instruction::transfer(&payer.pubkey(), &random_tip_account(), lamports);
+    let transaction =
+        Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+    VersionedTransaction::from(transaction)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleOutcome {
+    Landed { slot: u64 },
+    Dropped { reason: String },
+}
+
+impl fmt::Display for BundleOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleOutcome::Landed { slot } => write!(f, "landed at slot {}", slot),
+            BundleOutcome::Dropped { reason } => write!(f, "dropped ({})", reason),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InflightBundleStatuses {
+    value: Vec<InflightBundleStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InflightBundleStatus {
+    pub bundle_id: String,
+    // Invalid / Pending / Failed / Landed
+    pub status: String,
+    pub landed_slot: Option<u64>,
+}
+
+// block engine 的 JSON-RPC 客户端，endpoint 可以指向本地的 mock 服务器
+pub struct JitoClient {
+    http: reqwest::Client,
+    endpoints: Vec<String>,
+    uuid: Option<String>,
+}
+
+impl JitoClient {
+    pub fn new(endpoints: Vec<String>, uuid: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoints,
+            uuid,
+        }
+    }
+
+    fn bundles_url(endpoint: &str) -> String {
+        format!("{}{}", endpoint.trim_end_matches('/'), BUNDLES_PATH)
+    }
+
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        endpoint: &str,
+        method: &str,
+        params: Value,
+    ) -> anyhow::Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let mut request = self.http.post(Self::bundles_url(endpoint)).json(&body);
+        if let Some(uuid) = &self.uuid {
+            request = request.header("x-jito-auth", uuid);
+        }
+
+        let response: JsonRpcResponse<T> = request.send().await?.error_for_status()?.json().await?;
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("{} to {} failed: {}", method, endpoint, error));
+        }
+        response
+            .result
+            .ok_or_else(|| anyhow::anyhow!("{} to {} returned no result", method, endpoint))
+    }
+
+    // 同时发送到所有 block engine，任一成功即返回 bundle id
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> anyhow::Result<String> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(anyhow::anyhow!(
+                "Bundle must contain 1 to {} transactions, got {}",
+                MAX_BUNDLE_TRANSACTIONS,
+                transactions.len()
+            ));
+        }
+        let encoded = transactions
+            .iter()
+            .map(|transaction| Ok(bs58::encode(bincode::serialize(transaction)?).into_string()))
+            .collect::<anyhow::Result<Vec<String>>>()?;
+        let params = json!([encoded]);
+
+        let requests = self.endpoints.iter().map(|endpoint| {
+            let params = params.clone();
+            async move {
+                self.call::<String>(endpoint, "sendBundle", params)
+                    .await
+                    .map_err(|e| {
+                        warn!("Failed to send bundle to {}: {}", endpoint, e);
+                        e
+                    })
+            }
+            .boxed()
+        });
+        let (bundle_id, _) = select_ok(requests)
+            .await
+            .map_err(|e| anyhow::anyhow!("No block engine accepted the bundle, last error: {}", e))?;
+        Ok(bundle_id)
+    }
+
+    // 查询 bundle 当前状态，未知的 bundle 返回 None
+    pub async fn bundle_status(&self, bundle_id: &str) -> anyhow::Result<Option<InflightBundleStatus>> {
+        let endpoint = self
+            .endpoints
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No block engine endpoint configured"))?;
+        let statuses: InflightBundleStatuses = self
+            .call(endpoint, "getInflightBundleStatuses", json!([[bundle_id]]))
+            .await?;
+        Ok(statuses
+            .value
+            .into_iter()
+            .find(|status| status.bundle_id == bundle_id))
+    }
+
+    // 轮询直到 bundle 上链、失败或超时
+    pub async fn wait_for_bundle(
+        &self,
+        bundle_id: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> BundleOutcome {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            tokio::time::sleep(poll_interval).await;
+            match self.bundle_status(bundle_id).await {
+                Ok(Some(status)) => match status.status.as_str() {
+                    "Landed" => {
+                        return BundleOutcome::Landed {
+                            slot: status.landed_slot.unwrap_or_default(),
+                        }
+                    }
+                    "Failed" | "Invalid" => {
+                        return BundleOutcome::Dropped {
+                            reason: status.status.to_lowercase(),
+                        }
+                    }
+                    _ => {}
+                },
+                Ok(None) => {}
+                Err(e) => debug!("Failed to poll bundle {}: {}", bundle_id, e),
+            }
+        }
+        BundleOutcome::Dropped {
+            reason: format!("not landed within {:?}", timeout),
+        }
+    }
+}
+
+// 把套利交易和小费转账打包成 bundle 发送，并在后台报告上链结果
+pub struct JitoSender {
+    client: JitoClient,
+    config: JitoConfig,
+}
+
+impl JitoSender {
+    pub fn new(jito_config: &JitoConfig) -> Self {
+        Self {
+            client: JitoClient::new(jito_config.block_engine_urls.clone(), jito_config.uuid.clone()),
+            config: jito_config.clone(),
+        }
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.config
+            .status_poll_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_STATUS_POLL_INTERVAL)
+    }
+
+    fn status_timeout(&self) -> Duration {
+        self.config
+            .status_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_STATUS_TIMEOUT)
+    }
+
+    // 发送 [套利交易, 小费交易]，返回 bundle id；tip 由调用方按 tip_amount 计算，与报价计入的成本一致
+    pub async fn send(
+        &self,
+        payer: &Keypair,
+        transaction: VersionedTransaction,
+        tip: u64,
+        blockhash: Hash,
+    ) -> anyhow::Result<String> {
+        let tip_transaction = build_tip_transaction(payer, tip, blockhash);
+        let bundle_id = self.client.send_bundle(&[transaction, tip_transaction]).await?;
+        info!("Bundle {} sent with {} lamports tip", bundle_id, tip);
+        Ok(bundle_id)
+    }
+
+    // 发送并等待结果
+    pub async fn send_and_wait(
+        &self,
+        payer: &Keypair,
+        transaction: VersionedTransaction,
+        tip: u64,
+        blockhash: Hash,
+    ) -> anyhow::Result<BundleOutcome> {
+        let bundle_id = self.send(payer, transaction, tip, blockhash).await?;
+        let outcome = self
+            .client
+            .wait_for_bundle(&bundle_id, self.poll_interval(), self.status_timeout())
+            .await;
+        match &outcome {
+            BundleOutcome::Landed { .. } => info!("Bundle {} {}", bundle_id, outcome),
+            BundleOutcome::Dropped { .. } => warn!("Bundle {} {}", bundle_id, outcome),
+        }
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::system_instruction::SystemInstruction;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    const BUNDLE_ID: &str = "bundle-1";
+    const LANDED_SLOT: u64 = 42;
+
+    fn jito_config(url: String) -> JitoConfig {
+        JitoConfig {
+            enabled: true,
+            block_engine_urls: vec![url],
+            uuid: Some("test-uuid".to_string()),
+            tip_lamports: 10_000,
+            tip_percent: Some(50),
+            max_tip_lamports: Some(1_000_000),
+            status_poll_interval_ms: Some(10),
+            status_timeout_ms: Some(5_000),
+        }
+    }
+
+    // 读取一个 HTTP 请求，返回小写的请求头和 JSON body
+    async fn read_request(stream: &mut TcpStream) -> (String, Value) {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed before the request was complete");
+            buffer.extend_from_slice(&chunk[..read]);
+            let Some(header_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+                continue;
+            };
+            let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+            let content_length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |value| value.trim().parse::<usize>().unwrap());
+            let body_start = header_end + 4;
+            if buffer.len() >= body_start + content_length {
+                let body = serde_json::from_slice(&buffer[body_start..body_start + content_length]).unwrap();
+                return (headers, body);
+            }
+        }
+    }
+
+    // 本地 mock block engine：sendBundle 返回固定的 bundle id，
+    // 第一次状态查询返回 Pending，之后返回 final_status；收到的请求转发给测试
+    async fn spawn_block_engine(
+        final_status: &'static str,
+    ) -> (String, mpsc::UnboundedReceiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut polls = 0;
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let (headers, request) = read_request(&mut stream).await;
+                let result = match request["method"].as_str().unwrap() {
+                    "sendBundle" => json!(BUNDLE_ID),
+                    "getInflightBundleStatuses" => {
+                        polls += 1;
+                        let status = if polls == 1 { "Pending" } else { final_status };
+                        json!({
+                            "context": { "slot": 100 },
+                            "value": [{
+                                "bundle_id": BUNDLE_ID,
+                                "status": status,
+                                "landed_slot": (status == "Landed").then_some(LANDED_SLOT),
+                            }],
+                        })
+                    }
+                    method => panic!("unexpected method {}", method),
+                };
+                let _ = sender.send((headers, request));
+                let body = json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    fn transfer_lamports(transaction: &VersionedTransaction) -> u64 {
+        let instruction = &transaction.message.instructions()[0];
+        match bincode::deserialize(&instruction.data).unwrap() {
+            SystemInstruction::Transfer { lamports } => lamports,
+            other => panic!("unexpected instruction {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tip_is_a_share_of_profit_within_the_floor_and_cap() {
+        let config = jito_config(String::new());
+        assert_eq!(tip_amount(&config, None), 10_000);
+        assert_eq!(tip_amount(&config, Some(-5_000)), 10_000);
+        assert_eq!(tip_amount(&config, Some(8_000)), 10_000);
+        assert_eq!(tip_amount(&config, Some(100_000)), 50_000);
+        assert_eq!(tip_amount(&config, Some(10_000_000)), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn send_and_wait_reports_a_landed_bundle() {
+        let (url, mut requests) = spawn_block_engine("Landed").await;
+        let sender = JitoSender::new(&jito_config(url));
+        let payer = Keypair::new();
+        let blockhash = Hash::new_unique();
+        let transaction = build_tip_transaction(&payer, 1, blockhash);
+
+        let outcome = sender
+            .send_and_wait(&payer, transaction.clone(), 25_000, blockhash)
+            .await
+            .unwrap();
+        assert_eq!(outcome, BundleOutcome::Landed { slot: LANDED_SLOT });
+
+        let (headers, request) = requests.recv().await.unwrap();
+        assert!(headers.contains("x-jito-auth: test-uuid"));
+        assert_eq!(request["method"], "sendBundle");
+        let bundle: Vec<VersionedTransaction> = request["params"][0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|encoded| {
+                let bytes = bs58::decode(encoded.as_str().unwrap()).into_vec().unwrap();
+                bincode::deserialize(&bytes).unwrap()
+            })
+            .collect();
+        assert_eq!(bundle.len(), 2);
+        // 套利交易在前，小费交易在后
+        assert_eq!(bundle[0].signatures, transaction.signatures);
+        assert_eq!(transfer_lamports(&bundle[1]), 25_000);
+        assert_eq!(bundle[1].message.recent_blockhash(), &blockhash);
+
+        let (_, poll) = requests.recv().await.unwrap();
+        assert_eq!(poll["method"], "getInflightBundleStatuses");
+        assert_eq!(poll["params"], json!([[BUNDLE_ID]]));
+    }
+
+    #[tokio::test]
+    async fn send_and_wait_reports_a_failed_bundle_as_dropped() {
+        let (url, _requests) = spawn_block_engine("Failed").await;
+        let sender = JitoSender::new(&jito_config(url));
+        let payer = Keypair::new();
+        let blockhash = Hash::new_unique();
+        let transaction = build_tip_transaction(&payer, 1, blockhash);
+
+        let outcome = sender
+            .send_and_wait(&payer, transaction, 10_000, blockhash)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            BundleOutcome::Dropped {
+                reason: "failed".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use crate::confirmation::OutcomeRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// 报价选出的、达到利润阈值的机会
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpportunityEntry {
+    pub timestamp: u64,
+    pub mint: String,
+    pub base_mint: String,
+    pub route: String,
+    pub pools: Vec<String>,
+    pub dexes: Vec<String>,
+    pub amount_in: u64,
+    pub expected_profit: i64,
+    pub slot: u64,
+}
+
+// 已发送的交易，同一个机会发往多个 RPC 时每个签名一条
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentEntry {
+    pub timestamp: u64,
+    pub signature: String,
+    pub mint: String,
+    pub route: String,
+    // 接受了交易的 RPC，多个时以逗号分隔
+    pub rpc: String,
+    pub amount_in: Option<u64>,
+    pub expected_profit: Option<i64>,
+    pub tip: Option<u64>,
+    pub compute_unit_limit: Option<u32>,
+    pub compute_unit_price: Option<u64>,
+    pub slot: Option<u64>,
+}
+
+// 确认结果，fee / CU / 实际利润来自上链交易的 meta
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutcomeEntry {
+    pub timestamp: u64,
+    pub signature: String,
+    pub mint: String,
+    pub route: String,
+    pub rpc: String,
+    pub status: String,
+    pub slot: Option<u64>,
+    pub expected_profit: Option<i64>,
+    pub realized_profit: Option<i64>,
+    pub fee: Option<u64>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+impl OutcomeEntry {
+    pub fn from_record(record: &OutcomeRecord) -> Self {
+        let transaction = &record.transaction;
+        let details = record.outcome.details();
+        Self {
+            timestamp: unix_timestamp(),
+            signature: transaction.signature.to_string(),
+            mint: transaction.mint.clone(),
+            route: transaction.route.clone(),
+            rpc: transaction.rpcs.join(","),
+            status: record.outcome.status().to_string(),
+            slot: record.outcome.slot(),
+            expected_profit: transaction.expected_profit,
+            realized_profit: details.map(|details| details.profit),
+            fee: details.map(|details| details.fee),
+            compute_units_consumed: details.and_then(|details| details.compute_units_consumed),
+        }
+    }
+}
+
+// JSONL 中的一行，kind 字段区分类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Opportunity(OpportunityEntry),
+    Sent(SentEntry),
+    Outcome(OutcomeEntry),
+}
+
+// 追加写入 JSONL 文件，写文件在后台任务中进行，不阻塞报价和发送
+#[derive(Clone)]
+pub struct Journal {
+    sender: mpsc::UnboundedSender<JournalEntry>,
+}
+
+impl Journal {
+    pub async fn open(path: &str) -> anyhow::Result<(Self, JoinHandle<()>)> {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open journal {}: {}", path, e))?;
+        let (sender, mut receiver) = mpsc::unbounded_channel::<JournalEntry>();
+        let path = path.to_string();
+
+        let handle = tokio::spawn(async move {
+            while let Some(entry) = receiver.recv().await {
+                let mut line = match serde_json::to_string(&entry) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        error!("Failed to serialize journal entry: {}", e);
+                        continue;
+                    }
+                };
+                line.push('\n');
+                if let Err(e) = file.write_all(line.as_bytes()).await {
+                    error!("Failed to write journal {}: {}", path, e);
+                }
+            }
+            let _ = file.flush().await;
+        });
+        Ok((Self { sender }, handle))
+    }
+
+    pub fn record(&self, entry: JournalEntry) {
+        if self.sender.send(entry).is_err() {
+            warn!("Journal writer has stopped, entry dropped");
+        }
+    }
+}
+
+// 读取 journal，无法解析的行跳过
+pub fn read_entries(path: &str) -> anyhow::Result<Vec<JournalEntry>> {
+    let file =
+        File::open(path).map_err(|e| anyhow::anyhow!("Failed to open journal {}: {}", path, e))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping journal line {}: {}", index + 1, e),
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PnlSummary {
+    pub opportunities: u64,
+    pub sent: u64,
+    pub landed: u64,
+    pub failed: u64,
+    pub expired: u64,
+    // 只统计已确认交易的预期利润，便于和实际利润对比
+    pub expected_profit: i64,
+    pub realized_profit: i64,
+    pub fees: u64,
+}
+
+impl PnlSummary {
+    fn add(&mut self, entry: &JournalEntry) {
+        match entry {
+            JournalEntry::Opportunity(_) => self.opportunities += 1,
+            JournalEntry::Sent(_) => self.sent += 1,
+            JournalEntry::Outcome(outcome) => {
+                match outcome.status.as_str() {
+                    "landed" => self.landed += 1,
+                    "failed" => self.failed += 1,
+                    // expired 和 dropped：没有上链
+                    _ => self.expired += 1,
+                }
+                if !matches!(outcome.status.as_str(), "expired" | "dropped") {
+                    self.expected_profit += outcome.expected_profit.unwrap_or(0);
+                }
+                self.realized_profit += outcome.realized_profit.unwrap_or(0);
+                self.fees += outcome.fee.unwrap_or(0);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub by_mint: BTreeMap<String, PnlSummary>,
+    pub by_route: BTreeMap<String, PnlSummary>,
+    pub by_day: BTreeMap<String, PnlSummary>,
+}
+
+// 把自 1970-01-01 起的天数换算成 UTC 日期
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = (if month_index < 10 { month_index + 3 } else { month_index - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn utc_day(timestamp: u64) -> String {
+    let (year, month, day) = civil_date((timestamp / SECONDS_PER_DAY) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+impl Report {
+    pub fn from_entries(entries: &[JournalEntry]) -> Self {
+        let mut report = Self::default();
+        for entry in entries {
+            let (timestamp, mint, route) = match entry {
+                JournalEntry::Opportunity(entry) => (entry.timestamp, &entry.mint, &entry.route),
+                JournalEntry::Sent(entry) => (entry.timestamp, &entry.mint, &entry.route),
+                JournalEntry::Outcome(entry) => (entry.timestamp, &entry.mint, &entry.route),
+            };
+            report.by_mint.entry(mint.clone()).or_default().add(entry);
+            report.by_route.entry(route.clone()).or_default().add(entry);
+            report.by_day.entry(utc_day(timestamp)).or_default().add(entry);
+        }
+        report
+    }
+
+    fn print_section(title: &str, rows: &BTreeMap<String, PnlSummary>) {
+        println!("== {} ==", title);
+        for (key, summary) in rows {
+            println!(
+                "{}: opportunities {}, sent {}, landed {}, failed {}, expired {}, expected {}, realized {}, fees {}",
+                key,
+                summary.opportunities,
+                summary.sent,
+                summary.landed,
+                summary.failed,
+                summary.expired,
+                summary.expected_profit,
+                summary.realized_profit,
+                summary.fees
+            );
+        }
+        println!();
+    }
+
+    pub fn print(&self) {
+        Self::print_section("PnL per mint", &self.by_mint);
+        Self::print_section("PnL per route", &self.by_route);
+        Self::print_section("PnL per day (UTC)", &self.by_day);
+    }
+}
+
+// report 子命令入口
+pub fn print_report(path: &str) -> anyhow::Result<()> {
+    let entries = read_entries(path)?;
+    Report::from_entries(&entries).print();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2023-11-14 22:13:20 UTC
+    const NOW: u64 = 1_700_000_000;
+
+    fn opportunity(timestamp: u64, mint: &str, route: &str) -> JournalEntry {
+        JournalEntry::Opportunity(OpportunityEntry {
+            timestamp,
+            mint: mint.to_string(),
+            base_mint: "So11111111111111111111111111111111111111112".to_string(),
+            route: route.to_string(),
+            pools: vec![],
+            dexes: vec![],
+            amount_in: 1_000_000_000,
+            expected_profit: 20_000,
+            slot: 1,
+        })
+    }
+
+    fn sent(timestamp: u64, mint: &str, route: &str) -> JournalEntry {
+        JournalEntry::Sent(SentEntry {
+            timestamp,
+            signature: "signature".to_string(),
+            mint: mint.to_string(),
+            route: route.to_string(),
+            rpc: "rpc-a".to_string(),
+            amount_in: Some(1_000_000_000),
+            expected_profit: Some(20_000),
+            tip: None,
+            compute_unit_limit: Some(200_000),
+            compute_unit_price: Some(1_000),
+            slot: None,
+        })
+    }
+
+    fn outcome(timestamp: u64, mint: &str, route: &str, status: &str, realized_profit: Option<i64>) -> JournalEntry {
+        let landed = matches!(status, "landed" | "failed");
+        JournalEntry::Outcome(OutcomeEntry {
+            timestamp,
+            signature: "signature".to_string(),
+            mint: mint.to_string(),
+            route: route.to_string(),
+            rpc: "rpc-a".to_string(),
+            status: status.to_string(),
+            slot: landed.then_some(1),
+            expected_profit: Some(20_000),
+            realized_profit,
+            fee: landed.then_some(5_000),
+            compute_units_consumed: None,
+        })
+    }
+
+    #[test]
+    fn utc_day_formats_unix_timestamps() {
+        assert_eq!(utc_day(0), "1970-01-01");
+        assert_eq!(utc_day(951_782_400), "2000-02-29");
+        assert_eq!(utc_day(NOW), "2023-11-14");
+        assert_eq!(utc_day(1_704_067_199), "2023-12-31");
+        assert_eq!(utc_day(1_704_067_200), "2024-01-01");
+    }
+
+    #[test]
+    fn report_aggregates_by_mint_route_and_day() {
+        let entries = vec![
+            opportunity(NOW, "mint-a", "route-1"),
+            sent(NOW, "mint-a", "route-1"),
+            outcome(NOW, "mint-a", "route-1", "landed", Some(18_000)),
+            opportunity(NOW, "mint-a", "route-2"),
+            sent(NOW, "mint-a", "route-2"),
+            outcome(NOW, "mint-a", "route-2", "failed", Some(-5_000)),
+            opportunity(NOW + SECONDS_PER_DAY, "mint-b", "route-1"),
+            sent(NOW + SECONDS_PER_DAY, "mint-b", "route-1"),
+            outcome(NOW + SECONDS_PER_DAY, "mint-b", "route-1", "expired", None),
+            outcome(NOW + SECONDS_PER_DAY, "mint-b", "route-1", "dropped", None),
+        ];
+        let report = Report::from_entries(&entries);
+
+        let mint_a = report.by_mint["mint-a"];
+        assert_eq!((mint_a.opportunities, mint_a.sent, mint_a.landed, mint_a.failed), (2, 2, 1, 1));
+        // 失败的交易同样计入预期利润和手续费
+        assert_eq!(mint_a.expected_profit, 40_000);
+        assert_eq!(mint_a.realized_profit, 13_000);
+        assert_eq!(mint_a.fees, 10_000);
+
+        // 没有上链的交易不计入预期利润
+        let mint_b = report.by_mint["mint-b"];
+        assert_eq!((mint_b.sent, mint_b.landed, mint_b.expired), (1, 0, 2));
+        assert_eq!((mint_b.expected_profit, mint_b.realized_profit, mint_b.fees), (0, 0, 0));
+
+        let route_1 = report.by_route["route-1"];
+        assert_eq!((route_1.opportunities, route_1.landed, route_1.expired), (2, 1, 2));
+        assert_eq!(report.by_route["route-2"].failed, 1);
+
+        assert_eq!(report.by_day.keys().collect::<Vec<_>>(), vec!["2023-11-14", "2023-11-15"]);
+        assert_eq!(report.by_day["2023-11-14"].realized_profit, 13_000);
+        assert_eq!(report.by_day["2023-11-15"].expired, 2);
+    }
+
+    #[test]
+    fn read_entries_skips_unparseable_lines() {
+        let path = std::env::temp_dir().join(format!("journal-test-{}.jsonl", std::process::id()));
+        let lines = [
+            serde_json::to_string(&opportunity(NOW, "mint-a", "route-1")).unwrap(),
+            String::new(),
+            "{\"kind\":\"unknown\"}".to_string(),
+            "not json".to_string(),
+            serde_json::to_string(&outcome(NOW, "mint-a", "route-1", "landed", Some(1))).unwrap(),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let entries = read_entries(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0], JournalEntry::Opportunity(_)));
+        assert!(matches!(&entries[1], JournalEntry::Outcome(outcome) if outcome.status == "landed"));
+        assert!(read_entries(path.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use crate::config::KaminoFlashloanConfig;
+use crate::constants::sol_mint;
+use crate::flash_loan::{fee_from_ppm, FlashLoanProvider};
+use crate::quote::{read_pubkey, read_u64};
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::str::FromStr;
+
+pub const KLEND_PROGRAM_ID: &str = "KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD";
+// 闪电贷默认手续费率 0.001%，以 reserve 配置为准
+pub const DEFAULT_FEE_PPM: u64 = 10;
+// 主市场的 SOL reserve
+const DEFAULT_SOL_RESERVE: &str = "d4A2prbA2whesmvHaL88BH6Ewn5N4bTSU2Ze8P6Bc4Q";
+
+// anchor 指令的 discriminator：sha256("global:<name>") 的前 8 字节
+const FLASH_BORROW_DISCRIMINATOR: [u8; 8] = [135, 231, 52, 167, 7, 52, 212, 193];
+const FLASH_REPAY_DISCRIMINATOR: [u8; 8] = [185, 117, 0, 203, 96, 245, 180, 186];
+
+// Reserve 账户的偏移：discriminator(8) + version(8) + last_update(16)
+const RESERVE_LENDING_MARKET_OFFSET: usize = 32;
+// ReserveLiquidity 从 128 开始：mint、supply_vault、fee_vault、available_amount
+const RESERVE_LIQUIDITY_MINT_OFFSET: usize = 128;
+const RESERVE_SUPPLY_VAULT_OFFSET: usize = 160;
+const RESERVE_FEE_VAULT_OFFSET: usize = 192;
+const RESERVE_AVAILABLE_AMOUNT_OFFSET: usize = 224;
+
+pub fn klend_program_id() -> Pubkey {
+    Pubkey::from_str(KLEND_PROGRAM_ID).unwrap()
+}
+
+// 借入 base token 所需的 reserve 及相关账户，启动时解析一次
+#[derive(Debug, Clone)]
+pub struct KaminoReserve {
+    pub reserve: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+    pub liquidity_mint: Pubkey,
+    pub supply_vault: Pubkey,
+    pub fee_vault: Pubkey,
+    pub token_program: Pubkey,
+    // 手续费率（百万分之几）
+    pub fee_ppm: u64,
+}
+
+impl KaminoReserve {
+    pub async fn load(rpc_client: &NonblockingRpcClient, reserve: &Pubkey) -> anyhow::Result<Self> {
+        let account = rpc_client
+            .get_account(reserve)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch Kamino reserve {}: {}", reserve, e))?;
+        if account.owner != klend_program_id() {
+            return Err(anyhow::anyhow!(
+                "Account {} is not a Kamino reserve (owner {})",
+                reserve,
+                account.owner
+            ));
+        }
+        let data = &account.data;
+        let lending_market = read_pubkey(data, RESERVE_LENDING_MARKET_OFFSET)?;
+        let liquidity_mint = read_pubkey(data, RESERVE_LIQUIDITY_MINT_OFFSET)?;
+        // mint 账户的 owner 即 token program，兼容 Token-2022
+        let token_program = rpc_client
+            .get_account(&liquidity_mint)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch reserve mint {}: {}", liquidity_mint, e))?
+            .owner;
+
+        Ok(Self {
+            reserve: *reserve,
+            lending_market,
+            lending_market_authority: lending_market_authority(&lending_market),
+            liquidity_mint,
+            supply_vault: read_pubkey(data, RESERVE_SUPPLY_VAULT_OFFSET)?,
+            fee_vault: read_pubkey(data, RESERVE_FEE_VAULT_OFFSET)?,
+            token_program,
+            fee_ppm: DEFAULT_FEE_PPM,
+        })
+    }
+
+    // 钱包接收借款、归还借款的 token 账户
+    pub fn user_token_account(&self, wallet: &Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            wallet,
+            &self.liquidity_mint,
+            &self.token_program,
+        )
+    }
+
+    // 借入和归还的账户列表相同，只是 supply vault 的方向不同
+    fn accounts(&self, wallet: &Pubkey) -> Vec<AccountMeta> {
+        let program_id = klend_program_id();
+        vec![
+            AccountMeta::new_readonly(*wallet, true),
+            AccountMeta::new_readonly(self.lending_market_authority, false),
+            AccountMeta::new_readonly(self.lending_market, false),
+            AccountMeta::new(self.reserve, false),
+            AccountMeta::new_readonly(self.liquidity_mint, false),
+            AccountMeta::new(self.supply_vault, false),
+            AccountMeta::new(self.user_token_account(wallet), false),
+            AccountMeta::new(self.fee_vault, false),
+            // 不使用 referrer，可选账户按 anchor 的约定传程序地址
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new_readonly(program_id, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ]
+    }
+
+    pub fn flash_borrow_instruction(&self, wallet: &Pubkey, amount: u64) -> Instruction {
+        let mut data = FLASH_BORROW_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction {
+            program_id: klend_program_id(),
+            accounts: self.accounts(wallet),
+            data,
+        }
+    }
+
+    // amount 为借入的数量，手续费由程序另外转入 fee vault；
+    // borrow_instruction_index 是借款指令在交易中的位置，程序据此校验借还配对
+    pub fn flash_repay_instruction(
+        &self,
+        wallet: &Pubkey,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Instruction {
+        let mut data = FLASH_REPAY_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(borrow_instruction_index);
+        Instruction {
+            program_id: klend_program_id(),
+            accounts: self.accounts(wallet),
+            data,
+        }
+    }
+}
+
+pub fn lending_market_authority(lending_market: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lma", lending_market.as_ref()], &klend_program_id()).0
+}
+
+// 配置中 base mint 对应的 reserve 地址，SOL 未配置时使用默认 reserve
+pub fn reserve_address(
+    flashloan_config: &KaminoFlashloanConfig,
+    base_mint: &Pubkey,
+) -> anyhow::Result<Pubkey> {
+    let configured = flashloan_config
+        .reserves
+        .as_ref()
+        .and_then(|reserves| reserves.get(&base_mint.to_string()));
+    match configured {
+        Some(reserve) => Pubkey::from_str(reserve)
+            .map_err(|e| anyhow::anyhow!("Invalid Kamino reserve {}: {}", reserve, e)),
+        None if *base_mint == sol_mint() => Ok(Pubkey::from_str(DEFAULT_SOL_RESERVE).unwrap()),
+        None => Err(anyhow::anyhow!("No Kamino reserve configured for base mint {}", base_mint)),
+    }
+}
+
+impl FlashLoanProvider for KaminoReserve {
+    fn name(&self) -> String {
+        format!("Kamino reserve {}", self.reserve)
+    }
+
+    fn mint(&self) -> Pubkey {
+        self.liquidity_mint
+    }
+
+    fn liquidity_account(&self) -> Pubkey {
+        self.reserve
+    }
+
+    fn available_liquidity(&self, account: &Account) -> anyhow::Result<u64> {
+        read_u64(&account.data, RESERVE_AVAILABLE_AMOUNT_OFFSET)
+    }
+
+    fn fee(&self, amount: u64) -> u64 {
+        fee_from_ppm(amount, self.fee_ppm)
+    }
+
+    fn borrow_instruction(&self, wallet: &Pubkey, amount: u64) -> Instruction {
+        self.flash_borrow_instruction(wallet, amount)
+    }
+
+    fn repay_instruction(
+        &self,
+        wallet: &Pubkey,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Instruction {
+        self.flash_repay_instruction(wallet, amount, borrow_instruction_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::hashv;
+    use std::collections::HashMap;
+
+    fn reserve() -> KaminoReserve {
+        let lending_market = Pubkey::new_unique();
+        KaminoReserve {
+            reserve: Pubkey::new_unique(),
+            lending_market,
+            lending_market_authority: lending_market_authority(&lending_market),
+            liquidity_mint: sol_mint(),
+            supply_vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            token_program: spl_token::id(),
+            fee_ppm: DEFAULT_FEE_PPM,
+        }
+    }
+
+    fn flashloan_config(reserves: &[(Pubkey, &str)]) -> KaminoFlashloanConfig {
+        KaminoFlashloanConfig {
+            enabled: true,
+            max_borrow: None,
+            fee_ppm: None,
+            reserves: Some(
+                reserves
+                    .iter()
+                    .map(|(mint, reserve)| (mint.to_string(), reserve.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            ),
+        }
+    }
+
+    #[test]
+    fn discriminators_match_the_anchor_instruction_names() {
+        let discriminator =
+            |name: &str| hashv(&[format!("global:{}", name).as_bytes()]).to_bytes()[..8].to_vec();
+        assert_eq!(FLASH_BORROW_DISCRIMINATOR.to_vec(), discriminator("flash_borrow_reserve_liquidity"));
+        assert_eq!(FLASH_REPAY_DISCRIMINATOR.to_vec(), discriminator("flash_repay_reserve_liquidity"));
+    }
+
+    #[test]
+    fn borrow_and_repay_encode_the_amount_and_borrow_index() {
+        let reserve = reserve();
+        let wallet = Pubkey::new_unique();
+        let amount: u64 = 1_234_567_890;
+
+        let borrow = reserve.flash_borrow_instruction(&wallet, amount);
+        assert_eq!(borrow.program_id, klend_program_id());
+        assert_eq!(&borrow.data[..8], &FLASH_BORROW_DISCRIMINATOR);
+        assert_eq!(&borrow.data[8..], &amount.to_le_bytes());
+
+        let repay = reserve.flash_repay_instruction(&wallet, amount, 3);
+        assert_eq!(&repay.data[..8], &FLASH_REPAY_DISCRIMINATOR);
+        assert_eq!(&repay.data[8..16], &amount.to_le_bytes());
+        assert_eq!(repay.data[16..], [3]);
+        assert_eq!(repay.accounts, borrow.accounts);
+    }
+
+    #[test]
+    fn instructions_pass_the_wallet_and_its_token_account() {
+        let reserve = reserve();
+        let wallet = Pubkey::new_unique();
+        let accounts = reserve.flash_borrow_instruction(&wallet, 1).accounts;
+
+        assert_eq!(accounts.len(), 12);
+        assert_eq!(accounts[0], AccountMeta::new_readonly(wallet, true));
+        assert_eq!(accounts[1].pubkey, reserve.lending_market_authority);
+        assert!(accounts[3].is_writable && accounts[3].pubkey == reserve.reserve);
+        assert_eq!(
+            accounts[6].pubkey,
+            spl_associated_token_account::get_associated_token_address(&wallet, &sol_mint())
+        );
+        assert_eq!(accounts[10].pubkey, sysvar::instructions::id());
+        assert_eq!(accounts[11].pubkey, spl_token::id());
+    }
+
+    #[test]
+    fn available_liquidity_and_fee_come_from_the_reserve() {
+        let reserve = reserve();
+        let mut data = vec![0u8; RESERVE_AVAILABLE_AMOUNT_OFFSET + 8];
+        data[RESERVE_AVAILABLE_AMOUNT_OFFSET..].copy_from_slice(&5_000_000_000u64.to_le_bytes());
+        let account = Account {
+            lamports: 1,
+            data,
+            owner: klend_program_id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        assert_eq!(reserve.available_liquidity(&account).unwrap(), 5_000_000_000);
+        assert!(reserve.available_liquidity(&Account::default()).is_err());
+        // 0.001%
+        assert_eq!(reserve.fee(1_000_000_000), 10_000);
+    }
+
+    #[test]
+    fn reserve_address_prefers_the_configured_reserve() {
+        let usdc = Pubkey::new_unique();
+        let configured = Pubkey::new_unique();
+        let config = flashloan_config(&[(usdc, &configured.to_string())]);
+
+        assert_eq!(reserve_address(&config, &usdc).unwrap(), configured);
+        assert_eq!(
+            reserve_address(&config, &sol_mint()).unwrap(),
+            Pubkey::from_str(DEFAULT_SOL_RESERVE).unwrap()
+        );
+        assert!(reserve_address(&config, &Pubkey::new_unique()).is_err());
+        assert!(reserve_address(&flashloan_config(&[(usdc, "not-a-pubkey")]), &usdc).is_err());
+    }
+}
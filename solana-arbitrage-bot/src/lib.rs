@@ -1,8 +1,23 @@
+pub mod accounts;
 pub mod bot;
+pub mod compute_budget;
 pub mod config;
+pub mod confirmation;
 pub mod constants;
 pub mod dex;
+pub mod flash_loan;
+pub mod jito;
+pub mod journal;
 pub mod kamino;
 pub mod pools;
+pub mod priority_fee;
+pub mod quote;
 pub mod refresh;
-pub mod transaction;
\ No newline at end of file
+pub mod reload;
+pub mod risk;
+pub mod simulate;
+pub mod stream;
+pub mod supervisor;
+pub mod transaction;
+pub mod validate;
+pub mod window;
\ No newline at end of file
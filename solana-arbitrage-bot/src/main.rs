@@ -1,11 +1,26 @@
+mod accounts;
 mod bot;
+mod compute_budget;
 mod config;
+mod confirmation;
 mod constants;
 mod dex;
+mod flash_loan;
+mod jito;
+mod journal;
 mod kamino;
 mod pools;
+mod priority_fee;
+mod quote;
 mod refresh;
+mod reload;
+mod risk;
+mod simulate;
+mod stream;
+mod supervisor;
 mod transaction;
+mod validate;
+mod window;
 
 use clap::{App, Arg};
 use tracing::{info, Level};
@@ -36,5 +51,36 @@ async fn main() -> anyhow::Result<()> {
                 .takes_value(true)
                 .default_value("config.toml"),
         )
+        .subcommand(
+            App::new("report")
+                .about("Summarizes PnL per mint, per route and per day from the trade journal")
+                .arg(
+                    Arg::with_name("journal")
+                        .short('j')
+                        .long("journal")
+                        .value_name("FILE")
+                        .help("Path of the JSONL trade journal")
+                        .takes_value(true)
+                        .default_value("journal.jsonl"),
+                ),
+        )
+        .subcommand(
+            App::new("validate")
+                .about("Checks the config file and reports every problem at once")
+                .arg(
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .help("Skips the RPC checks of pool accounts"),
+                ),
+        )
         .get_matches();
+
+    if let Some(report_matches) = matches.subcommand_matches("report") {
+        return journal::print_report(report_matches.value_of("journal").unwrap());
+    }
+
+    let config_path = matches.value_of("config").unwrap();
+    if let Some(validate_matches) = matches.subcommand_matches("validate") {
+        return validate::run(config_path, !validate_matches.is_present("offline")).await;
+    }
 }
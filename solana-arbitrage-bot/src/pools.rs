@@ -0,0 +1,49 @@
+use crate::dex::DexType;
+use crate::quote::PoolKeys;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+// 一个 mint 的全部池子。各 DEX 的账户布局由 dex::registry() 中的实现负责，
+// 这里只按 PoolKeys 统一保存，新增 DEX 不需要修改这里
+#[derive(Debug, Clone)]
+pub struct MintPoolData {
+    pub mint: Pubkey,
+    pub wallet_account: Pubkey,
+    pub pools: Vec<PoolKeys>,
+    // mint 和 base mint 各自的 token program，初始化时按链上 mint 账户的 owner 设置
+    pub token_program: Pubkey,
+    pub base_token_program: Pubkey,
+}
+
+impl MintPoolData {
+    pub fn new(mint: &str, wallet_account: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            mint: Pubkey::from_str(mint)
+                .map_err(|e| anyhow::anyhow!("Invalid mint {}: {}", mint, e))?,
+            wallet_account: Pubkey::from_str(wallet_account)
+                .map_err(|e| anyhow::anyhow!("Invalid wallet account {}: {}", wallet_account, e))?,
+            pools: Vec::new(),
+            token_program: spl_token::id(),
+            base_token_program: spl_token::id(),
+        })
+    }
+
+    // 同一个池子只能加入一次，否则报价会把它当成两个池子
+    pub fn add_pool(&mut self, keys: PoolKeys) -> anyhow::Result<()> {
+        if self.pools.iter().any(|existing| existing.pool == keys.pool) {
+            return Err(anyhow::anyhow!("Pool {} is already added for mint {}", keys.pool, self.mint));
+        }
+        self.pools.push(keys);
+        Ok(())
+    }
+
+    // 某个 DEX 的池子，各 DEX 的 refresh_arrays 用来替换自己的 array
+    pub fn pools_of_mut(&mut self, dex: DexType) -> impl Iterator<Item = &mut PoolKeys> {
+        self.pools.iter_mut().filter(move |keys| keys.dex == dex)
+    }
+
+    // 只保留指定的池子，用于把报价选出的路径交给交易构建
+    pub fn retain_pools(&mut self, pools: &[Pubkey]) {
+        self.pools.retain(|keys| pools.contains(&keys.pool));
+    }
+}
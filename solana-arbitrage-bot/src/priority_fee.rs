@@ -0,0 +1,309 @@
+use crate::config::{Config, PriorityFeeConfig, PriorityFeeStrategy};
+use crate::dex::adapter;
+use crate::pools::MintPoolData;
+use crate::quote::PoolKeys;
+use crate::supervisor::Shutdown;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+// getRecentPrioritizationFees 单次最多查询 128 个账户
+const MAX_FEE_ACCOUNTS: usize = 128;
+const DEFAULT_PERCENTILE: u8 = 75;
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+// 每个 CU 的价格以 micro-lamports 计
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+// 最近若干个 slot 的优先费样本（micro-lamports / CU），fees 已排序
+#[derive(Debug, Clone, Default)]
+pub struct FeeSamples {
+    by_slot: BTreeMap<u64, u64>,
+    fees: Vec<u64>,
+    pub latest_slot: u64,
+}
+
+impl FeeSamples {
+    // samples 为 (slot, fee)
+    pub fn new(samples: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut by_slot = BTreeMap::new();
+        for (slot, fee) in samples {
+            let entry = by_slot.entry(slot).or_insert(0);
+            *entry = fee.max(*entry);
+        }
+        let mut fees: Vec<u64> = by_slot.values().copied().collect();
+        fees.sort_unstable();
+        let latest_slot = by_slot.keys().next_back().copied().unwrap_or_default();
+        Self {
+            by_slot,
+            fees,
+            latest_slot,
+        }
+    }
+
+    // 多个池子的样本按 slot 取最大值，与一次查询所有池子可写账户的结果相同
+    pub fn merge<'a>(samples: impl IntoIterator<Item = &'a FeeSamples>) -> Self {
+        Self::new(
+            samples
+                .into_iter()
+                .flat_map(|samples| samples.by_slot.iter().map(|(slot, fee)| (*slot, *fee))),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fees.is_empty()
+    }
+
+    // 最近邻取整的分位数，没有样本时为 0
+    pub fn percentile(&self, percentile: u8) -> u64 {
+        if self.fees.is_empty() {
+            return 0;
+        }
+        let rank = (percentile.min(100) as usize * (self.fees.len() - 1) + 50) / 100;
+        self.fees[rank]
+    }
+}
+
+// 每个 mint 各池子的优先费样本，按池子地址索引，由后台任务刷新
+pub type PriorityFeeCache = Arc<RwLock<HashMap<Pubkey, FeeSamples>>>;
+
+// 路径经过的池子的样本，发送时只和这些池子的写锁竞争
+pub fn route_fee_samples(cache: &HashMap<Pubkey, FeeSamples>, pools: &[Pubkey]) -> FeeSamples {
+    FeeSamples::merge(pools.iter().filter_map(|pool| cache.get(pool)))
+}
+
+// 未开启 [priority_fee] 或没有样本时使用 [spam] 中固定的 CU 价格
+pub fn static_compute_unit_price(config: &Config) -> u64 {
+    match &config.spam {
+        Some(spam_config) if spam_config.enabled => spam_config.compute_unit_price,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityFeeEstimator {
+    pub strategy: PriorityFeeStrategy,
+    pub percentile: u8,
+    pub max_compute_unit_price: Option<u64>,
+    pub profit_fraction_percent: u64,
+    // MintConfig 中的固定价格，优先于估计
+    pub fixed_compute_unit_price: Option<u64>,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(fee_config: &PriorityFeeConfig, fixed_compute_unit_price: Option<u64>) -> Self {
+        Self {
+            strategy: fee_config.strategy,
+            percentile: fee_config.percentile.unwrap_or(DEFAULT_PERCENTILE),
+            max_compute_unit_price: fee_config.max_compute_unit_price,
+            profit_fraction_percent: fee_config.profit_fraction_percent.unwrap_or(0),
+            fixed_compute_unit_price,
+        }
+    }
+
+    // 选定的 CU 价格（micro-lamports），profit_fraction 需要预期利润和 CU limit
+    pub fn compute_unit_price(
+        &self,
+        samples: &FeeSamples,
+        expected_profit: Option<i64>,
+        compute_unit_limit: u32,
+    ) -> u64 {
+        if let Some(price) = self.fixed_compute_unit_price {
+            return price;
+        }
+        let observed = samples.percentile(self.percentile);
+        match self.strategy {
+            PriorityFeeStrategy::Percentile => observed,
+            PriorityFeeStrategy::Cap => {
+                self.max_compute_unit_price.map_or(observed, |max_price| observed.min(max_price))
+            }
+            // 总优先费不超过预期利润的一定比例，同时不高于观测到的分位数
+            PriorityFeeStrategy::ProfitFraction => {
+                let profit = expected_profit.unwrap_or(0).max(0) as u128;
+                let budget = profit * self.profit_fraction_percent as u128 / 100;
+                let price =
+                    budget * MICRO_LAMPORTS_PER_LAMPORT as u128 / compute_unit_limit.max(1) as u128;
+                observed.min(price.min(u64::MAX as u128) as u64)
+            }
+        }
+    }
+}
+
+// 单个池子 swap 时可写的账户：池子、vault 等，优先费只和这些账户的写锁竞争有关
+pub fn writable_accounts(keys: &PoolKeys) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    adapter(keys.dex)
+        .swap_accounts(keys)
+        .into_iter()
+        .filter(|meta| meta.is_writable && seen.insert(meta.pubkey))
+        .map(|meta| meta.pubkey)
+        .take(MAX_FEE_ACCOUNTS)
+        .collect()
+}
+
+pub async fn fetch_fee_samples(
+    rpc_client: &NonblockingRpcClient,
+    accounts: &[Pubkey],
+) -> anyhow::Result<FeeSamples> {
+    let fees = rpc_client.get_recent_prioritization_fees(accounts).await?;
+    Ok(FeeSamples::new(
+        fees.into_iter().map(|fee| (fee.slot, fee.prioritization_fee)),
+    ))
+}
+
+// 与 blockhash_refresher 类似，定时按每个池子的可写账户刷新优先费样本；
+// 报价选出路径后只合并路径上池子的样本，不受其他池子的竞争影响
+pub fn spawn_priority_fee_refresher(
+    rpc_client: Arc<NonblockingRpcClient>,
+    pool_data: Arc<Mutex<MintPoolData>>,
+    cache: PriorityFeeCache,
+    refresh_interval: Option<Duration>,
+    mut shutdown: Shutdown,
+) -> JoinHandle<()> {
+    let refresh_interval = refresh_interval.unwrap_or(DEFAULT_REFRESH_INTERVAL);
+    tokio::spawn(async move {
+        while !shutdown.is_triggered() {
+            let (mint, pools) = {
+                let pool_data = pool_data.lock().await;
+                (pool_data.mint, pool_data.pools.clone())
+            };
+            let mut refreshed = HashMap::with_capacity(pools.len());
+            for keys in &pools {
+                match fetch_fee_samples(&rpc_client, &writable_accounts(keys)).await {
+                    Ok(samples) => {
+                        debug!(
+                            "Priority fees for {} pool {} of mint {} refreshed at slot {}: p50 {}, p75 {}, p90 {}",
+                            keys.dex,
+                            keys.pool,
+                            mint,
+                            samples.latest_slot,
+                            samples.percentile(50),
+                            samples.percentile(75),
+                            samples.percentile(90)
+                        );
+                        refreshed.insert(keys.pool, samples);
+                    }
+                    Err(e) => error!(
+                        "Failed to get recent prioritization fees for pool {} of mint {}: {}",
+                        keys.pool, mint, e
+                    ),
+                }
+            }
+            // 拉取失败的池子保留上一次的样本，热加载删除的池子随之移除
+            {
+                let mut cache = cache.write().await;
+                cache.retain(|pool, _| pools.iter().any(|keys| keys.pool == *pool));
+                cache.extend(refreshed);
+            }
+            shutdown.sleep(refresh_interval).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimator(strategy: PriorityFeeStrategy) -> PriorityFeeEstimator {
+        PriorityFeeEstimator {
+            strategy,
+            percentile: 50,
+            max_compute_unit_price: Some(3_000),
+            profit_fraction_percent: 10,
+            fixed_compute_unit_price: None,
+        }
+    }
+
+    // slot 1..=5 的费用 5000, 1000, 4000, 2000, 3000
+    fn samples() -> FeeSamples {
+        FeeSamples::new([(1, 5_000), (2, 1_000), (3, 4_000), (4, 2_000), (5, 3_000)])
+    }
+
+    #[test]
+    fn percentile_rounds_to_the_nearest_rank() {
+        let samples = samples();
+        assert_eq!(samples.latest_slot, 5);
+        assert_eq!(samples.percentile(0), 1_000);
+        assert_eq!(samples.percentile(50), 3_000);
+        // 第 0.6 * 4 = 2.4 位取整到第 2 位
+        assert_eq!(samples.percentile(60), 3_000);
+        assert_eq!(samples.percentile(75), 4_000);
+        assert_eq!(samples.percentile(100), 5_000);
+        assert_eq!(samples.percentile(200), 5_000);
+        assert_eq!(FeeSamples::default().percentile(75), 0);
+    }
+
+    #[test]
+    fn merge_keeps_the_highest_fee_per_slot() {
+        let first = FeeSamples::new([(1, 100), (2, 500), (2, 200)]);
+        let second = FeeSamples::new([(2, 300), (3, 50)]);
+        let merged = FeeSamples::merge([&first, &second]);
+
+        assert_eq!(merged.latest_slot, 3);
+        assert_eq!(merged.fees, vec![50, 100, 500]);
+        assert!(FeeSamples::merge([]).is_empty());
+    }
+
+    #[test]
+    fn route_fee_samples_only_merges_route_pools() {
+        let (route_pool, other_pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let cache = HashMap::from([
+            (route_pool, FeeSamples::new([(1, 100)])),
+            (other_pool, FeeSamples::new([(1, 9_000)])),
+        ]);
+        let samples = route_fee_samples(&cache, &[route_pool, Pubkey::new_unique()]);
+        assert_eq!(samples.percentile(100), 100);
+    }
+
+    #[test]
+    fn percentile_strategy_uses_the_observed_fee() {
+        let estimator = estimator(PriorityFeeStrategy::Percentile);
+        assert_eq!(estimator.compute_unit_price(&samples(), None, 200_000), 3_000);
+    }
+
+    #[test]
+    fn cap_strategy_limits_the_observed_fee() {
+        let estimator = PriorityFeeEstimator {
+            percentile: 100,
+            ..estimator(PriorityFeeStrategy::Cap)
+        };
+        assert_eq!(estimator.compute_unit_price(&samples(), None, 200_000), 3_000);
+
+        let uncapped = PriorityFeeEstimator {
+            max_compute_unit_price: None,
+            ..estimator
+        };
+        assert_eq!(uncapped.compute_unit_price(&samples(), None, 200_000), 5_000);
+    }
+
+    #[test]
+    fn profit_fraction_strategy_spends_a_share_of_the_profit() {
+        let estimator = estimator(PriorityFeeStrategy::ProfitFraction);
+        // 利润 1000 lamports 的 10% = 100 lamports，分摊到 200_000 CU 为 500 micro-lamports / CU
+        assert_eq!(estimator.compute_unit_price(&samples(), Some(1_000), 200_000), 500);
+        // 预算超过观测值时不多付
+        assert_eq!(estimator.compute_unit_price(&samples(), Some(1_000_000), 200_000), 3_000);
+        // 没有利润或亏损时不付优先费
+        assert_eq!(estimator.compute_unit_price(&samples(), None, 200_000), 0);
+        assert_eq!(estimator.compute_unit_price(&samples(), Some(-1_000), 200_000), 0);
+    }
+
+    #[test]
+    fn fixed_price_overrides_every_strategy() {
+        for strategy in [
+            PriorityFeeStrategy::Percentile,
+            PriorityFeeStrategy::Cap,
+            PriorityFeeStrategy::ProfitFraction,
+        ] {
+            let estimator = PriorityFeeEstimator {
+                fixed_compute_unit_price: Some(42),
+                ..estimator(strategy)
+            };
+            assert_eq!(estimator.compute_unit_price(&samples(), Some(1_000), 200_000), 42);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+// 集中流动性（Raydium CLMM / Orca Whirlpool）共用的 Q64.64 数学
+use uint::construct_uint;
+
+construct_uint! {
+    pub struct U256(4);
+}
+
+construct_uint! {
+    pub struct U512(8);
+}
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+// 费率精度：百万分之一
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+fn to_u512(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+fn u512_to_u128(value: U512) -> anyhow::Result<u128> {
+    if value > U512::from(u128::MAX) {
+        return Err(anyhow::anyhow!("Q64.64 math overflow"));
+    }
+    Ok(value.low_u128())
+}
+
+// a * b / denominator，按需向上取整
+pub fn mul_div(a: U256, b: U256, denominator: U256, round_up: bool) -> anyhow::Result<u128> {
+    if denominator.is_zero() {
+        return Err(anyhow::anyhow!("Q64.64 division by zero"));
+    }
+    let product = to_u512(a) * to_u512(b);
+    let denominator = to_u512(denominator);
+    let (quotient, remainder) = product.div_mod(denominator);
+    let quotient = if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    };
+    u512_to_u128(quotient)
+}
+
+// Raydium CLMM 的 tick -> sqrt price，全程使用 u128 的 Q64 计算
+pub fn get_sqrt_price_at_tick(tick: i32) -> anyhow::Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(anyhow::anyhow!("Tick {} is out of range", tick));
+    }
+    const RATIOS: [u128; 19] = [
+        0xfffcb933bd6fb800,
+        0xfff97272373d4000,
+        0xfff2e50f5f657000,
+        0xffe5caca7e10f000,
+        0xffcb9843d60f7000,
+        0xff973b41fa98e800,
+        0xff2ea16466c9b000,
+        0xfe5dee046a9a3800,
+        0xfcbe86c7900bb000,
+        0xf987a7253ac65800,
+        0xf3392b0822bb6000,
+        0xe7159475a2caf000,
+        0xd097f3bdfd2f2000,
+        0xa9f746462d9f8000,
+        0x70d869a156f31c00,
+        0x31be135f97ed3200,
+        0x9aa508b5b85a500,
+        0x5d6af8dedc582c,
+        0x2216e584f5fa,
+    ];
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 0x1 != 0 {
+        RATIOS[0]
+    } else {
+        1u128 << 64
+    };
+    for (i, factor) in RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << i) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Ok(ratio)
+}
+
+// sqrt price -> tick：返回满足 sqrt_price_at_tick(tick) <= sqrt_price 的最大 tick
+pub fn get_tick_at_sqrt_price(
+    sqrt_price_x64: u128,
+    sqrt_price_at_tick: fn(i32) -> anyhow::Result<u128>,
+) -> anyhow::Result<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return Err(anyhow::anyhow!("Sqrt price {} is out of range", sqrt_price_x64));
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price_x64 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+fn sorted(sqrt_price_a: u128, sqrt_price_b: u128) -> (u128, u128) {
+    if sqrt_price_a > sqrt_price_b {
+        (sqrt_price_b, sqrt_price_a)
+    } else {
+        (sqrt_price_a, sqrt_price_b)
+    }
+}
+
+// token0 数量：liquidity * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)
+pub fn get_delta_amount_0(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> anyhow::Result<u128> {
+    let (lower, upper) = sorted(sqrt_price_a, sqrt_price_b);
+    if lower == 0 {
+        return Err(anyhow::anyhow!("Sqrt price must be positive"));
+    }
+    mul_div(
+        U256::from(liquidity) << 64,
+        U256::from(upper - lower),
+        U256::from(lower) * U256::from(upper),
+        round_up,
+    )
+}
+
+// token1 数量：liquidity * (sqrt_b - sqrt_a) / 2^64
+pub fn get_delta_amount_1(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> anyhow::Result<u128> {
+    let (lower, upper) = sorted(sqrt_price_a, sqrt_price_b);
+    mul_div(
+        U256::from(liquidity),
+        U256::from(upper - lower),
+        U256::one() << 64,
+        round_up,
+    )
+}
+
+// 输入 token0 时价格下降（向上取整），输入 token1 时价格上升（向下取整）
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u128,
+    zero_for_one: bool,
+) -> anyhow::Result<u128> {
+    if amount_in == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    if liquidity == 0 {
+        return Err(anyhow::anyhow!("Cannot move price without liquidity"));
+    }
+    let numerator = U256::from(liquidity) << 64;
+    if zero_for_one {
+        let product = U256::from(amount_in) * U256::from(sqrt_price_x64);
+        mul_div(numerator, U256::from(sqrt_price_x64), numerator + product, true)
+    } else {
+        let delta = (U256::from(amount_in) << 64) / U256::from(liquidity);
+        let next = U256::from(sqrt_price_x64) + delta;
+        if next > U256::from(u128::MAX) {
+            return Err(anyhow::anyhow!("Sqrt price overflow"));
+        }
+        Ok(next.low_u128())
+    }
+}
+
+// 输出 token1 时价格下降（向下取整），输出 token0 时价格上升（向上取整）
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_out: u128,
+    zero_for_one: bool,
+) -> anyhow::Result<u128> {
+    if amount_out == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    if liquidity == 0 {
+        return Err(anyhow::anyhow!("Cannot move price without liquidity"));
+    }
+    if zero_for_one {
+        let delta = mul_div(
+            U256::from(amount_out) << 64,
+            U256::one(),
+            U256::from(liquidity),
+            true,
+        )?;
+        sqrt_price_x64
+            .checked_sub(delta)
+            .ok_or_else(|| anyhow::anyhow!("Sqrt price underflow"))
+    } else {
+        let numerator = U256::from(liquidity) << 64;
+        let product = U256::from(amount_out) * U256::from(sqrt_price_x64);
+        if product >= numerator {
+            return Err(anyhow::anyhow!("Not enough liquidity for output"));
+        }
+        mul_div(numerator, U256::from(sqrt_price_x64), numerator - product, true)
+    }
+}
+
+// 单个价格区间内的交易结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub fee_amount: u128,
+}
+
+// 在 [sqrt_price_current, sqrt_price_target] 之间尽可能多地成交
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee_rate: u32,
+    is_base_input: bool,
+    zero_for_one: bool,
+) -> anyhow::Result<SwapStep> {
+    let fee_denominator = FEE_RATE_DENOMINATOR as u128;
+    let fee_rate = fee_rate as u128;
+    let amount_in_to_target = |round_up| {
+        if zero_for_one {
+            get_delta_amount_0(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, round_up)
+        } else {
+            get_delta_amount_1(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, round_up)
+        }
+    };
+    let amount_out_to_target = |round_up| {
+        if zero_for_one {
+            get_delta_amount_1(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, round_up)
+        } else {
+            get_delta_amount_0(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, round_up)
+        }
+    };
+
+    let mut step = SwapStep::default();
+    if is_base_input {
+        let amount_remaining_less_fee = mul_div(
+            U256::from(amount_remaining),
+            U256::from(fee_denominator - fee_rate),
+            U256::from(fee_denominator),
+            false,
+        )?;
+        step.amount_in = amount_in_to_target(true)?;
+        step.sqrt_price_next_x64 = if amount_remaining_less_fee >= step.amount_in {
+            sqrt_price_target_x64
+        } else {
+            get_next_sqrt_price_from_input(
+                sqrt_price_current_x64,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        };
+    } else {
+        step.amount_out = amount_out_to_target(false)?;
+        step.sqrt_price_next_x64 = if amount_remaining >= step.amount_out {
+            sqrt_price_target_x64
+        } else {
+            get_next_sqrt_price_from_output(
+                sqrt_price_current_x64,
+                liquidity,
+                amount_remaining,
+                zero_for_one,
+            )?
+        };
+    }
+
+    let reached_target = step.sqrt_price_next_x64 == sqrt_price_target_x64;
+    let (next, current) = (step.sqrt_price_next_x64, sqrt_price_current_x64);
+    if zero_for_one {
+        if !(reached_target && is_base_input) {
+            step.amount_in = get_delta_amount_0(next, current, liquidity, true)?;
+        }
+        if !(reached_target && !is_base_input) {
+            step.amount_out = get_delta_amount_1(next, current, liquidity, false)?;
+        }
+    } else {
+        if !(reached_target && is_base_input) {
+            step.amount_in = get_delta_amount_1(current, next, liquidity, true)?;
+        }
+        if !(reached_target && !is_base_input) {
+            step.amount_out = get_delta_amount_0(current, next, liquidity, false)?;
+        }
+    }
+
+    if !is_base_input && step.amount_out > amount_remaining {
+        step.amount_out = amount_remaining;
+    }
+
+    step.fee_amount = if is_base_input && !reached_target {
+        amount_remaining - step.amount_in
+    } else {
+        mul_div(
+            U256::from(step.amount_in),
+            U256::from(fee_rate),
+            U256::from(fee_denominator - fee_rate),
+            true,
+        )?
+    };
+
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_price_matches_tick_bounds() {
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), 1u128 << 64);
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert!(get_sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_rounds_down_to_tick() {
+        let sqrt_price = get_sqrt_price_at_tick(-19293).unwrap();
+        assert_eq!(get_tick_at_sqrt_price(sqrt_price, get_sqrt_price_at_tick).unwrap(), -19293);
+        assert_eq!(
+            get_tick_at_sqrt_price(sqrt_price - 1, get_sqrt_price_at_tick).unwrap(),
+            -19294
+        );
+        let next = get_sqrt_price_at_tick(-19292).unwrap();
+        assert_eq!(get_tick_at_sqrt_price(next - 1, get_sqrt_price_at_tick).unwrap(), -19293);
+    }
+
+    #[test]
+    fn delta_amounts_round_in_pool_favour() {
+        let lower = get_sqrt_price_at_tick(-19300).unwrap();
+        let upper = get_sqrt_price_at_tick(-19293).unwrap();
+        let liquidity = 112_035_621_120_963;
+        let amount_0_up = get_delta_amount_0(lower, upper, liquidity, true).unwrap();
+        let amount_0_down = get_delta_amount_0(upper, lower, liquidity, false).unwrap();
+        assert_eq!(amount_0_up, amount_0_down + 1);
+        let amount_1_up = get_delta_amount_1(lower, upper, liquidity, true).unwrap();
+        let amount_1_down = get_delta_amount_1(lower, upper, liquidity, false).unwrap();
+        assert_eq!(amount_1_up, amount_1_down + 1);
+    }
+
+    #[test]
+    fn swap_step_stops_inside_range_and_keeps_fee_remainder() {
+        let current = 7_030_853_491_284_420_608;
+        let target = get_sqrt_price_at_tick(-19300).unwrap();
+        let step =
+            compute_swap_step(current, target, 112_035_621_120_963, 10_000_000_000, 100, true, true)
+                .unwrap();
+        assert_eq!(step.sqrt_price_next_x64, 7_030_614_334_864_704_879);
+        assert_eq!(step.amount_out, 1_452_507_711);
+        assert_eq!(step.fee_amount, 1_000_000);
+        assert_eq!(step.amount_in + step.fee_amount, 10_000_000_000);
+    }
+}
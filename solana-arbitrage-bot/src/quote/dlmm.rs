@@ -0,0 +1,554 @@
+use crate::dex::dlmm::constants::dlmm_program_id;
+use crate::quote::{
+    read_i32, read_pubkey, read_u16, read_u32, read_u64, read_u128, AccountSnapshot, PoolKeys,
+    SwapDirection,
+};
+use solana_sdk::pubkey::Pubkey;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+pub const BASIS_POINT_MAX: u128 = 10_000;
+// 费率精度与上限
+pub const FEE_PRECISION: u128 = 1_000_000_000;
+pub const MAX_FEE_RATE: u128 = 100_000_000;
+const SCALE_OFFSET: u32 = 64;
+const ONE: u128 = 1u128 << SCALE_OFFSET;
+const MAX_EXPONENTIAL: u32 = 0x80000;
+
+// LbPair 字段偏移：StaticParameters 从 8 开始，VariableParameters 从 40 开始
+const LB_PAIR_BASE_FACTOR_OFFSET: usize = 8;
+const LB_PAIR_FILTER_PERIOD_OFFSET: usize = 10;
+const LB_PAIR_DECAY_PERIOD_OFFSET: usize = 12;
+const LB_PAIR_REDUCTION_FACTOR_OFFSET: usize = 14;
+const LB_PAIR_VARIABLE_FEE_CONTROL_OFFSET: usize = 16;
+const LB_PAIR_MAX_VOLATILITY_ACCUMULATOR_OFFSET: usize = 20;
+const LB_PAIR_MIN_BIN_ID_OFFSET: usize = 24;
+const LB_PAIR_MAX_BIN_ID_OFFSET: usize = 28;
+const LB_PAIR_PROTOCOL_SHARE_OFFSET: usize = 32;
+const LB_PAIR_BASE_FEE_POWER_FACTOR_OFFSET: usize = 34;
+const LB_PAIR_VOLATILITY_ACCUMULATOR_OFFSET: usize = 40;
+const LB_PAIR_VOLATILITY_REFERENCE_OFFSET: usize = 44;
+const LB_PAIR_INDEX_REFERENCE_OFFSET: usize = 48;
+const LB_PAIR_LAST_UPDATE_TIMESTAMP_OFFSET: usize = 56;
+const LB_PAIR_ACTIVE_ID_OFFSET: usize = 76;
+const LB_PAIR_BIN_STEP_OFFSET: usize = 80;
+const LB_PAIR_RESERVE_X_OFFSET: usize = 152;
+// BinArray: discriminator + index(i64) + version + padding + lb_pair 之后是 70 个 Bin
+const BIN_ARRAY_INDEX_OFFSET: usize = 8;
+const BIN_ARRAY_BINS_OFFSET: usize = 56;
+const BIN_LEN: usize = 144;
+
+pub fn bin_id_to_bin_array_index(bin_id: i32) -> i64 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY) as i64
+}
+
+pub fn bin_array_address(lb_pair: &Pubkey, index: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()],
+        &dlmm_program_id(),
+    )
+    .0
+}
+
+// 与 Meteora 链上 pow 一致的 Q64.64 幂运算
+fn pow(base: u128, exp: i32) -> Option<u128> {
+    let mut invert = exp.is_negative();
+    if exp == 0 {
+        return Some(ONE);
+    }
+    let exp = exp.unsigned_abs();
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+
+    let mut squared_base = base;
+    let mut result = ONE;
+    if squared_base >= result {
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        invert = !invert;
+    }
+    for bit in 0..19 {
+        if exp & (1 << bit) != 0 {
+            result = result.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+        squared_base = squared_base.checked_mul(squared_base)? >> SCALE_OFFSET;
+    }
+
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+
+// bin 价格：(1 + bin_step / 10000) ^ bin_id，Q64.64
+pub fn get_price_from_id(bin_id: i32, bin_step: u16) -> anyhow::Result<u128> {
+    let bps = ((bin_step as u128) << SCALE_OFFSET) / BASIS_POINT_MAX;
+    pow(ONE + bps, bin_id)
+        .ok_or_else(|| anyhow::anyhow!("Price overflow for bin {} with step {}", bin_id, bin_step))
+}
+
+fn mul_shr(x: u128, y: u128, round_up: bool) -> anyhow::Result<u128> {
+    let product = x
+        .checked_mul(y)
+        .ok_or_else(|| anyhow::anyhow!("DLMM math overflow"))?;
+    let result = product >> SCALE_OFFSET;
+    Ok(if round_up && product & (ONE - 1) != 0 {
+        result + 1
+    } else {
+        result
+    })
+}
+
+fn shl_div(x: u128, y: u128, round_up: bool) -> anyhow::Result<u128> {
+    let numerator = x
+        .checked_shl(SCALE_OFFSET)
+        .filter(|n| n >> SCALE_OFFSET == x)
+        .ok_or_else(|| anyhow::anyhow!("DLMM math overflow"))?;
+    let result = numerator / y;
+    Ok(if round_up && numerator % y != 0 {
+        result + 1
+    } else {
+        result
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DlmmPairState {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub protocol_share: u16,
+    pub base_fee_power_factor: u8,
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub reserve_x: Pubkey,
+}
+
+impl DlmmPairState {
+    pub fn load(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            base_factor: read_u16(data, LB_PAIR_BASE_FACTOR_OFFSET)?,
+            filter_period: read_u16(data, LB_PAIR_FILTER_PERIOD_OFFSET)?,
+            decay_period: read_u16(data, LB_PAIR_DECAY_PERIOD_OFFSET)?,
+            reduction_factor: read_u16(data, LB_PAIR_REDUCTION_FACTOR_OFFSET)?,
+            variable_fee_control: read_u32(data, LB_PAIR_VARIABLE_FEE_CONTROL_OFFSET)?,
+            max_volatility_accumulator: read_u32(data, LB_PAIR_MAX_VOLATILITY_ACCUMULATOR_OFFSET)?,
+            min_bin_id: read_i32(data, LB_PAIR_MIN_BIN_ID_OFFSET)?,
+            max_bin_id: read_i32(data, LB_PAIR_MAX_BIN_ID_OFFSET)?,
+            protocol_share: read_u16(data, LB_PAIR_PROTOCOL_SHARE_OFFSET)?,
+            base_fee_power_factor: *data
+                .get(LB_PAIR_BASE_FEE_POWER_FACTOR_OFFSET)
+                .ok_or_else(|| anyhow::anyhow!("LbPair data too short"))?,
+            volatility_accumulator: read_u32(data, LB_PAIR_VOLATILITY_ACCUMULATOR_OFFSET)?,
+            volatility_reference: read_u32(data, LB_PAIR_VOLATILITY_REFERENCE_OFFSET)?,
+            index_reference: read_i32(data, LB_PAIR_INDEX_REFERENCE_OFFSET)?,
+            last_update_timestamp: read_u64(data, LB_PAIR_LAST_UPDATE_TIMESTAMP_OFFSET)? as i64,
+            active_id: read_i32(data, LB_PAIR_ACTIVE_ID_OFFSET)?,
+            bin_step: read_u16(data, LB_PAIR_BIN_STEP_OFFSET)?,
+            reserve_x: read_pubkey(data, LB_PAIR_RESERVE_X_OFFSET)?,
+        })
+    }
+
+    // 与链上 update_references 一致：超过 filter_period 后重置参考值
+    pub fn update_references(&mut self, current_timestamp: i64) {
+        let elapsed = current_timestamp - self.last_update_timestamp;
+        if elapsed >= self.filter_period as i64 {
+            self.index_reference = self.active_id;
+            self.volatility_reference = if elapsed < self.decay_period as i64 {
+                (self.volatility_accumulator as u128 * self.reduction_factor as u128
+                    / BASIS_POINT_MAX) as u32
+            } else {
+                0
+            };
+        }
+    }
+
+    pub fn update_volatility_accumulator(&mut self) {
+        let delta_id = (self.index_reference as i64 - self.active_id as i64).unsigned_abs() as u128;
+        let volatility_accumulator =
+            self.volatility_reference as u128 + delta_id * BASIS_POINT_MAX;
+        self.volatility_accumulator =
+            volatility_accumulator.min(self.max_volatility_accumulator as u128) as u32;
+    }
+
+    pub fn base_fee_rate(&self) -> u128 {
+        self.base_factor as u128
+            * self.bin_step as u128
+            * 10
+            * 10u128.pow(self.base_fee_power_factor as u32)
+    }
+
+    pub fn variable_fee_rate(&self) -> u128 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+        let square_vfa_bin = (self.volatility_accumulator as u128 * self.bin_step as u128).pow(2);
+        let v_fee = self.variable_fee_control as u128 * square_vfa_bin;
+        (v_fee + 99_999_999_999) / 100_000_000_000
+    }
+
+    pub fn total_fee_rate(&self) -> u128 {
+        (self.base_fee_rate() + self.variable_fee_rate()).min(MAX_FEE_RATE)
+    }
+
+    // 已知不含手续费的输入，求手续费
+    pub fn compute_fee(&self, amount: u128) -> u128 {
+        let fee_rate = self.total_fee_rate();
+        let denominator = FEE_PRECISION - fee_rate;
+        (amount * fee_rate + denominator - 1) / denominator
+    }
+
+    // 已知包含手续费的输入，求手续费
+    pub fn compute_fee_from_amount(&self, amount_with_fees: u128) -> u128 {
+        (amount_with_fees * self.total_fee_rate() + FEE_PRECISION - 1) / FEE_PRECISION
+    }
+
+    pub fn compute_protocol_fee(&self, fee: u128) -> u128 {
+        fee * self.protocol_share as u128 / BASIS_POINT_MAX
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DlmmBin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub price: u128,
+}
+
+#[derive(Debug, Clone)]
+pub struct DlmmBinArray {
+    pub address: Pubkey,
+    pub index: i64,
+    pub bins: Vec<DlmmBin>,
+}
+
+impl DlmmBinArray {
+    pub fn load(address: Pubkey, data: &[u8]) -> anyhow::Result<Self> {
+        let index = read_u64(data, BIN_ARRAY_INDEX_OFFSET)? as i64;
+        let mut bins = Vec::with_capacity(MAX_BIN_PER_ARRAY as usize);
+        for i in 0..MAX_BIN_PER_ARRAY as usize {
+            let offset = BIN_ARRAY_BINS_OFFSET + i * BIN_LEN;
+            bins.push(DlmmBin {
+                amount_x: read_u64(data, offset)?,
+                amount_y: read_u64(data, offset + 8)?,
+                price: read_u128(data, offset + 16)?,
+            });
+        }
+        Ok(Self {
+            address,
+            index,
+            bins,
+        })
+    }
+
+    pub fn bin(&self, bin_id: i32) -> Option<&DlmmBin> {
+        if bin_id_to_bin_array_index(bin_id) != self.index {
+            return None;
+        }
+        let offset = bin_id - self.index as i32 * MAX_BIN_PER_ARRAY;
+        self.bins.get(offset as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DlmmSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub active_id: i32,
+    // 交易经过的 bin array，swap 指令需要作为 remaining accounts 传入
+    pub bin_arrays_touched: Vec<Pubkey>,
+    // 交易会走出已获取的 bin array 范围，链上会失败，路径应跳过
+    pub exceeds_bin_array_window: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DlmmQuoteState {
+    pub pair: DlmmPairState,
+    pub bin_arrays: Vec<DlmmBinArray>,
+}
+
+impl DlmmQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let pair = DlmmPairState::load(snapshot.data(&keys.pool)?)?;
+        let mut bin_arrays = Vec::new();
+        for address in &keys.extra_accounts {
+            if let Ok(data) = snapshot.data(address) {
+                bin_arrays.push(DlmmBinArray::load(*address, data)?);
+            }
+        }
+        Ok(Self { pair, bin_arrays })
+    }
+
+    // 输入 X 换出 Y 时 swap_for_y 为 true
+    pub fn swap_for_y(&self, keys: &PoolKeys, direction: SwapDirection) -> bool {
+        let base_is_x = self.pair.reserve_x == keys.base_vault;
+        match direction {
+            SwapDirection::BaseToToken => base_is_x,
+            SwapDirection::TokenToBase => !base_is_x,
+        }
+    }
+
+    pub fn quote_exact_in(&self, swap_for_y: bool, amount_in: u64) -> anyhow::Result<DlmmSwapResult> {
+        let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        self.simulate_swap(swap_for_y, amount_in, current_timestamp)
+    }
+
+    // 从 active_id 开始沿交易方向逐个 bin 成交
+    pub fn simulate_swap(
+        &self,
+        swap_for_y: bool,
+        amount_in: u64,
+        current_timestamp: i64,
+    ) -> anyhow::Result<DlmmSwapResult> {
+        let mut pair = self.pair;
+        pair.update_references(current_timestamp);
+
+        let mut amount_left = amount_in as u128;
+        let mut amount_out: u128 = 0;
+        let mut total_fee: u128 = 0;
+        let mut protocol_fee: u128 = 0;
+        let mut bin_arrays_touched: Vec<Pubkey> = Vec::new();
+        let mut exceeds_bin_array_window = false;
+
+        while amount_left > 0 {
+            if pair.active_id < pair.min_bin_id || pair.active_id > pair.max_bin_id {
+                exceeds_bin_array_window = true;
+                break;
+            }
+            let Some(bin_array) = self
+                .bin_arrays
+                .iter()
+                .find(|array| array.index == bin_id_to_bin_array_index(pair.active_id))
+            else {
+                exceeds_bin_array_window = true;
+                break;
+            };
+            if !bin_arrays_touched.contains(&bin_array.address) {
+                bin_arrays_touched.push(bin_array.address);
+            }
+
+            pair.update_volatility_accumulator();
+            let bin = bin_array
+                .bin(pair.active_id)
+                .ok_or_else(|| anyhow::anyhow!("Bin {} not found", pair.active_id))?;
+
+            let bin_amount_out = if swap_for_y { bin.amount_y } else { bin.amount_x } as u128;
+            if bin_amount_out > 0 {
+                let price = if bin.price == 0 {
+                    get_price_from_id(pair.active_id, pair.bin_step)?
+                } else {
+                    bin.price
+                };
+
+                let mut max_amount_in = if swap_for_y {
+                    shl_div(bin_amount_out, price, true)?
+                } else {
+                    mul_shr(bin_amount_out, price, true)?
+                };
+                let max_fee = pair.compute_fee(max_amount_in);
+                max_amount_in += max_fee;
+
+                let (step_in, step_out, step_fee) = if amount_left >= max_amount_in {
+                    (max_amount_in, bin_amount_out, max_fee)
+                } else {
+                    let fee = pair.compute_fee_from_amount(amount_left);
+                    let amount_in_after_fee = amount_left - fee;
+                    let out = if swap_for_y {
+                        mul_shr(amount_in_after_fee, price, false)?
+                    } else {
+                        shl_div(amount_in_after_fee, price, false)?
+                    };
+                    (amount_left, out.min(bin_amount_out), fee)
+                };
+
+                amount_left -= step_in;
+                amount_out += step_out;
+                total_fee += step_fee;
+                protocol_fee += pair.compute_protocol_fee(step_fee);
+            }
+
+            if amount_left > 0 {
+                pair.active_id += if swap_for_y { -1 } else { 1 };
+            }
+        }
+
+        Ok(DlmmSwapResult {
+            amount_in: u64::try_from(amount_in as u128 - amount_left)?,
+            amount_out: u64::try_from(amount_out)?,
+            fee: u64::try_from(total_fee)?,
+            protocol_fee: u64::try_from(protocol_fee)?,
+            active_id: pair.active_id,
+            bin_arrays_touched,
+            exceeds_bin_array_window,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bin_step 10，基础费率 0.1%，协议分成 5%；active bin -1959 的价格约为 0.145 USDC/lamport，
+    // 位于 index -28 的 bin array 的第一个 bin 之后，向下走一个 bin 就进入 index -29
+    const ACTIVE_ID: i32 = -1959;
+    const NOW: i64 = 1_005;
+
+    fn pair() -> DlmmPairState {
+        DlmmPairState {
+            base_factor: 10_000,
+            filter_period: 30,
+            decay_period: 600,
+            reduction_factor: 5_000,
+            variable_fee_control: 0,
+            max_volatility_accumulator: 150_000,
+            min_bin_id: -443_636,
+            max_bin_id: 443_636,
+            protocol_share: 500,
+            base_fee_power_factor: 0,
+            volatility_accumulator: 0,
+            volatility_reference: 0,
+            index_reference: ACTIVE_ID,
+            last_update_timestamp: 1_000,
+            active_id: ACTIVE_ID,
+            bin_step: 10,
+            reserve_x: Pubkey::new_unique(),
+        }
+    }
+
+    // active bin 两侧都有流动性，下方的 bin 只有 Y，上方的 bin 只有 X；price 为 0 时按 bin id 计算
+    fn bin_array(index: i64) -> DlmmBinArray {
+        let bins = (0..MAX_BIN_PER_ARRAY)
+            .map(|offset| {
+                let bin_id = index as i32 * MAX_BIN_PER_ARRAY + offset;
+                let (amount_x, amount_y) = match bin_id {
+                    ACTIVE_ID => (5_000_000_000, 700_000_000),
+                    -1970..=-1960 => (0, 1_500_000_000),
+                    -1958..=-1951 => (10_000_000_000, 0),
+                    _ => (0, 0),
+                };
+                DlmmBin {
+                    amount_x,
+                    amount_y,
+                    price: 0,
+                }
+            })
+            .collect();
+        DlmmBinArray {
+            address: Pubkey::new_unique(),
+            index,
+            bins,
+        }
+    }
+
+    fn quote_state(pair: DlmmPairState) -> DlmmQuoteState {
+        DlmmQuoteState {
+            pair,
+            bin_arrays: vec![bin_array(-28), bin_array(-29)],
+        }
+    }
+
+    #[test]
+    fn price_from_id_matches_bin_step_power() {
+        assert_eq!(get_price_from_id(0, 10).unwrap(), ONE);
+        assert_eq!(get_price_from_id(1, 10).unwrap(), 18_465_190_817_783_261_167);
+        // 1.001 ^ -1931 ≈ 0.1451430976
+        assert_eq!(get_price_from_id(-1931, 10).unwrap(), 2_677_417_575_554_708_256);
+    }
+
+    #[test]
+    fn bin_array_index_rounds_toward_negative_infinity() {
+        assert_eq!(bin_id_to_bin_array_index(69), 0);
+        assert_eq!(bin_id_to_bin_array_index(-1), -1);
+        assert_eq!(bin_id_to_bin_array_index(-70), -1);
+        assert_eq!(bin_id_to_bin_array_index(-71), -2);
+        assert_eq!(bin_id_to_bin_array_index(ACTIVE_ID), -28);
+    }
+
+    #[test]
+    fn swap_within_active_bin() {
+        let state = quote_state(pair());
+        assert_eq!(state.pair.total_fee_rate(), 1_000_000);
+        let result = state.simulate_swap(true, 1_000_000_000, NOW).unwrap();
+        assert_eq!(result.amount_in, 1_000_000_000);
+        assert_eq!(result.amount_out, 140_996_296);
+        assert_eq!(result.fee, 1_000_000);
+        assert_eq!(result.protocol_fee, 50_000);
+        assert_eq!(result.active_id, ACTIVE_ID);
+        assert_eq!(result.bin_arrays_touched, vec![state.bin_arrays[0].address]);
+        assert!(!result.exceeds_bin_array_window);
+    }
+
+    #[test]
+    fn swap_for_y_walks_bins_into_next_array() {
+        let state = quote_state(pair());
+        let result = state.simulate_swap(true, 30_000_000_000, NOW).unwrap();
+        assert_eq!(result.amount_in, 30_000_000_000);
+        assert_eq!(result.amount_out, 4_223_814_388);
+        assert_eq!(result.fee, 30_000_002);
+        assert_eq!(result.protocol_fee, 1_499_998);
+        assert_eq!(result.active_id, -1962);
+        assert_eq!(
+            result.bin_arrays_touched,
+            vec![state.bin_arrays[0].address, state.bin_arrays[1].address]
+        );
+        assert!(!result.exceeds_bin_array_window);
+    }
+
+    #[test]
+    fn swap_for_x_walks_bins_upward() {
+        let result = quote_state(pair()).simulate_swap(false, 3_000_000_000, NOW).unwrap();
+        assert_eq!(result.amount_out, 21_212_190_923);
+        assert_eq!(result.fee, 3_000_001);
+        assert_eq!(result.active_id, -1957);
+    }
+
+    #[test]
+    fn volatility_accumulator_raises_fee_per_crossed_bin() {
+        let state = quote_state(DlmmPairState {
+            variable_fee_control: 7_500,
+            ..pair()
+        });
+        let result = state.simulate_swap(true, 30_000_000_000, NOW).unwrap();
+        assert_eq!(result.amount_out, 4_223_805_225);
+        assert_eq!(result.fee, 30_065_120);
+        assert_eq!(result.protocol_fee, 1_503_254);
+    }
+
+    #[test]
+    fn references_decay_after_filter_period() {
+        // 距上次交易 100 秒：超过 filter_period，未到 decay_period，参考值减半并以 active bin 为参考
+        let state = quote_state(DlmmPairState {
+            variable_fee_control: 7_500,
+            volatility_accumulator: 20_000,
+            index_reference: -1950,
+            ..pair()
+        });
+        let result = state.simulate_swap(true, 30_000_000_000, 1_100).unwrap();
+        assert_eq!(result.amount_out, 4_223_792_951);
+        assert_eq!(result.fee, 30_152_341);
+        assert_eq!(result.protocol_fee, 1_507_614);
+    }
+
+    #[test]
+    fn swap_past_loaded_bin_arrays_is_flagged() {
+        let result = quote_state(pair()).simulate_swap(true, 200_000_000_000, NOW).unwrap();
+        assert!(result.exceeds_bin_array_window);
+        assert_eq!(result.amount_in, 122_693_511_385);
+        assert_eq!(result.amount_out, 17_200_000_000);
+        assert_eq!(result.active_id, -2031);
+    }
+}
@@ -0,0 +1,564 @@
+use crate::accounts::FetchPolicy;
+use crate::config::{Config, CycleConfig};
+use crate::dex::{collect_pool_keys, DexType};
+use crate::pools::MintPoolData;
+use crate::quote::sizing::{size_route, Sizing, SizingMethod, TradeCost};
+use crate::priority_fee::static_compute_unit_price;
+use crate::quote::{
+    fixed_cost, flash_loan_available, flash_loan_fee_ppm, quote_exact_in, swap_keys,
+    AccountSnapshot, PoolKeys, QuoteParams, SwapDirection,
+};
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::debug;
+
+pub const DEFAULT_MAX_HOPS: usize = 2;
+// 套利合约的指令只接受一个 mint 及其 token 账户，后面跟各池子的 swap 账户；
+// 跨 mint 的三跳环路没有对应的账户排列，在合约支持之前只搜索单 mint 的两跳环路
+pub const EXECUTABLE_MAX_HOPS: usize = 2;
+
+// 图中的一条边：连接 MintConfig 的 base_mint 与 mint 的池子
+#[derive(Debug, Clone)]
+pub struct PoolEdge {
+    pub base_mint: Pubkey,
+    pub token_mint: Pubkey,
+    pub keys: PoolKeys,
+}
+
+impl PoolEdge {
+    // 从 from 出发经过该池子到达的 mint，以及对应的交易方向
+    pub fn traverse(&self, from: &Pubkey) -> Option<(Pubkey, SwapDirection)> {
+        if *from == self.base_mint {
+            Some((self.token_mint, SwapDirection::BaseToToken))
+        } else if *from == self.token_mint {
+            Some((self.base_mint, SwapDirection::TokenToBase))
+        } else {
+            None
+        }
+    }
+}
+
+// 环路中的一跳
+#[derive(Debug, Clone, Copy)]
+pub struct Hop {
+    pub edge: usize,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub direction: SwapDirection,
+}
+
+// 路由图：节点是 mint，边是池子，由所有 MintConfig 的池子共同构成
+#[derive(Debug, Default, Clone)]
+pub struct RouteGraph {
+    edges: Vec<PoolEdge>,
+    adjacency: HashMap<Pubkey, Vec<usize>>,
+}
+
+impl RouteGraph {
+    pub fn new<'a>(pool_data_list: impl IntoIterator<Item = (Pubkey, &'a MintPoolData)>) -> Self {
+        let mut graph = Self::default();
+        for (base_mint, pool_data) in pool_data_list {
+            for keys in collect_pool_keys(pool_data) {
+                graph.add_edge(PoolEdge {
+                    base_mint,
+                    token_mint: pool_data.mint,
+                    keys,
+                });
+            }
+        }
+        graph
+    }
+
+    pub fn add_edge(&mut self, edge: PoolEdge) {
+        // 同一个池子可能配置在多个 MintConfig 中，只保留一条边
+        if self.edges.iter().any(|existing| existing.keys.pool == edge.keys.pool) {
+            return;
+        }
+        let index = self.edges.len();
+        self.adjacency.entry(edge.base_mint).or_default().push(index);
+        self.adjacency.entry(edge.token_mint).or_default().push(index);
+        self.edges.push(edge);
+    }
+
+    pub fn edges(&self) -> &[PoolEdge] {
+        &self.edges
+    }
+
+    // 报价需要的全部账户
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        self.edges.iter().flat_map(|edge| edge.keys.accounts()).collect()
+    }
+
+    // 从 start 出发、2 到 max_hops 跳回到 start 的所有环路，池子和中间 mint 都不重复经过
+    pub fn cycles(&self, start: &Pubkey, max_hops: usize) -> Vec<Vec<Hop>> {
+        let mut cycles = Vec::new();
+        self.extend_cycles(start, &mut Vec::new(), max_hops, &mut cycles);
+        cycles
+    }
+
+    fn extend_cycles(
+        &self,
+        start: &Pubkey,
+        path: &mut Vec<Hop>,
+        max_hops: usize,
+        cycles: &mut Vec<Vec<Hop>>,
+    ) {
+        let current = path.last().map_or(*start, |hop| hop.to);
+        for &edge in self.adjacency.get(&current).into_iter().flatten() {
+            if path.iter().any(|hop| hop.edge == edge) {
+                continue;
+            }
+            let Some((to, direction)) = self.edges[edge].traverse(&current) else {
+                continue;
+            };
+            let hop = Hop {
+                edge,
+                from: current,
+                to,
+                direction,
+            };
+
+            if to == *start {
+                if !path.is_empty() {
+                    path.push(hop);
+                    cycles.push(path.clone());
+                    path.pop();
+                }
+                continue;
+            }
+            // 还需要至少一跳才能回到起点
+            if path.len() + 1 >= max_hops || path.iter().any(|hop| hop.from == to) {
+                continue;
+            }
+            path.push(hop);
+            self.extend_cycles(start, path, max_hops, cycles);
+            path.pop();
+        }
+    }
+}
+
+// 单跳的报价结果
+#[derive(Debug, Clone)]
+pub struct HopQuote {
+    // swap 指令使用的账户，由 DexPool::swap_keys 按交易方向和投入选取
+    pub keys: PoolKeys,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub direction: SwapDirection,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+// 环路的报价结果，起点和终点都是 base token
+#[derive(Debug, Clone)]
+pub struct CycleQuote {
+    pub hops: Vec<HopQuote>,
+    // 扣除交易成本之后的净利润（base token 最小单位），可能为负
+    pub net_profit: i64,
+    // 报价所用账户中最新的 slot
+    pub slot: u64,
+    pub sizing: SizingMethod,
+}
+
+impl CycleQuote {
+    pub fn amount_in(&self) -> u64 {
+        self.hops.first().map_or(0, |hop| hop.amount_in)
+    }
+
+    pub fn amount_out(&self) -> u64 {
+        self.hops.last().map_or(0, |hop| hop.amount_out)
+    }
+
+    pub fn gross_profit(&self) -> i64 {
+        self.amount_out() as i64 - self.amount_in() as i64
+    }
+
+    pub fn pools(&self) -> Vec<Pubkey> {
+        self.hops.iter().map(|hop| hop.keys.pool).collect()
+    }
+
+    pub fn dexes(&self) -> Vec<DexType> {
+        self.hops.iter().map(|hop| hop.keys.dex).collect()
+    }
+
+    // 按路径统计落地率时使用的标识
+    pub fn label(&self) -> String {
+        self.hops
+            .iter()
+            .map(|hop| format!("{} {}", hop.keys.dex, hop.keys.pool))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    // 所有池子都属于同一个 mint 的两跳环路，可以交给现有的单 mint 交易构建
+    pub fn single_mint(&self) -> Option<Pubkey> {
+        match self.hops.as_slice() {
+            [first, second] if first.to_mint == second.from_mint => Some(first.to_mint),
+            _ => None,
+        }
+    }
+
+    // 只保留环路中用到的池子，并换成本次 swap 使用的账户，交给单 mint 交易构建
+    pub fn restrict(&self, pool_data: &MintPoolData) -> MintPoolData {
+        MintPoolData {
+            pools: self.hops.iter().map(|hop| hop.keys.clone()).collect(),
+            ..pool_data.clone()
+        }
+    }
+}
+
+impl fmt::Display for CycleQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.amount_in())?;
+        for hop in &self.hops {
+            write!(
+                f,
+                " -> [{} {}] -> {} {}",
+                hop.keys.dex, hop.keys.pool, hop.amount_out, hop.to_mint
+            )?;
+        }
+        write!(
+            f,
+            " (net profit {}, slot {}, {} sizing)",
+            self.net_profit, self.slot, self.sizing
+        )
+    }
+}
+
+pub fn quote_cycle(
+    graph: &RouteGraph,
+    cycle: &[Hop],
+    snapshot: &AccountSnapshot,
+    amount_in: u64,
+    cost: &TradeCost,
+) -> anyhow::Result<CycleQuote> {
+    let mut hops = Vec::with_capacity(cycle.len());
+    let mut accounts = Vec::new();
+    let mut amount = amount_in;
+    for hop in cycle {
+        let keys = &graph.edges[hop.edge].keys;
+        let amount_out = quote_exact_in(keys, snapshot, hop.direction, amount)?;
+        if amount_out == 0 {
+            return Err(anyhow::anyhow!("{} pool {} returns nothing", keys.dex, keys.pool));
+        }
+        accounts.extend(keys.accounts());
+        hops.push(HopQuote {
+            keys: swap_keys(keys, snapshot, hop.direction, amount)?,
+            from_mint: hop.from,
+            to_mint: hop.to,
+            direction: hop.direction,
+            amount_in: amount,
+            amount_out,
+        });
+        amount = amount_out;
+    }
+
+    Ok(CycleQuote {
+        hops,
+        net_profit: amount as i64 - amount_in as i64 - cost.total(amount_in) as i64,
+        slot: snapshot.slot(&accounts),
+        sizing: SizingMethod::Fixed,
+    })
+}
+
+// 对所有环路按 sizing 选择投入并报价，返回净利润最高的一条
+pub fn best_cycle(
+    graph: &RouteGraph,
+    cycles: &[Vec<Hop>],
+    snapshot: &AccountSnapshot,
+    sizing: &Sizing,
+    cost: &TradeCost,
+) -> Option<CycleQuote> {
+    let mut best: Option<CycleQuote> = None;
+    for cycle in cycles {
+        let legs: Vec<(&PoolKeys, SwapDirection)> = cycle
+            .iter()
+            .map(|hop| (&graph.edges[hop.edge].keys, hop.direction))
+            .collect();
+        let Some(trade) = size_route(&legs, snapshot, cost, sizing) else {
+            debug!("Skipping {}-hop cycle: no quote", cycle.len());
+            continue;
+        };
+        match quote_cycle(graph, cycle, snapshot, trade.amount_in(), cost) {
+            Ok(mut quote) => {
+                quote.sizing = trade.method;
+                if best.as_ref().map_or(true, |best| quote.net_profit > best.net_profit) {
+                    best = Some(quote);
+                }
+            }
+            Err(e) => debug!("Skipping {}-hop cycle: {}", cycle.len(), e),
+        }
+    }
+    best
+}
+
+// 环路报价参数：起点为 [routing.cycle] 的 base_mint，默认 SOL
+pub fn cycle_params(config: &Config, cycle_config: &CycleConfig) -> anyhow::Result<QuoteParams> {
+    let base_mint = cycle_config.base_mint()?;
+    // 与 QuoteParams::new 一致，交易成本换算成 base token
+    let fixed = fixed_cost(
+        config,
+        static_compute_unit_price(config),
+        None,
+        &base_mint,
+        cycle_config.base_per_sol,
+    )?;
+    Ok(QuoteParams {
+        base_mint,
+        amount_in: cycle_config.amount_in,
+        min_profit: cycle_config.min_profit,
+        cost: TradeCost {
+            fixed,
+            flash_loan_fee_ppm: flash_loan_fee_ppm(config),
+        },
+        optimize_size: config
+            .quote
+            .as_ref()
+            .and_then(|quote_config| quote_config.optimize_size)
+            .unwrap_or(true),
+        max_amount_in: cycle_config.max_amount_in,
+        flash_loan_available: flash_loan_available(config),
+        base_per_sol: cycle_config.base_per_sol,
+    })
+}
+
+// 拉取图中所有账户并报价，只有净利润达到阈值的环路才会返回
+pub async fn find_profitable_cycle(
+    rpc_client: &NonblockingRpcClient,
+    graph: &RouteGraph,
+    max_hops: usize,
+    fetch_policy: &FetchPolicy,
+    params: &QuoteParams,
+    sizing: &Sizing,
+) -> anyhow::Result<Option<CycleQuote>> {
+    let cycles = graph.cycles(&params.base_mint, max_hops);
+    if cycles.is_empty() {
+        return Ok(None);
+    }
+    let snapshot = AccountSnapshot::fetch(rpc_client, &graph.accounts(), fetch_policy).await?;
+
+    match best_cycle(graph, &cycles, &snapshot, sizing, &params.cost) {
+        Some(cycle) if cycle.net_profit >= params.min_profit as i64 => Ok(Some(cycle)),
+        Some(cycle) => {
+            debug!("Best cycle is below threshold: {}", cycle);
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::raydium::fixtures::amm_pool;
+    use solana_sdk::account::Account;
+    use std::collections::HashSet;
+
+    const SOL_RESERVE: u64 = 1_000_000_000_000;
+    const TOKEN_RESERVE: u64 = 150_000_000_000;
+
+    fn pool_data(mint: Pubkey, pools: Vec<PoolKeys>) -> MintPoolData {
+        MintPoolData {
+            mint,
+            wallet_account: Pubkey::new_unique(),
+            pools,
+            token_program: spl_token::id(),
+            base_token_program: spl_token::id(),
+        }
+    }
+
+    fn pool_sets(cycles: &[Vec<Hop>], graph: &RouteGraph) -> HashSet<Vec<Pubkey>> {
+        cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(|hop| graph.edges()[hop.edge].keys.pool).collect())
+            .collect()
+    }
+
+    // SOL 与 A 之间两个池子，SOL 与 B、A 与 B 之间各一个池子
+    struct Triangle {
+        sol: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        sol_a: [PoolKeys; 2],
+        sol_b: PoolKeys,
+        a_b: PoolKeys,
+        graph: RouteGraph,
+        snapshot: AccountSnapshot,
+    }
+
+    fn triangle() -> Triangle {
+        let (sol, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts: HashMap<Pubkey, (u64, Account)> = HashMap::new();
+        // 第二个 SOL/A 池子里 A 更贵，先在第一个池子买入再到第二个池子卖出可以获利
+        let sol_a = [
+            amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE),
+            amm_pool(&mut accounts, SOL_RESERVE * 11 / 10, TOKEN_RESERVE),
+        ];
+        let sol_b = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let a_b = amm_pool(&mut accounts, TOKEN_RESERVE, TOKEN_RESERVE);
+        let members = [
+            (sol, pool_data(mint_a, sol_a.to_vec())),
+            (sol, pool_data(mint_b, vec![sol_b.clone()])),
+            // A 作为 base 的 mint B
+            (mint_a, pool_data(mint_b, vec![a_b.clone()])),
+        ];
+        let graph = RouteGraph::new(members.iter().map(|(base_mint, pool_data)| (*base_mint, pool_data)));
+        Triangle {
+            sol,
+            mint_a,
+            mint_b,
+            sol_a,
+            sol_b,
+            a_b,
+            graph,
+            snapshot: AccountSnapshot::from_accounts(accounts),
+        }
+    }
+
+    #[test]
+    fn new_keeps_one_edge_per_pool() {
+        let mut accounts = HashMap::new();
+        let shared = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let other = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let (sol, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first = pool_data(mint, vec![shared.clone(), other]);
+        let second = pool_data(mint, vec![shared]);
+
+        let graph = RouteGraph::new([(sol, &first), (sol, &second)]);
+        assert_eq!(graph.edges().len(), 2);
+        assert_eq!(graph.accounts().len(), 6);
+    }
+
+    #[test]
+    fn two_hops_only_round_trip_through_one_mint() {
+        let triangle = triangle();
+        let cycles = triangle.graph.cycles(&triangle.sol, 2);
+        let [first, second] = &triangle.sol_a;
+        assert_eq!(
+            pool_sets(&cycles, &triangle.graph),
+            HashSet::from([vec![first.pool, second.pool], vec![second.pool, first.pool]])
+        );
+        for cycle in &cycles {
+            assert_eq!(cycle[0].to, triangle.mint_a);
+            assert_eq!(cycle[0].direction, SwapDirection::BaseToToken);
+            assert_eq!(cycle[1].direction, SwapDirection::TokenToBase);
+        }
+    }
+
+    #[test]
+    fn three_hops_add_both_directions_around_the_triangle() {
+        let triangle = triangle();
+        let cycles = triangle.graph.cycles(&triangle.sol, 3);
+        let [first, second] = &triangle.sol_a;
+        let (sol_b, a_b) = (triangle.sol_b.pool, triangle.a_b.pool);
+        assert_eq!(
+            pool_sets(&cycles, &triangle.graph),
+            HashSet::from([
+                vec![first.pool, second.pool],
+                vec![second.pool, first.pool],
+                vec![first.pool, a_b, sol_b],
+                vec![second.pool, a_b, sol_b],
+                vec![sol_b, a_b, first.pool],
+                vec![sol_b, a_b, second.pool],
+            ])
+        );
+
+        let via_b = cycles
+            .iter()
+            .find(|cycle| triangle.graph.edges()[cycle[0].edge].keys.pool == sol_b)
+            .unwrap();
+        let mints: Vec<Pubkey> = via_b.iter().map(|hop| hop.to).collect();
+        assert_eq!(mints, vec![triangle.mint_b, triangle.mint_a, triangle.sol]);
+        // B -> A 经过以 A 为 base 的池子，方向是 token 换 base
+        assert_eq!(via_b[1].direction, SwapDirection::TokenToBase);
+    }
+
+    #[test]
+    fn cycles_do_not_revisit_intermediate_mints() {
+        let (sol, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = HashMap::new();
+        let sol_a = pool_data(
+            mint_a,
+            vec![
+                amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE),
+                amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE),
+            ],
+        );
+        // A 与 B 之间两个池子，SOL -> A -> B -> A -> SOL 会重复经过 A
+        let a_b = pool_data(
+            mint_b,
+            vec![
+                amm_pool(&mut accounts, TOKEN_RESERVE, TOKEN_RESERVE),
+                amm_pool(&mut accounts, TOKEN_RESERVE, TOKEN_RESERVE),
+            ],
+        );
+        let graph = RouteGraph::new([(sol, &sol_a), (mint_a, &a_b)]);
+
+        let cycles = graph.cycles(&sol, 4);
+        assert_eq!(cycles.len(), 2);
+        for cycle in &cycles {
+            let visited: HashSet<Pubkey> = cycle.iter().map(|hop| hop.from).collect();
+            assert_eq!(visited.len(), cycle.len());
+            assert!(cycle.iter().all(|hop| hop.to != mint_b));
+        }
+    }
+
+    #[test]
+    fn quote_cycle_chains_hop_outputs_and_subtracts_costs() {
+        let triangle = triangle();
+        let cycles = triangle.graph.cycles(&triangle.sol, 2);
+        let [first, _] = &triangle.sol_a;
+        let cycle = cycles
+            .iter()
+            .find(|cycle| triangle.graph.edges()[cycle[0].edge].keys.pool == first.pool)
+            .unwrap();
+        let cost = TradeCost {
+            fixed: 5_000,
+            flash_loan_fee_ppm: 0,
+        };
+
+        let quote = quote_cycle(&triangle.graph, cycle, &triangle.snapshot, 1_000_000_000, &cost).unwrap();
+        assert_eq!(quote.hops.len(), 2);
+        assert_eq!(quote.amount_in(), 1_000_000_000);
+        assert_eq!(quote.hops[0].amount_out, quote.hops[1].amount_in);
+        assert_eq!(quote.hops[1].to_mint, triangle.sol);
+        assert_eq!(quote.net_profit, quote.gross_profit() - 5_000);
+        assert!(quote.net_profit > 0);
+        assert_eq!(quote.single_mint(), Some(triangle.mint_a));
+        assert_eq!(quote.slot, 1);
+    }
+
+    #[test]
+    fn best_cycle_picks_the_profitable_direction() {
+        let triangle = triangle();
+        let cycles = triangle.graph.cycles(&triangle.sol, 3);
+        let [first, second] = &triangle.sol_a;
+        let cost = TradeCost {
+            fixed: 5_000,
+            flash_loan_fee_ppm: 0,
+        };
+
+        let best = best_cycle(
+            &triangle.graph,
+            &cycles,
+            &triangle.snapshot,
+            &Sizing::Optimal {
+                max_amount_in: 100_000_000_000,
+            },
+            &cost,
+        )
+        .unwrap();
+        assert_eq!(best.pools(), vec![first.pool, second.pool]);
+        assert_eq!(best.sizing, SizingMethod::ClosedForm);
+        assert!(best.net_profit > 0);
+
+        // 只保留环路用到的池子，顺序与环路一致
+        let member = pool_data(triangle.mint_a, vec![second.clone(), triangle.sol_b.clone(), first.clone()]);
+        let restricted = best.restrict(&member);
+        assert_eq!(restricted.mint, triangle.mint_a);
+        let pools: Vec<Pubkey> = restricted.pools.iter().map(|keys| keys.pool).collect();
+        assert_eq!(pools, vec![first.pool, second.pool]);
+    }
+}
@@ -0,0 +1,837 @@
+use crate::accounts::{fetch_multiple_accounts_with_slot, FetchPolicy};
+use crate::config::{Config, MintConfig, QuoteConfig};
+use crate::constants::sol_mint;
+use crate::dex::{adapter, collect_pool_accounts, collect_pool_keys, DexType};
+use crate::jito::tip_amount;
+use crate::kamino::DEFAULT_FEE_PPM;
+use crate::pools::MintPoolData;
+use crate::priority_fee::static_compute_unit_price;
+use crate::quote::sizing::{size_route, SizeLimits, Sizing, SizingMethod, TradeCost};
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use tracing::debug;
+
+pub mod clmm_math;
+pub mod dlmm;
+pub mod graph;
+pub mod pump;
+pub mod raydium;
+pub mod raydium_clmm;
+pub mod sizing;
+pub mod whirlpool;
+
+// 每笔交易的基础签名费（lamports）
+pub const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// 交易方向：先用 base token（默认 SOL）买入 token，再把 token 卖回 base token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapDirection {
+    BaseToToken,
+    TokenToBase,
+}
+
+// 报价和交易所需的池子账户，由各个 DEX 解析池子后写入 MintPoolData
+#[derive(Debug, Clone)]
+pub struct PoolKeys {
+    pub dex: DexType,
+    pub pool: Pubkey,
+    pub token_vault: Pubkey,
+    // 池子中 base token 一侧的 vault
+    pub base_vault: Pubkey,
+    // 报价额外需要的账户：CP/CLMM 的 amm_config、tick array、bin array 等
+    pub extra_accounts: Vec<Pubkey>,
+    // 只有 swap 指令需要、报价不需要读取的账户：oracle、observation、手续费钱包等
+    pub instruction_accounts: Vec<Pubkey>,
+}
+
+impl PoolKeys {
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.pool, self.token_vault, self.base_vault];
+        accounts.extend(self.extra_accounts.iter().copied());
+        accounts
+    }
+}
+
+// 单个池子的报价结果
+#[derive(Debug, Clone)]
+pub struct PoolQuote {
+    pub dex: DexType,
+    pub pool: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    // swap 指令使用的账户，由 DexPool::swap_keys 按交易方向和投入选取
+    pub keys: PoolKeys,
+}
+
+// base -> token -> base 两跳路径的报价结果
+#[derive(Debug, Clone)]
+pub struct RouteQuote {
+    pub buy: PoolQuote,
+    pub sell: PoolQuote,
+    // 扣除交易手续费和小费之后的净利润（base token 最小单位），可能为负
+    pub net_profit: i64,
+    // 报价所用账户中最新的 slot
+    pub slot: u64,
+    pub sizing: SizingMethod,
+}
+
+impl RouteQuote {
+    pub fn gross_profit(&self) -> i64 {
+        self.sell.amount_out as i64 - self.buy.amount_in as i64
+    }
+
+    pub fn dexes(&self) -> Vec<DexType> {
+        vec![self.buy.dex, self.sell.dex]
+    }
+
+    // 按路径统计落地率时使用的标识
+    pub fn label(&self) -> String {
+        format!("{} {} -> {} {}", self.buy.dex, self.buy.pool, self.sell.dex, self.sell.pool)
+    }
+
+    // 只保留路径中用到的两个池子，并换成本次 swap 使用的账户，交给交易构建
+    pub fn restrict(&self, pool_data: &MintPoolData) -> MintPoolData {
+        MintPoolData {
+            pools: vec![self.buy.keys.clone(), self.sell.keys.clone()],
+            ..pool_data.clone()
+        }
+    }
+}
+
+impl fmt::Display for RouteQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> [{} {}] -> {} -> [{} {}] -> {} (net profit {}, slot {}, {} sizing)",
+            self.buy.amount_in,
+            self.buy.dex,
+            self.buy.pool,
+            self.buy.amount_out,
+            self.sell.dex,
+            self.sell.pool,
+            self.sell.amount_out,
+            self.net_profit,
+            self.slot,
+            self.sizing
+        )
+    }
+}
+
+// 链上账户及其对应的 slot
+#[derive(Debug, Clone)]
+pub struct SlotAccount {
+    pub slot: u64,
+    pub account: Account,
+}
+
+// 某一时刻链上账户的快照，报价只读取快照中的数据
+#[derive(Debug, Default, Clone)]
+pub struct AccountSnapshot {
+    accounts: HashMap<Pubkey, SlotAccount>,
+}
+
+impl AccountSnapshot {
+    pub fn from_accounts(accounts: HashMap<Pubkey, (u64, Account)>) -> Self {
+        Self {
+            accounts: accounts
+                .into_iter()
+                .map(|(pubkey, (slot, account))| (pubkey, SlotAccount { slot, account }))
+                .collect(),
+        }
+    }
+
+    pub async fn fetch(
+        rpc_client: &NonblockingRpcClient,
+        pubkeys: &[Pubkey],
+        policy: &FetchPolicy,
+    ) -> anyhow::Result<Self> {
+        Ok(Self::from_accounts(
+            fetch_multiple_accounts_with_slot(rpc_client, pubkeys, policy).await?,
+        ))
+    }
+
+    // 只接受不早于当前数据的更新，返回是否生效
+    pub fn apply(&mut self, pubkey: Pubkey, slot: u64, account: Account) -> bool {
+        match self.accounts.get(&pubkey) {
+            Some(existing) if existing.slot > slot => false,
+            _ => {
+                self.accounts.insert(pubkey, SlotAccount { slot, account });
+                true
+            }
+        }
+    }
+
+    // 合并另一份快照，同样只接受不早于当前数据的账户
+    pub fn merge(&mut self, other: AccountSnapshot) {
+        for (pubkey, account) in other.accounts {
+            self.apply(pubkey, account.slot, account.account);
+        }
+    }
+
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.accounts.contains_key(pubkey)
+    }
+
+    pub fn pubkeys(&self) -> impl Iterator<Item = &Pubkey> {
+        self.accounts.keys()
+    }
+
+    // 一组账户中最新的 slot，报价以此说明基于哪个 slot 的状态
+    pub fn slot(&self, pubkeys: &[Pubkey]) -> u64 {
+        pubkeys
+            .iter()
+            .filter_map(|pubkey| self.accounts.get(pubkey))
+            .map(|account| account.slot)
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> anyhow::Result<&Account> {
+        self.accounts
+            .get(pubkey)
+            .map(|account| &account.account)
+            .ok_or_else(|| anyhow::anyhow!("Account {} is missing from snapshot", pubkey))
+    }
+
+    pub fn data(&self, pubkey: &Pubkey) -> anyhow::Result<&[u8]> {
+        Ok(&self.get(pubkey)?.data)
+    }
+
+    // 读取 SPL token 账户余额，amount 位于 mint(32) + owner(32) 之后
+    pub fn token_balance(&self, vault: &Pubkey) -> anyhow::Result<u64> {
+        read_u64(self.data(vault)?, 64)
+    }
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> anyhow::Result<[u8; N]> {
+    let bytes = data.get(offset..offset + N).ok_or_else(|| {
+        anyhow::anyhow!("Account data too short to read {} bytes at {}", N, offset)
+    })?;
+    Ok(bytes.try_into()?)
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> anyhow::Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_i32(data: &[u8], offset: usize) -> anyhow::Result<i32> {
+    Ok(i32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> anyhow::Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_u128(data: &[u8], offset: usize) -> anyhow::Result<u128> {
+    Ok(u128::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_i128(data: &[u8], offset: usize) -> anyhow::Result<i128> {
+    Ok(i128::from_le_bytes(read_bytes(data, offset)?))
+}
+
+pub fn read_pubkey(data: &[u8], offset: usize) -> anyhow::Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+// 按照 DEX 类型分发到各自的报价实现
+pub fn quote_exact_in(
+    keys: &PoolKeys,
+    snapshot: &AccountSnapshot,
+    direction: SwapDirection,
+    amount_in: u64,
+) -> anyhow::Result<u64> {
+    adapter(keys.dex).quote_exact_in(keys, snapshot, direction, amount_in)
+}
+
+pub fn swap_keys(
+    keys: &PoolKeys,
+    snapshot: &AccountSnapshot,
+    direction: SwapDirection,
+    amount_in: u64,
+) -> anyhow::Result<PoolKeys> {
+    adapter(keys.dex).swap_keys(keys, snapshot, direction, amount_in)
+}
+
+// 发送一笔交易的固定成本：签名费 + 优先费 + 小费
+// compute_unit_price 为本次使用的 CU 价格（估计值或 [spam] 中的固定值）
+// [quote] 未指定小费时按 [jito] 计算，expected_profit（lamports）用于 tip_percent，未知时按固定小费
+pub fn transaction_cost(
+    config: &Config,
+    quote_config: &QuoteConfig,
+    compute_unit_price: u64,
+    expected_profit: Option<i64>,
+) -> u64 {
+    let priority_fee = config.bot.compute_unit_limit as u64 * compute_unit_price / 1_000_000;
+    let jito_tip = match &config.jito {
+        // bundle 中的小费交易还需要一笔签名费
+        Some(jito_config) if jito_config.enabled => {
+            tip_amount(jito_config, expected_profit) + SIGNATURE_FEE_LAMPORTS
+        }
+        _ => 0,
+    };
+    SIGNATURE_FEE_LAMPORTS + priority_fee + quote_config.tip_lamports.unwrap_or(jito_tip)
+}
+
+// 把以 lamports 计的金额换算成 base token 最小单位，向上取整，成本不会被低估
+pub fn lamports_to_base(lamports: u64, base_per_sol: u64) -> u64 {
+    let base = (lamports as u128 * base_per_sol as u128).div_ceil(LAMPORTS_PER_SOL as u128);
+    base.min(u64::MAX as u128) as u64
+}
+
+// base 为 SOL 时原样返回；否则按 base_per_sol 换算，没有配置时报错而不是按 0 成本报价
+pub fn lamports_in_base(lamports: u64, base_mint: &Pubkey, base_per_sol: Option<u64>) -> anyhow::Result<u64> {
+    if *base_mint == sol_mint() {
+        return Ok(lamports);
+    }
+    match base_per_sol {
+        Some(base_per_sol) => Ok(lamports_to_base(lamports, base_per_sol)),
+        None => Err(anyhow::anyhow!(
+            "Base mint {} is not SOL, base_per_sol is required to convert transaction costs",
+            base_mint
+        )),
+    }
+}
+
+// lamports_in_base 的反向换算，用于按利润比例计算小费；无法换算时为 None
+pub fn base_in_lamports(amount: i64, base_mint: &Pubkey, base_per_sol: Option<u64>) -> Option<i64> {
+    if *base_mint == sol_mint() {
+        return Some(amount);
+    }
+    let base_per_sol = base_per_sol.filter(|base_per_sol| *base_per_sol > 0)?;
+    let lamports = amount as i128 * LAMPORTS_PER_SOL as i128 / base_per_sol as i128;
+    Some(lamports.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
+
+// 报价使用的固定成本（base token）；没有 [quote] 时只计签名费
+// expected_profit 以 base token 计，用于按利润比例计算的 Jito 小费
+pub fn fixed_cost(
+    config: &Config,
+    compute_unit_price: u64,
+    expected_profit: Option<i64>,
+    base_mint: &Pubkey,
+    base_per_sol: Option<u64>,
+) -> anyhow::Result<u64> {
+    let expected_profit =
+        expected_profit.and_then(|profit| base_in_lamports(profit, base_mint, base_per_sol));
+    let lamports = match &config.quote {
+        Some(quote_config) => {
+            transaction_cost(config, quote_config, compute_unit_price, expected_profit)
+        }
+        None => SIGNATURE_FEE_LAMPORTS,
+    };
+    lamports_in_base(lamports, base_mint, base_per_sol)
+}
+
+// 开启闪电贷时单笔最多可借入的数量
+pub fn flash_loan_available(config: &Config) -> u64 {
+    match &config.kamino_flashloan {
+        Some(flashloan_config) if flashloan_config.enabled => flashloan_config.max_borrow.unwrap_or(0),
+        _ => 0,
+    }
+}
+
+// 闪电贷手续费率（百万分之几），未开启时为 0
+// 配置了多个来源时按最便宜的来源报价，发送时再按交易规模选择来源
+pub fn flash_loan_fee_ppm(config: &Config) -> u64 {
+    match &config.kamino_flashloan {
+        Some(flashloan_config) if flashloan_config.enabled => {
+            let default_fee_ppm = flashloan_config.fee_ppm.unwrap_or(DEFAULT_FEE_PPM);
+            config
+                .flash_loan_providers
+                .as_ref()
+                .and_then(|providers| {
+                    providers
+                        .iter()
+                        .map(|provider| provider.fee_ppm.unwrap_or(default_fee_ppm))
+                        .min()
+                })
+                .unwrap_or(default_fee_ppm)
+        }
+        _ => 0,
+    }
+}
+
+// 单个 mint 的报价参数，金额均以 base token 的最小单位计
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteParams {
+    pub base_mint: Pubkey,
+    pub amount_in: u64,
+    pub min_profit: u64,
+    pub cost: TradeCost,
+    pub optimize_size: bool,
+    pub max_amount_in: Option<u64>,
+    pub flash_loan_available: u64,
+    // 换算交易成本用，base 为 SOL 时不需要
+    pub base_per_sol: Option<u64>,
+}
+
+impl QuoteParams {
+    pub fn new(
+        config: &Config,
+        quote_config: &QuoteConfig,
+        mint_config: &MintConfig,
+    ) -> anyhow::Result<Self> {
+        let base_mint = mint_config.base_mint()?;
+        // 交易成本以 lamports 计，换算成 base token；闪电贷手续费以 base token 计，始终计入
+        // 报价时按 [spam] 的固定 CU 价格和固定小费计，选出路径后由 with_costs 替换
+        let fixed = lamports_in_base(
+            transaction_cost(config, quote_config, static_compute_unit_price(config), None),
+            &base_mint,
+            mint_config.base_per_sol,
+        )?;
+        // 开启闪电贷时，MintConfig 的 max_flash_loan 覆盖全局的 max_borrow
+        let flash_loan_available = match flash_loan_available(config) {
+            0 => 0,
+            max_borrow => mint_config.max_flash_loan.unwrap_or(max_borrow),
+        };
+        Ok(Self {
+            base_mint,
+            amount_in: mint_config.amount_in.unwrap_or(quote_config.amount_in),
+            min_profit: match mint_config.min_profit {
+                Some(min_profit) => min_profit,
+                None => lamports_in_base(quote_config.min_profit_lamports, &base_mint, mint_config.base_per_sol)?,
+            },
+            cost: TradeCost {
+                fixed,
+                flash_loan_fee_ppm: flash_loan_fee_ppm(config),
+            },
+            optimize_size: quote_config.optimize_size.unwrap_or(true),
+            max_amount_in: mint_config.max_amount_in,
+            flash_loan_available,
+            base_per_sol: mint_config.base_per_sol,
+        })
+    }
+
+    // 按本次使用的 CU 价格和路径的预期利润（决定 tip_percent 部分的小费）重新计算固定成本
+    pub fn with_costs(
+        &self,
+        config: &Config,
+        compute_unit_price: u64,
+        expected_profit: Option<i64>,
+    ) -> anyhow::Result<Self> {
+        let fixed = fixed_cost(
+            config,
+            compute_unit_price,
+            expected_profit,
+            &self.base_mint,
+            self.base_per_sol,
+        )?;
+        Ok(Self {
+            cost: TradeCost { fixed, ..self.cost },
+            ..*self
+        })
+    }
+
+    // 开启 Jito 时 bundle 中的小费（lamports），与 with_costs 计入成本的小费相同
+    pub fn jito_tip(&self, config: &Config, expected_profit: Option<i64>) -> Option<u64> {
+        let jito_config = config.jito.as_ref().filter(|jito_config| jito_config.enabled)?;
+        let expected_profit = expected_profit
+            .and_then(|profit| base_in_lamports(profit, &self.base_mint, self.base_per_sol));
+        Some(tip_amount(jito_config, expected_profit))
+    }
+
+    // 按当前钱包余额确定本轮报价的投入规模
+    pub fn sizing(&self, wallet_balance: u64) -> Sizing {
+        let limits = SizeLimits {
+            wallet_balance,
+            flash_loan_available: self.flash_loan_available,
+            max_amount_in: self.max_amount_in,
+        };
+        Sizing::new(self.optimize_size, self.amount_in, &limits)
+    }
+}
+
+// 遍历所有 base -> token -> base 的两跳组合，每条路径按 sizing 选择投入，返回净利润最高的路径
+pub fn best_route(
+    pool_keys: &[PoolKeys],
+    snapshot: &AccountSnapshot,
+    sizing: &Sizing,
+    cost: &TradeCost,
+) -> Option<RouteQuote> {
+    let mut best: Option<RouteQuote> = None;
+
+    for buy_keys in pool_keys {
+        for sell_keys in pool_keys {
+            if sell_keys.pool == buy_keys.pool {
+                continue;
+            }
+            let legs = [
+                (buy_keys, SwapDirection::BaseToToken),
+                (sell_keys, SwapDirection::TokenToBase),
+            ];
+            let Some(trade) = size_route(&legs, snapshot, cost, sizing) else {
+                debug!(
+                    "Skipping {} pool {} -> {} pool {}: no quote",
+                    buy_keys.dex, buy_keys.pool, sell_keys.dex, sell_keys.pool
+                );
+                continue;
+            };
+
+            if best.as_ref().map_or(true, |route| trade.net_profit > route.net_profit) {
+                let swap_keys = swap_keys(buy_keys, snapshot, SwapDirection::BaseToToken, trade.amounts[0])
+                    .and_then(|buy| {
+                        Ok((buy, swap_keys(sell_keys, snapshot, SwapDirection::TokenToBase, trade.amounts[1])?))
+                    });
+                let (buy_swap_keys, sell_swap_keys) = match swap_keys {
+                    Ok(swap_keys) => swap_keys,
+                    Err(e) => {
+                        debug!(
+                            "Skipping {} pool {} -> {} pool {}: {}",
+                            buy_keys.dex, buy_keys.pool, sell_keys.dex, sell_keys.pool, e
+                        );
+                        continue;
+                    }
+                };
+                best = Some(RouteQuote {
+                    buy: PoolQuote {
+                        dex: buy_keys.dex,
+                        pool: buy_keys.pool,
+                        amount_in: trade.amounts[0],
+                        amount_out: trade.amounts[1],
+                        keys: buy_swap_keys,
+                    },
+                    sell: PoolQuote {
+                        dex: sell_keys.dex,
+                        pool: sell_keys.pool,
+                        amount_in: trade.amounts[1],
+                        amount_out: trade.amounts[2],
+                        keys: sell_swap_keys,
+                    },
+                    net_profit: trade.net_profit,
+                    slot: snapshot.slot(
+                        &[buy_keys.accounts(), sell_keys.accounts()].concat(),
+                    ),
+                    sizing: trade.method,
+                });
+            }
+        }
+    }
+
+    best
+}
+
+// 拉取最新的池子状态并报价，只有净利润达到阈值的路径才会返回
+pub async fn find_profitable_route(
+    rpc_client: &NonblockingRpcClient,
+    pool_data: &MintPoolData,
+    fetch_policy: &FetchPolicy,
+    params: &QuoteParams,
+    sizing: &Sizing,
+) -> anyhow::Result<Option<RouteQuote>> {
+    let accounts = collect_pool_accounts(pool_data);
+    let snapshot = AccountSnapshot::fetch(rpc_client, &accounts, fetch_policy).await?;
+
+    Ok(profitable_route(pool_data, &snapshot, params, sizing))
+}
+
+// 基于已有快照报价（例如由 stream 持续更新的快照），不发起 RPC 请求
+pub fn profitable_route(
+    pool_data: &MintPoolData,
+    snapshot: &AccountSnapshot,
+    params: &QuoteParams,
+    sizing: &Sizing,
+) -> Option<RouteQuote> {
+    let pool_keys = collect_pool_keys(pool_data);
+    let route = best_route(&pool_keys, snapshot, sizing, &params.cost);
+
+    match route {
+        Some(route) if route.net_profit >= params.min_profit as i64 => Some(route),
+        Some(route) => {
+            debug!("Best route for mint {} is below threshold: {}", pool_data.mint, route);
+            None
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::raydium::amm_quote_exact_in;
+    use crate::quote::raydium::fixtures::amm_pool;
+    use std::str::FromStr;
+
+    const SOL_RESERVE: u64 = 1_000_000_000_000;
+    const TOKEN_RESERVE: u64 = 150_000_000_000;
+    const AMOUNT_IN: u64 = 1_000_000_000;
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn config(extra: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+[bot]
+compute_unit_limit = 200000
+
+[rpc]
+url = "http://127.0.0.1:8899"
+
+[wallet]
+private_key = "test-key"
+
+[routing]
+mint_config_list = []
+
+[quote]
+enabled = true
+amount_in = 1000000000
+min_profit_lamports = 10000
+{}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn transaction_cost_of(config: &Config, compute_unit_price: u64, expected_profit: Option<i64>) -> u64 {
+        transaction_cost(config, config.quote.as_ref().unwrap(), compute_unit_price, expected_profit)
+    }
+
+    // 第一个池子的 token 便宜，第二个池子贵 10%，第三个与第一个相同
+    struct Pools {
+        cheap: PoolKeys,
+        expensive: PoolKeys,
+        pool_data: MintPoolData,
+        snapshot: AccountSnapshot,
+    }
+
+    fn pools() -> Pools {
+        let mut accounts = HashMap::new();
+        let cheap = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let expensive = amm_pool(&mut accounts, SOL_RESERVE * 11 / 10, TOKEN_RESERVE);
+        let same = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let mut pool_data = MintPoolData::new(USDC, &Pubkey::new_unique().to_string()).unwrap();
+        pool_data.pools = vec![cheap.clone(), expensive.clone(), same];
+        Pools {
+            cheap,
+            expensive,
+            pool_data,
+            snapshot: AccountSnapshot::from_accounts(accounts),
+        }
+    }
+
+    fn params(min_profit: u64, fixed: u64) -> QuoteParams {
+        QuoteParams {
+            base_mint: sol_mint(),
+            amount_in: AMOUNT_IN,
+            min_profit,
+            cost: TradeCost {
+                fixed,
+                flash_loan_fee_ppm: 0,
+            },
+            optimize_size: false,
+            max_amount_in: None,
+            flash_loan_available: 0,
+            base_per_sol: None,
+        }
+    }
+
+    // 在便宜的池子买入、贵的池子卖出 AMOUNT_IN 的毛利润
+    fn expected_gross_profit() -> i64 {
+        let token_out = amm_quote_exact_in(AMOUNT_IN, SOL_RESERVE, TOKEN_RESERVE, 25, 10_000).unwrap();
+        let base_out = amm_quote_exact_in(token_out, TOKEN_RESERVE, SOL_RESERVE * 11 / 10, 25, 10_000).unwrap();
+        base_out as i64 - AMOUNT_IN as i64
+    }
+
+    #[test]
+    fn transaction_cost_adds_signature_priority_fee_and_tip() {
+        let config = config("");
+        // 200_000 CU * 10_000 micro-lamports = 2_000 lamports
+        assert_eq!(transaction_cost_of(&config, 10_000, None), SIGNATURE_FEE_LAMPORTS + 2_000);
+
+        let config = self::config("tip_lamports = 1000");
+        assert_eq!(transaction_cost_of(&config, 0, None), SIGNATURE_FEE_LAMPORTS + 1_000);
+    }
+
+    #[test]
+    fn transaction_cost_includes_the_jito_tip_and_its_signature() {
+        let config = config(
+            r#"
+[jito]
+enabled = true
+block_engine_urls = ["https://mainnet.block-engine.jito.wtf"]
+tip_lamports = 10000
+tip_percent = 50
+max_tip_lamports = 80000
+"#,
+        );
+        // 固定小费
+        assert_eq!(transaction_cost_of(&config, 0, None), 2 * SIGNATURE_FEE_LAMPORTS + 10_000);
+        // 利润的 50%，不超过上限
+        assert_eq!(transaction_cost_of(&config, 0, Some(100_000)), 2 * SIGNATURE_FEE_LAMPORTS + 50_000);
+        assert_eq!(transaction_cost_of(&config, 0, Some(1_000_000)), 2 * SIGNATURE_FEE_LAMPORTS + 80_000);
+    }
+
+    #[test]
+    fn lamports_and_base_convert_both_ways() {
+        let usdc = Pubkey::from_str(USDC).unwrap();
+        // 150 USDC / SOL
+        let base_per_sol = Some(150_000_000);
+
+        assert_eq!(lamports_in_base(5_000, &sol_mint(), None).unwrap(), 5_000);
+        assert_eq!(lamports_in_base(5_000, &usdc, base_per_sol).unwrap(), 750);
+        // 成本向上取整
+        assert_eq!(lamports_in_base(1, &usdc, base_per_sol).unwrap(), 1);
+        assert!(lamports_in_base(5_000, &usdc, None).is_err());
+
+        assert_eq!(base_in_lamports(-5_000, &sol_mint(), None), Some(-5_000));
+        assert_eq!(base_in_lamports(750, &usdc, base_per_sol), Some(5_000));
+        assert_eq!(base_in_lamports(-750, &usdc, base_per_sol), Some(-5_000));
+        assert_eq!(base_in_lamports(750, &usdc, None), None);
+        assert_eq!(base_in_lamports(750, &usdc, Some(0)), None);
+    }
+
+    #[test]
+    fn best_route_buys_cheap_and_sells_expensive() {
+        let pools = pools();
+        let cost = TradeCost {
+            fixed: 5_000,
+            flash_loan_fee_ppm: 0,
+        };
+        let route = best_route(&pools.pool_data.pools, &pools.snapshot, &Sizing::Fixed(AMOUNT_IN), &cost).unwrap();
+
+        // 两个便宜的池子报价相同，先遍历到的胜出
+        assert_eq!(route.buy.pool, pools.cheap.pool);
+        assert_eq!(route.sell.pool, pools.expensive.pool);
+        assert_eq!(route.buy.amount_in, AMOUNT_IN);
+        assert_eq!(route.buy.amount_out, route.sell.amount_in);
+        assert_eq!(route.gross_profit(), expected_gross_profit());
+        assert_eq!(route.net_profit, expected_gross_profit() - 5_000);
+        assert_eq!(route.slot, 1);
+        assert_eq!(route.sizing, SizingMethod::Fixed);
+        assert_eq!(route.restrict(&pools.pool_data).pools.len(), 2);
+    }
+
+    #[test]
+    fn best_route_needs_two_pools() {
+        let pools = pools();
+        let single = [pools.cheap.clone()];
+        let route = best_route(&single, &pools.snapshot, &Sizing::Fixed(AMOUNT_IN), &TradeCost::default());
+        assert!(route.is_none());
+
+        // 缺少账户的池子被跳过
+        let missing = [pools.cheap.clone(), amm_pool(&mut HashMap::new(), SOL_RESERVE, TOKEN_RESERVE)];
+        let route = best_route(&missing, &pools.snapshot, &Sizing::Fixed(AMOUNT_IN), &TradeCost::default());
+        assert!(route.is_none());
+    }
+
+    #[test]
+    fn profitable_route_applies_the_min_profit_after_costs() {
+        let pools = pools();
+        let sizing = Sizing::Fixed(AMOUNT_IN);
+        let net_profit = expected_gross_profit() - 5_000;
+
+        let route = profitable_route(&pools.pool_data, &pools.snapshot, &params(net_profit as u64, 5_000), &sizing);
+        assert_eq!(route.unwrap().net_profit, net_profit);
+
+        let route =
+            profitable_route(&pools.pool_data, &pools.snapshot, &params(net_profit as u64 + 1, 5_000), &sizing);
+        assert!(route.is_none());
+
+        // 成本超过毛利润时即使阈值为 0 也不返回
+        let cost = expected_gross_profit() as u64 + 1;
+        assert!(profitable_route(&pools.pool_data, &pools.snapshot, &params(0, cost), &sizing).is_none());
+    }
+
+    #[test]
+    fn profitable_route_sizes_within_the_wallet_balance() {
+        let pools = pools();
+        let params = QuoteParams {
+            optimize_size: true,
+            ..params(0, 5_000)
+        };
+        let sizing = params.sizing(AMOUNT_IN * 10);
+        let route = profitable_route(&pools.pool_data, &pools.snapshot, &params, &sizing).unwrap();
+
+        assert_eq!(route.sizing, SizingMethod::ClosedForm);
+        assert!(route.buy.amount_in <= AMOUNT_IN * 10);
+        assert!(route.net_profit > expected_gross_profit() - 5_000);
+    }
+
+    fn mint_config(extra: &str) -> MintConfig {
+        toml::from_str(&format!(
+            r#"
+mint = "{}"
+base_mint = "{}"
+process_delay = 1000
+{}"#,
+            Pubkey::new_unique(),
+            USDC,
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn lamports_to_base_rounds_up_and_saturates() {
+        // 150 USDC / SOL
+        assert_eq!(lamports_to_base(1_000_000_000, 150_000_000), 150_000_000);
+        assert_eq!(lamports_to_base(6_667, 150_000_000), 1_001);
+        assert_eq!(lamports_to_base(1, 150_000_000), 1);
+        assert_eq!(lamports_to_base(0, 150_000_000), 0);
+        assert_eq!(lamports_to_base(u64::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn quote_params_convert_lamport_costs_into_the_base_token() {
+        let config = config(
+            r#"
+[spam]
+enabled = true
+sending_rpc_urls = []
+compute_unit_price = 10000
+"#,
+        );
+        let quote_config = config.quote.as_ref().unwrap();
+        let params = QuoteParams::new(&config, quote_config, &mint_config("base_per_sol = 150000000")).unwrap();
+
+        assert_eq!(params.base_mint, Pubkey::from_str(USDC).unwrap());
+        assert_eq!(params.amount_in, AMOUNT_IN);
+        // 签名费 5_000 + 优先费 2_000 lamports
+        assert_eq!(params.cost.fixed, 1_050);
+        // min_profit_lamports = 10_000
+        assert_eq!(params.min_profit, 1_500);
+
+        // MintConfig 中的 amount_in / min_profit 已经以 base token 计，不再换算
+        let mint_config = mint_config("base_per_sol = 150000000\namount_in = 50000000\nmin_profit = 20000");
+        let params = QuoteParams::new(&config, quote_config, &mint_config).unwrap();
+        assert_eq!((params.amount_in, params.min_profit), (50_000_000, 20_000));
+    }
+
+    #[test]
+    fn quote_params_require_base_per_sol_for_non_sol_bases() {
+        let config = config("");
+        let error = QuoteParams::new(&config, config.quote.as_ref().unwrap(), &mint_config("")).unwrap_err();
+        assert!(error.to_string().contains("base_per_sol is required"));
+    }
+
+    #[test]
+    fn jito_tip_and_costs_convert_the_expected_profit_to_lamports() {
+        let config = config(
+            r#"
+[jito]
+enabled = true
+block_engine_urls = ["https://mainnet.block-engine.jito.wtf"]
+tip_lamports = 1000
+tip_percent = 50
+"#,
+        );
+        let quote_config = config.quote.as_ref().unwrap();
+        let params = QuoteParams::new(&config, quote_config, &mint_config("base_per_sol = 150000000")).unwrap();
+
+        // 1_500 USDC 最小单位 = 10_000 lamports，小费取一半
+        assert_eq!(params.jito_tip(&config, Some(1_500)), Some(5_000));
+        assert_eq!(params.jito_tip(&config, None), Some(1_000));
+        // 两个签名 10_000 + 小费 5_000 lamports
+        assert_eq!(params.with_costs(&config, 0, Some(1_500)).unwrap().cost.fixed, 2_250);
+        assert_eq!(params.jito_tip(&self::config(""), Some(1_500)), None);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::dex::pump::pump_program_id;
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{read_pubkey, read_u64, AccountSnapshot, PoolKeys, SwapDirection};
+use solana_sdk::pubkey::Pubkey;
+
+pub const BASIS_POINTS_DENOMINATOR: u128 = 10_000;
+
+// Pool 字段偏移
+const POOL_BASE_TOKEN_ACCOUNT_OFFSET: usize = 139;
+const POOL_COIN_CREATOR_OFFSET: usize = 211;
+// GlobalConfig: discriminator + admin 之后是费率，8 个 protocol_fee_recipients 之后是 creator 费率
+const GLOBAL_CONFIG_LP_FEE_BPS_OFFSET: usize = 40;
+const GLOBAL_CONFIG_PROTOCOL_FEE_BPS_OFFSET: usize = 48;
+const GLOBAL_CONFIG_COIN_CREATOR_FEE_BPS_OFFSET: usize = 313;
+
+pub fn pump_global_config() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_config"], &pump_program_id()).0
+}
+
+fn ceil_div(numerator: u128, denominator: u128) -> u128 {
+    (numerator + denominator - 1) / denominator
+}
+
+// 与链上一致：每一项手续费单独按 bps 向上取整
+fn fee(amount: u128, basis_points: u64) -> u128 {
+    ceil_div(amount * basis_points as u128, BASIS_POINTS_DENOMINATOR)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PumpFees {
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    // 池子没有设置 coin_creator 时为 0
+    pub coin_creator_fee_basis_points: u64,
+}
+
+impl PumpFees {
+    pub fn load(global_config_data: &[u8], has_coin_creator: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            lp_fee_basis_points: read_u64(global_config_data, GLOBAL_CONFIG_LP_FEE_BPS_OFFSET)?,
+            protocol_fee_basis_points: read_u64(
+                global_config_data,
+                GLOBAL_CONFIG_PROTOCOL_FEE_BPS_OFFSET,
+            )?,
+            coin_creator_fee_basis_points: if has_coin_creator {
+                read_u64(global_config_data, GLOBAL_CONFIG_COIN_CREATOR_FEE_BPS_OFFSET)?
+            } else {
+                0
+            },
+        })
+    }
+
+    pub fn total_basis_points(&self) -> u64 {
+        self.lp_fee_basis_points + self.protocol_fee_basis_points + self.coin_creator_fee_basis_points
+    }
+}
+
+// Pump 的手续费全部以 quote token 计价
+#[derive(Debug, Clone, Copy)]
+pub struct PumpSwapResult {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub coin_creator_fee: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PumpQuoteState {
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    // 我们的 base token 是否在 Pump 池子的 base 一侧
+    pub base_mint_is_pool_base: bool,
+    pub fees: PumpFees,
+}
+
+impl PumpQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let global_config = keys
+            .extra_accounts
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Pump pool {} has no global config", keys.pool))?;
+        let data = snapshot.data(&keys.pool)?;
+        let has_coin_creator = read_pubkey(data, POOL_COIN_CREATOR_OFFSET)? != Pubkey::default();
+        let fees = PumpFees::load(snapshot.data(global_config)?, has_coin_creator)?;
+
+        let base_mint_is_pool_base = read_pubkey(data, POOL_BASE_TOKEN_ACCOUNT_OFFSET)? == keys.base_vault;
+        let base_mint_reserve = snapshot.token_balance(&keys.base_vault)?;
+        let token_reserve = snapshot.token_balance(&keys.token_vault)?;
+        let (base_reserve, quote_reserve) = if base_mint_is_pool_base {
+            (base_mint_reserve, token_reserve)
+        } else {
+            (token_reserve, base_mint_reserve)
+        };
+
+        Ok(Self {
+            base_reserve,
+            quote_reserve,
+            base_mint_is_pool_base,
+            fees,
+        })
+    }
+
+    // 输入 quote 买 base 即 buy，输入 base 卖出 quote 即 sell
+    fn is_buy(&self, direction: SwapDirection) -> bool {
+        match direction {
+            SwapDirection::BaseToToken => !self.base_mint_is_pool_base,
+            SwapDirection::TokenToBase => self.base_mint_is_pool_base,
+        }
+    }
+
+    pub fn quote_exact_in(&self, direction: SwapDirection, amount_in: u64) -> anyhow::Result<u64> {
+        if self.is_buy(direction) {
+            Ok(self.buy_quote_input(amount_in)?.base_amount)
+        } else {
+            Ok(self.sell_base_input(amount_in)?.quote_amount)
+        }
+    }
+
+    // buy 的手续费按输入折算，费率为 bps / (10000 + bps)；
+    // sell 的手续费从输出中扣除，等价于把 quote 储备按 (10000 - bps) / 10000 缩小、输入不收费
+    pub fn constant_product(&self, direction: SwapDirection) -> ConstantProduct {
+        let basis_points = self.fees.total_basis_points();
+        if self.is_buy(direction) {
+            ConstantProduct {
+                reserve_in: self.quote_reserve,
+                reserve_out: self.base_reserve,
+                fee_numerator: basis_points,
+                fee_denominator: BASIS_POINTS_DENOMINATOR as u64 + basis_points,
+            }
+        } else {
+            let reserve_out = self.quote_reserve as u128
+                * BASIS_POINTS_DENOMINATOR.saturating_sub(basis_points as u128)
+                / BASIS_POINTS_DENOMINATOR;
+            ConstantProduct {
+                reserve_in: self.base_reserve,
+                reserve_out: reserve_out as u64,
+                fee_numerator: 0,
+                fee_denominator: 1,
+            }
+        }
+    }
+
+    // sell：卖出 base，从换出的 quote 中扣除各项手续费
+    pub fn sell_base_input(&self, base_amount_in: u64) -> anyhow::Result<PumpSwapResult> {
+        if self.base_reserve == 0 || self.quote_reserve == 0 {
+            return Err(anyhow::anyhow!("Pump pool has no liquidity"));
+        }
+        let quote_amount_out = self.quote_reserve as u128 * base_amount_in as u128
+            / (self.base_reserve as u128 + base_amount_in as u128);
+        let lp_fee = fee(quote_amount_out, self.fees.lp_fee_basis_points);
+        let protocol_fee = fee(quote_amount_out, self.fees.protocol_fee_basis_points);
+        let coin_creator_fee = fee(quote_amount_out, self.fees.coin_creator_fee_basis_points);
+        let quote_amount = quote_amount_out
+            .checked_sub(lp_fee + protocol_fee + coin_creator_fee)
+            .ok_or_else(|| anyhow::anyhow!("Pump sell output is smaller than fees"))?;
+
+        Ok(PumpSwapResult {
+            base_amount: base_amount_in,
+            quote_amount: quote_amount as u64,
+            lp_fee: lp_fee as u64,
+            protocol_fee: protocol_fee as u64,
+            coin_creator_fee: coin_creator_fee as u64,
+        })
+    }
+
+    // buy：链上指令指定 base 输出，所需 quote 在交换金额之上再加各项手续费
+    pub fn buy_base_output(&self, base_amount_out: u64) -> anyhow::Result<PumpSwapResult> {
+        if base_amount_out >= self.base_reserve {
+            return Err(anyhow::anyhow!("Pump pool cannot provide {} base out", base_amount_out));
+        }
+        let quote_amount_in = ceil_div(
+            self.quote_reserve as u128 * base_amount_out as u128,
+            (self.base_reserve - base_amount_out) as u128,
+        );
+        let lp_fee = fee(quote_amount_in, self.fees.lp_fee_basis_points);
+        let protocol_fee = fee(quote_amount_in, self.fees.protocol_fee_basis_points);
+        let coin_creator_fee = fee(quote_amount_in, self.fees.coin_creator_fee_basis_points);
+        let quote_amount = quote_amount_in + lp_fee + protocol_fee + coin_creator_fee;
+
+        Ok(PumpSwapResult {
+            base_amount: base_amount_out,
+            quote_amount: u64::try_from(quote_amount)?,
+            lp_fee: lp_fee as u64,
+            protocol_fee: protocol_fee as u64,
+            coin_creator_fee: coin_creator_fee as u64,
+        })
+    }
+
+    // 给定 quote 预算，先按总费率折算出有效输入，再反推可买到的 base
+    pub fn buy_quote_input(&self, quote_amount_in: u64) -> anyhow::Result<PumpSwapResult> {
+        if self.base_reserve == 0 || self.quote_reserve == 0 {
+            return Err(anyhow::anyhow!("Pump pool has no liquidity"));
+        }
+        let mut budget = quote_amount_in;
+        loop {
+            let effective_quote = budget as u128 * BASIS_POINTS_DENOMINATOR
+                / (BASIS_POINTS_DENOMINATOR + self.fees.total_basis_points() as u128);
+            let base_amount_out = self.base_reserve as u128 * effective_quote
+                / (self.quote_reserve as u128 + effective_quote);
+
+            let result = self.buy_base_output(base_amount_out as u64)?;
+            if result.quote_amount <= quote_amount_in || base_amount_out == 0 {
+                return Ok(result);
+            }
+            // 单项向上取整可能让实际花费略高于预算；1 个 base 单位往往不到 1 个 quote 单位，
+            // 按超出的部分缩小预算重新计算
+            budget = budget.saturating_sub(result.quote_amount - quote_amount_in);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2 亿 token / 85 SOL 的池子，SOL 在 quote 一侧；费率 lp 20 + protocol 5 + creator 5 bps
+    fn quote_state() -> PumpQuoteState {
+        PumpQuoteState {
+            base_reserve: 200_000_000_000_000,
+            quote_reserve: 85_000_000_000,
+            base_mint_is_pool_base: false,
+            fees: PumpFees {
+                lp_fee_basis_points: 20,
+                protocol_fee_basis_points: 5,
+                coin_creator_fee_basis_points: 5,
+            },
+        }
+    }
+
+    #[test]
+    fn sell_deducts_each_fee_rounded_up() {
+        let result = quote_state().sell_base_input(1_000_000_000_000).unwrap();
+        assert_eq!(result.lp_fee, 845_772);
+        assert_eq!(result.protocol_fee, 211_443);
+        assert_eq!(result.coin_creator_fee, 211_443);
+        assert_eq!(result.quote_amount, 421_616_914);
+    }
+
+    #[test]
+    fn buy_base_output_adds_fees_to_input() {
+        let result = quote_state().buy_base_output(2_000_000_000_000).unwrap();
+        assert_eq!(result.lp_fee, 1_717_172);
+        assert_eq!(result.protocol_fee, 429_293);
+        assert_eq!(result.coin_creator_fee, 429_293);
+        assert_eq!(result.quote_amount, 861_161_617);
+    }
+
+    #[test]
+    fn buy_quote_input_stays_within_budget() {
+        let state = quote_state();
+        // 直接按费率折算会多花 1 lamport
+        let result = state.buy_quote_input(1_000_000_000).unwrap();
+        assert_eq!(result.base_amount, 2_318_706_159_477);
+        assert_eq!(result.quote_amount, 1_000_000_000);
+
+        for budget in [1, 50, 999_999_999, 123_456_789, 7_777_777_777] {
+            let result = state.buy_quote_input(budget).unwrap();
+            assert!(result.quote_amount <= budget, "budget {} spent {}", budget, result.quote_amount);
+        }
+    }
+
+    #[test]
+    fn quote_exact_in_follows_pool_side() {
+        let state = quote_state();
+        assert_eq!(
+            state.quote_exact_in(SwapDirection::BaseToToken, 1_000_000_000).unwrap(),
+            2_318_706_159_477
+        );
+        assert_eq!(
+            state.quote_exact_in(SwapDirection::TokenToBase, 1_000_000_000_000).unwrap(),
+            421_616_914
+        );
+    }
+
+    #[test]
+    fn coin_creator_fee_only_applies_with_creator() {
+        let mut data = vec![0u8; GLOBAL_CONFIG_COIN_CREATOR_FEE_BPS_OFFSET + 8];
+        data[GLOBAL_CONFIG_LP_FEE_BPS_OFFSET..][..8].copy_from_slice(&20u64.to_le_bytes());
+        data[GLOBAL_CONFIG_PROTOCOL_FEE_BPS_OFFSET..][..8].copy_from_slice(&5u64.to_le_bytes());
+        data[GLOBAL_CONFIG_COIN_CREATOR_FEE_BPS_OFFSET..][..8].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(PumpFees::load(&data, true).unwrap().total_basis_points(), 30);
+        assert_eq!(PumpFees::load(&data, false).unwrap().total_basis_points(), 25);
+    }
+}
@@ -0,0 +1,558 @@
+use crate::quote::sizing::ConstantProduct;
+use crate::quote::{read_pubkey, read_u64, AccountSnapshot, PoolKeys, SwapDirection};
+
+// Raydium CP 的费率精度
+pub const CP_FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// AmmInfo 中 Fees.swap_fee_numerator/denominator 的偏移（16 个 u64 之后的第 7、8 个字段）
+const AMM_SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const AMM_SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+// OutPutData.need_take_pnl_coin/pc
+const AMM_NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const AMM_NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const AMM_COIN_VAULT_OFFSET: usize = 336;
+
+// CP PoolState（packed）中的 vault 与待提取的手续费
+const CP_TOKEN_0_VAULT_OFFSET: usize = 72;
+const CP_PROTOCOL_FEES_TOKEN_0_OFFSET: usize = 341;
+const CP_PROTOCOL_FEES_TOKEN_1_OFFSET: usize = 349;
+const CP_FUND_FEES_TOKEN_0_OFFSET: usize = 357;
+const CP_FUND_FEES_TOKEN_1_OFFSET: usize = 365;
+// CP AmmConfig: discriminator + bump + disable_create_pool + index 之后
+const CP_TRADE_FEE_RATE_OFFSET: usize = 12;
+const CP_PROTOCOL_FEE_RATE_OFFSET: usize = 20;
+const CP_FUND_FEE_RATE_OFFSET: usize = 28;
+
+fn ceil_div(numerator: u128, denominator: u128) -> u128 {
+    (numerator + denominator - 1) / denominator
+}
+
+// 交易所需的两侧储备，已经扣除了尚未提取的 pnl / 手续费
+#[derive(Debug, Clone, Copy)]
+pub struct Reserves {
+    pub base: u64,
+    pub token: u64,
+}
+
+impl Reserves {
+    pub fn oriented(&self, direction: SwapDirection) -> (u64, u64) {
+        match direction {
+            SwapDirection::BaseToToken => (self.base, self.token),
+            SwapDirection::TokenToBase => (self.token, self.base),
+        }
+    }
+}
+
+// ---------------- Raydium AMM v4 ----------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct RaydiumAmmQuoteState {
+    pub reserves: Reserves,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+impl RaydiumAmmQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let data = snapshot.data(&keys.pool)?;
+        let need_take_pnl_coin = read_u64(data, AMM_NEED_TAKE_PNL_COIN_OFFSET)?;
+        let need_take_pnl_pc = read_u64(data, AMM_NEED_TAKE_PNL_PC_OFFSET)?;
+        let coin_vault = read_pubkey(data, AMM_COIN_VAULT_OFFSET)?;
+
+        // 判断 base vault 是 coin 侧还是 pc 侧，以扣除对应的 pnl
+        let (base_pnl, token_pnl) = if coin_vault == keys.base_vault {
+            (need_take_pnl_coin, need_take_pnl_pc)
+        } else {
+            (need_take_pnl_pc, need_take_pnl_coin)
+        };
+
+        Ok(Self {
+            reserves: Reserves {
+                base: snapshot.token_balance(&keys.base_vault)?.saturating_sub(base_pnl),
+                token: snapshot.token_balance(&keys.token_vault)?.saturating_sub(token_pnl),
+            },
+            swap_fee_numerator: read_u64(data, AMM_SWAP_FEE_NUMERATOR_OFFSET)?,
+            swap_fee_denominator: read_u64(data, AMM_SWAP_FEE_DENOMINATOR_OFFSET)?,
+        })
+    }
+
+    pub fn quote_exact_in(&self, direction: SwapDirection, amount_in: u64) -> anyhow::Result<u64> {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        amm_quote_exact_in(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            self.swap_fee_numerator,
+            self.swap_fee_denominator,
+        )
+    }
+
+    pub fn constant_product(&self, direction: SwapDirection) -> ConstantProduct {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        ConstantProduct {
+            reserve_in,
+            reserve_out,
+            fee_numerator: self.swap_fee_numerator,
+            fee_denominator: self.swap_fee_denominator,
+        }
+    }
+
+    pub fn quote_exact_out(&self, direction: SwapDirection, amount_out: u64) -> anyhow::Result<u64> {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        amm_quote_exact_out(
+            amount_out,
+            reserve_in,
+            reserve_out,
+            self.swap_fee_numerator,
+            self.swap_fee_denominator,
+        )
+    }
+}
+
+// 与链上 swap_base_in 一致：手续费向上取整，输出向下取整
+pub fn amm_quote_exact_in(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> anyhow::Result<u64> {
+    if fee_denominator == 0 || reserve_in == 0 || reserve_out == 0 {
+        return Err(anyhow::anyhow!("Raydium AMM pool has no liquidity or invalid fee"));
+    }
+    let swap_fee = ceil_div(
+        amount_in as u128 * fee_numerator as u128,
+        fee_denominator as u128,
+    );
+    let amount_in_after_fee = amount_in as u128 - swap_fee;
+    let amount_out =
+        reserve_out as u128 * amount_in_after_fee / (reserve_in as u128 + amount_in_after_fee);
+    Ok(amount_out as u64)
+}
+
+// 与链上 swap_base_out 一致：反推所需输入并向上取整，再加上手续费
+pub fn amm_quote_exact_out(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> anyhow::Result<u64> {
+    if amount_out >= reserve_out || fee_numerator >= fee_denominator {
+        return Err(anyhow::anyhow!("Raydium AMM pool cannot provide {} out", amount_out));
+    }
+    let amount_in_before_fee = ceil_div(
+        reserve_in as u128 * amount_out as u128,
+        (reserve_out - amount_out) as u128,
+    );
+    let amount_in = ceil_div(
+        amount_in_before_fee * fee_denominator as u128,
+        (fee_denominator - fee_numerator) as u128,
+    );
+    u64::try_from(amount_in).map_err(|_| anyhow::anyhow!("Raydium AMM amount in overflows u64"))
+}
+
+// ---------------- Raydium CP ----------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct CpFeeRates {
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+}
+
+impl CpFeeRates {
+    pub fn load(amm_config_data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            trade_fee_rate: read_u64(amm_config_data, CP_TRADE_FEE_RATE_OFFSET)?,
+            protocol_fee_rate: read_u64(amm_config_data, CP_PROTOCOL_FEE_RATE_OFFSET)?,
+            fund_fee_rate: read_u64(amm_config_data, CP_FUND_FEE_RATE_OFFSET)?,
+        })
+    }
+}
+
+// CP 交易的拆分结果，protocol/fund 手续费都是从 trade_fee 中划出的
+#[derive(Debug, Clone, Copy)]
+pub struct CpSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub trade_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RaydiumCpQuoteState {
+    pub reserves: Reserves,
+    pub fees: CpFeeRates,
+}
+
+impl RaydiumCpQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let amm_config = keys
+            .extra_accounts
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Raydium CP pool {} has no amm config", keys.pool))?;
+        let fees = CpFeeRates::load(snapshot.data(amm_config)?)?;
+
+        let data = snapshot.data(&keys.pool)?;
+        let token_0_vault = read_pubkey(data, CP_TOKEN_0_VAULT_OFFSET)?;
+        let fees_token_0 = read_u64(data, CP_PROTOCOL_FEES_TOKEN_0_OFFSET)?
+            + read_u64(data, CP_FUND_FEES_TOKEN_0_OFFSET)?;
+        let fees_token_1 = read_u64(data, CP_PROTOCOL_FEES_TOKEN_1_OFFSET)?
+            + read_u64(data, CP_FUND_FEES_TOKEN_1_OFFSET)?;
+        let (base_fees, token_fees) = if token_0_vault == keys.base_vault {
+            (fees_token_0, fees_token_1)
+        } else {
+            (fees_token_1, fees_token_0)
+        };
+
+        Ok(Self {
+            reserves: Reserves {
+                base: snapshot.token_balance(&keys.base_vault)?.saturating_sub(base_fees),
+                token: snapshot.token_balance(&keys.token_vault)?.saturating_sub(token_fees),
+            },
+            fees,
+        })
+    }
+
+    pub fn quote_exact_in(
+        &self,
+        direction: SwapDirection,
+        amount_in: u64,
+    ) -> anyhow::Result<CpSwapResult> {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        cp_quote_exact_in(amount_in, reserve_in, reserve_out, &self.fees)
+    }
+
+    pub fn quote_exact_out(
+        &self,
+        direction: SwapDirection,
+        amount_out: u64,
+    ) -> anyhow::Result<CpSwapResult> {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        cp_quote_exact_out(amount_out, reserve_in, reserve_out, &self.fees)
+    }
+
+    pub fn constant_product(&self, direction: SwapDirection) -> ConstantProduct {
+        let (reserve_in, reserve_out) = self.reserves.oriented(direction);
+        ConstantProduct {
+            reserve_in,
+            reserve_out,
+            fee_numerator: self.fees.trade_fee_rate,
+            fee_denominator: CP_FEE_RATE_DENOMINATOR,
+        }
+    }
+}
+
+fn cp_fee_split(amount_in: u64, fees: &CpFeeRates) -> (u64, u64, u64) {
+    let denominator = CP_FEE_RATE_DENOMINATOR as u128;
+    let trade_fee = ceil_div(amount_in as u128 * fees.trade_fee_rate as u128, denominator);
+    let protocol_fee = trade_fee * fees.protocol_fee_rate as u128 / denominator;
+    let fund_fee = trade_fee * fees.fund_fee_rate as u128 / denominator;
+    (trade_fee as u64, protocol_fee as u64, fund_fee as u64)
+}
+
+// 与链上 swap_base_input 一致
+pub fn cp_quote_exact_in(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fees: &CpFeeRates,
+) -> anyhow::Result<CpSwapResult> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(anyhow::anyhow!("Raydium CP pool has no liquidity"));
+    }
+    let (trade_fee, protocol_fee, fund_fee) = cp_fee_split(amount_in, fees);
+    let amount_in_less_fees = (amount_in - trade_fee) as u128;
+    let amount_out =
+        amount_in_less_fees * reserve_out as u128 / (reserve_in as u128 + amount_in_less_fees);
+    Ok(CpSwapResult {
+        amount_in,
+        amount_out: amount_out as u64,
+        trade_fee,
+        protocol_fee,
+        fund_fee,
+    })
+}
+
+// 与链上 swap_base_output 一致
+pub fn cp_quote_exact_out(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fees: &CpFeeRates,
+) -> anyhow::Result<CpSwapResult> {
+    if amount_out >= reserve_out || fees.trade_fee_rate >= CP_FEE_RATE_DENOMINATOR {
+        return Err(anyhow::anyhow!("Raydium CP pool cannot provide {} out", amount_out));
+    }
+    let amount_in_less_fees = ceil_div(
+        reserve_in as u128 * amount_out as u128,
+        (reserve_out - amount_out) as u128,
+    );
+    let amount_in = if fees.trade_fee_rate == 0 {
+        amount_in_less_fees
+    } else {
+        ceil_div(
+            amount_in_less_fees * CP_FEE_RATE_DENOMINATOR as u128,
+            (CP_FEE_RATE_DENOMINATOR - fees.trade_fee_rate) as u128,
+        )
+    };
+    let amount_in = u64::try_from(amount_in)
+        .map_err(|_| anyhow::anyhow!("Raydium CP amount in overflows u64"))?;
+    let (trade_fee, protocol_fee, fund_fee) = cp_fee_split(amount_in, fees);
+    Ok(CpSwapResult {
+        amount_in,
+        amount_out,
+        trade_fee,
+        protocol_fee,
+        fund_fee,
+    })
+}
+
+// 测试用的账户构造，其他模块的测试也用它搭建恒定乘积池子
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::dex::DexType;
+    use solana_sdk::account::Account;
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashMap;
+
+    pub fn account(data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1,
+            data,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn token_account(amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        account(data)
+    }
+
+    // 按给定储备构造 AMM v4 池子和两个 vault，费率 25 / 10000，coin 一侧为 token；账户写入 accounts
+    pub fn amm_pool(
+        accounts: &mut HashMap<Pubkey, (u64, Account)>,
+        base_reserve: u64,
+        token_reserve: u64,
+    ) -> PoolKeys {
+        let pool = Pubkey::new_unique();
+        let token_vault = Pubkey::new_unique();
+        let base_vault = Pubkey::new_unique();
+        let mut data = vec![0u8; 752];
+        data[AMM_SWAP_FEE_NUMERATOR_OFFSET..][..8].copy_from_slice(&25u64.to_le_bytes());
+        data[AMM_SWAP_FEE_DENOMINATOR_OFFSET..][..8].copy_from_slice(&10_000u64.to_le_bytes());
+        data[AMM_COIN_VAULT_OFFSET..][..32].copy_from_slice(token_vault.as_ref());
+        accounts.insert(pool, (1, account(data)));
+        accounts.insert(token_vault, (1, token_account(token_reserve)));
+        accounts.insert(base_vault, (1, token_account(base_reserve)));
+        PoolKeys {
+            dex: DexType::RaydiumAmm,
+            pool,
+            token_vault,
+            base_vault,
+            extra_accounts: vec![],
+            instruction_accounts: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{account, token_account};
+    use super::*;
+    use crate::dex::DexType;
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashMap;
+
+    // 5 万 SOL / 750 万 USDC 的 AMM v4 池子，费率 25 / 10000
+    const RESERVE_SOL: u64 = 50_000_000_000_000;
+    const RESERVE_USDC: u64 = 7_500_000_000_000;
+
+    // CP AmmConfig index 0 的费率
+    const CP_FEES: CpFeeRates = CpFeeRates {
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 120_000,
+        fund_fee_rate: 40_000,
+    };
+
+    #[test]
+    fn amm_exact_in_rounds_fee_up_and_output_down() {
+        let amount_out =
+            amm_quote_exact_in(1_000_000_000, RESERVE_SOL, RESERVE_USDC, 25, 10_000).unwrap();
+        // 手续费 2_500_000，7.5e12 * 997_500_000 / (5e13 + 997_500_000) = 149_622_015.3
+        assert_eq!(amount_out, 149_622_015);
+
+        // 1 的手续费向上取整为 1，输入全部被扣掉
+        assert_eq!(amm_quote_exact_in(1, RESERVE_SOL, RESERVE_USDC, 25, 10_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn amm_exact_out_is_the_smallest_input() {
+        let amount_in =
+            amm_quote_exact_out(150_000_000, RESERVE_SOL, RESERVE_USDC, 25, 10_000).unwrap();
+        assert_eq!(amount_in, 1_002_526_317);
+        assert_eq!(
+            amm_quote_exact_in(amount_in, RESERVE_SOL, RESERVE_USDC, 25, 10_000).unwrap(),
+            150_000_000
+        );
+        assert_eq!(
+            amm_quote_exact_in(amount_in - 1, RESERVE_SOL, RESERVE_USDC, 25, 10_000).unwrap(),
+            149_999_999
+        );
+    }
+
+    #[test]
+    fn amm_rejects_empty_pool_and_full_output() {
+        assert!(amm_quote_exact_in(1_000, 0, RESERVE_USDC, 25, 10_000).is_err());
+        assert!(amm_quote_exact_in(1_000, RESERVE_SOL, RESERVE_USDC, 25, 0).is_err());
+        assert!(amm_quote_exact_out(RESERVE_USDC, RESERVE_SOL, RESERVE_USDC, 25, 10_000).is_err());
+    }
+
+    #[test]
+    fn cp_exact_in_splits_protocol_and_fund_fees_from_trade_fee() {
+        let result =
+            cp_quote_exact_in(2_000_000_000, 1_200_000_000_000, 180_000_000_000, &CP_FEES).unwrap();
+        assert_eq!(result.trade_fee, 5_000_000);
+        assert_eq!(result.protocol_fee, 600_000);
+        assert_eq!(result.fund_fee, 200_000);
+        assert_eq!(result.amount_out, 298_753_322);
+    }
+
+    #[test]
+    fn cp_exact_out_round_trips_through_exact_in() {
+        let result =
+            cp_quote_exact_out(300_000_000, 1_200_000_000_000, 180_000_000_000, &CP_FEES).unwrap();
+        assert_eq!(result.amount_in, 2_008_359_799);
+        assert_eq!(result.trade_fee, 5_020_900);
+        assert_eq!(result.protocol_fee, 602_508);
+        assert_eq!(result.fund_fee, 200_836);
+        let exact_in =
+            cp_quote_exact_in(result.amount_in, 1_200_000_000_000, 180_000_000_000, &CP_FEES)
+                .unwrap();
+        assert_eq!(exact_in.amount_out, 300_000_000);
+    }
+
+    #[test]
+    fn amm_state_subtracts_pending_pnl_from_vaults() {
+        let pool = Pubkey::new_unique();
+        let coin_vault = Pubkey::new_unique();
+        let pc_vault = Pubkey::new_unique();
+        let mut data = vec![0u8; 752];
+        data[AMM_SWAP_FEE_NUMERATOR_OFFSET..][..8].copy_from_slice(&25u64.to_le_bytes());
+        data[AMM_SWAP_FEE_DENOMINATOR_OFFSET..][..8].copy_from_slice(&10_000u64.to_le_bytes());
+        data[AMM_NEED_TAKE_PNL_COIN_OFFSET..][..8].copy_from_slice(&1_000u64.to_le_bytes());
+        data[AMM_NEED_TAKE_PNL_PC_OFFSET..][..8].copy_from_slice(&2_000u64.to_le_bytes());
+        data[AMM_COIN_VAULT_OFFSET..][..32].copy_from_slice(coin_vault.as_ref());
+
+        let snapshot = AccountSnapshot::from_accounts(HashMap::from([
+            (pool, (1, account(data))),
+            (coin_vault, (1, token_account(RESERVE_USDC + 1_000))),
+            (pc_vault, (1, token_account(RESERVE_SOL + 2_000))),
+        ]));
+        // base（SOL）在 pc 一侧
+        let keys = PoolKeys {
+            dex: DexType::RaydiumAmm,
+            pool,
+            token_vault: coin_vault,
+            base_vault: pc_vault,
+            extra_accounts: vec![],
+            instruction_accounts: vec![],
+        };
+        let state = RaydiumAmmQuoteState::load(&keys, &snapshot).unwrap();
+        assert_eq!(state.reserves.base, RESERVE_SOL);
+        assert_eq!(state.reserves.token, RESERVE_USDC);
+        assert_eq!(
+            state.quote_exact_in(SwapDirection::BaseToToken, 1_000_000_000).unwrap(),
+            149_622_015
+        );
+    }
+
+    #[test]
+    fn amm_state_orients_reserves_when_base_is_the_coin_side() {
+        let mut accounts = HashMap::new();
+        let mut keys = fixtures::amm_pool(&mut accounts, RESERVE_SOL, RESERVE_USDC);
+        // fixture 的 coin vault 是 token vault，交换两侧后 base 在 coin 一侧
+        std::mem::swap(&mut keys.base_vault, &mut keys.token_vault);
+        let snapshot = AccountSnapshot::from_accounts(accounts);
+
+        let state = RaydiumAmmQuoteState::load(&keys, &snapshot).unwrap();
+        assert_eq!(state.reserves.base, RESERVE_USDC);
+        assert_eq!(state.reserves.token, RESERVE_SOL);
+        let pool = state.constant_product(SwapDirection::TokenToBase);
+        assert_eq!((pool.reserve_in, pool.reserve_out), (RESERVE_SOL, RESERVE_USDC));
+        assert_eq!((pool.fee_numerator, pool.fee_denominator), (25, 10_000));
+    }
+
+    // CP 池子：token 0 为 SOL，token 1 为 USDC，各自有待提取的协议费和基金费
+    fn cp_pool(base_is_token_0: bool) -> (PoolKeys, AccountSnapshot) {
+        let (pool, amm_config) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (sol_vault, usdc_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; 637];
+        data[CP_TOKEN_0_VAULT_OFFSET..][..32].copy_from_slice(sol_vault.as_ref());
+        data[CP_PROTOCOL_FEES_TOKEN_0_OFFSET..][..8].copy_from_slice(&300u64.to_le_bytes());
+        data[CP_FUND_FEES_TOKEN_0_OFFSET..][..8].copy_from_slice(&100u64.to_le_bytes());
+        data[CP_PROTOCOL_FEES_TOKEN_1_OFFSET..][..8].copy_from_slice(&30u64.to_le_bytes());
+        data[CP_FUND_FEES_TOKEN_1_OFFSET..][..8].copy_from_slice(&10u64.to_le_bytes());
+        let mut config_data = vec![0u8; 236];
+        config_data[CP_TRADE_FEE_RATE_OFFSET..][..8].copy_from_slice(&CP_FEES.trade_fee_rate.to_le_bytes());
+        config_data[CP_PROTOCOL_FEE_RATE_OFFSET..][..8]
+            .copy_from_slice(&CP_FEES.protocol_fee_rate.to_le_bytes());
+        config_data[CP_FUND_FEE_RATE_OFFSET..][..8].copy_from_slice(&CP_FEES.fund_fee_rate.to_le_bytes());
+
+        let snapshot = AccountSnapshot::from_accounts(HashMap::from([
+            (pool, (1, account(data))),
+            (amm_config, (1, account(config_data))),
+            (sol_vault, (1, token_account(1_200_000_000_000 + 400))),
+            (usdc_vault, (1, token_account(180_000_000_000 + 40))),
+        ]));
+        let (base_vault, token_vault) =
+            if base_is_token_0 { (sol_vault, usdc_vault) } else { (usdc_vault, sol_vault) };
+        let keys = PoolKeys {
+            dex: DexType::RaydiumCp,
+            pool,
+            token_vault,
+            base_vault,
+            extra_accounts: vec![amm_config],
+            instruction_accounts: vec![],
+        };
+        (keys, snapshot)
+    }
+
+    #[test]
+    fn cp_state_reads_config_fees_and_subtracts_pending_fees() {
+        let (keys, snapshot) = cp_pool(true);
+        let state = RaydiumCpQuoteState::load(&keys, &snapshot).unwrap();
+        assert_eq!(state.reserves.base, 1_200_000_000_000);
+        assert_eq!(state.reserves.token, 180_000_000_000);
+        assert_eq!(state.fees.trade_fee_rate, CP_FEES.trade_fee_rate);
+        assert_eq!(state.fees.protocol_fee_rate, CP_FEES.protocol_fee_rate);
+        assert_eq!(state.fees.fund_fee_rate, CP_FEES.fund_fee_rate);
+        assert_eq!(
+            state.quote_exact_in(SwapDirection::BaseToToken, 2_000_000_000).unwrap().amount_out,
+            cp_quote_exact_in(2_000_000_000, 1_200_000_000_000, 180_000_000_000, &CP_FEES)
+                .unwrap()
+                .amount_out
+        );
+
+        // base 在 token 1 一侧时扣除 token 1 的手续费
+        let (keys, snapshot) = cp_pool(false);
+        let state = RaydiumCpQuoteState::load(&keys, &snapshot).unwrap();
+        assert_eq!(state.reserves.base, 180_000_000_000);
+        assert_eq!(state.reserves.token, 1_200_000_000_000);
+    }
+
+    #[test]
+    fn cp_state_requires_the_amm_config() {
+        let (mut keys, snapshot) = cp_pool(true);
+        keys.extra_accounts.clear();
+        assert!(RaydiumCpQuoteState::load(&keys, &snapshot).is_err());
+        keys.extra_accounts.push(Pubkey::new_unique());
+        assert!(RaydiumCpQuoteState::load(&keys, &snapshot).is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use crate::quote::clmm_math::{
+    compute_swap_step, get_sqrt_price_at_tick, get_tick_at_sqrt_price, MAX_SQRT_PRICE_X64,
+    MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK,
+};
+use crate::quote::{
+    read_i128, read_i32, read_pubkey, read_u128, read_u16, read_u32, AccountSnapshot, PoolKeys,
+    SwapDirection,
+};
+use solana_sdk::pubkey::Pubkey;
+
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+// PoolState（packed）字段偏移
+const POOL_TOKEN_VAULT_0_OFFSET: usize = 137;
+const POOL_TICK_SPACING_OFFSET: usize = 235;
+const POOL_LIQUIDITY_OFFSET: usize = 237;
+const POOL_SQRT_PRICE_X64_OFFSET: usize = 253;
+const POOL_TICK_CURRENT_OFFSET: usize = 269;
+// AmmConfig: discriminator + bump + index + owner + protocol_fee_rate 之后
+const AMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 47;
+// TickArrayState: discriminator + pool_id 之后是 start_tick_index，再之后是 60 个 TickState
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 40;
+const TICK_ARRAY_TICKS_OFFSET: usize = 44;
+const TICK_STATE_LEN: usize = 168;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClmmPoolState {
+    pub token_vault_0: Pubkey,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl ClmmPoolState {
+    pub fn load(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            token_vault_0: read_pubkey(data, POOL_TOKEN_VAULT_0_OFFSET)?,
+            tick_spacing: read_u16(data, POOL_TICK_SPACING_OFFSET)?,
+            liquidity: read_u128(data, POOL_LIQUIDITY_OFFSET)?,
+            sqrt_price_x64: read_u128(data, POOL_SQRT_PRICE_X64_OFFSET)?,
+            tick_current: read_i32(data, POOL_TICK_CURRENT_OFFSET)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InitializedTick {
+    pub tick: i32,
+    pub liquidity_net: i128,
+}
+
+// 只保留已初始化（liquidity_gross != 0）的 tick
+#[derive(Debug, Clone)]
+pub struct ClmmTickArray {
+    pub address: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<InitializedTick>,
+}
+
+impl ClmmTickArray {
+    pub fn load(address: Pubkey, data: &[u8]) -> anyhow::Result<Self> {
+        let start_tick_index = read_i32(data, TICK_ARRAY_START_TICK_INDEX_OFFSET)?;
+        let mut ticks = Vec::new();
+        for i in 0..TICK_ARRAY_SIZE as usize {
+            let offset = TICK_ARRAY_TICKS_OFFSET + i * TICK_STATE_LEN;
+            let liquidity_gross = read_u128(data, offset + 20)?;
+            if liquidity_gross == 0 {
+                continue;
+            }
+            ticks.push(InitializedTick {
+                tick: read_i32(data, offset)?,
+                liquidity_net: read_i128(data, offset + 4)?,
+            });
+        }
+        Ok(Self {
+            address,
+            start_tick_index,
+            ticks,
+        })
+    }
+
+    pub fn end_tick_index(&self, tick_spacing: u16) -> i32 {
+        self.start_tick_index + TICK_ARRAY_SIZE * tick_spacing as i32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClmmSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    // 价格实际经过的 tick array，交易指令需要按顺序传入
+    pub crossed_tick_arrays: Vec<Pubkey>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RaydiumClmmQuoteState {
+    pub pool: ClmmPoolState,
+    pub trade_fee_rate: u32,
+    pub tick_arrays: Vec<ClmmTickArray>,
+}
+
+impl RaydiumClmmQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let (amm_config, tick_array_keys) = keys
+            .extra_accounts
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Raydium CLMM pool {} has no amm config", keys.pool))?;
+
+        let pool = ClmmPoolState::load(snapshot.data(&keys.pool)?)?;
+        let trade_fee_rate = read_u32(snapshot.data(amm_config)?, AMM_CONFIG_TRADE_FEE_RATE_OFFSET)?;
+
+        // 未初始化的 tick array 在链上不存在，直接跳过
+        let mut tick_arrays = Vec::new();
+        for address in tick_array_keys {
+            if let Ok(data) = snapshot.data(address) {
+                tick_arrays.push(ClmmTickArray::load(*address, data)?);
+            }
+        }
+        tick_arrays.sort_by_key(|array| array.start_tick_index);
+
+        Ok(Self {
+            pool,
+            trade_fee_rate,
+            tick_arrays,
+        })
+    }
+
+    // token0 换 token1 时 zero_for_one 为 true
+    pub fn zero_for_one(&self, keys: &PoolKeys, direction: SwapDirection) -> bool {
+        let base_is_token_0 = self.pool.token_vault_0 == keys.base_vault;
+        match direction {
+            SwapDirection::BaseToToken => base_is_token_0,
+            SwapDirection::TokenToBase => !base_is_token_0,
+        }
+    }
+
+    // 已获取的 tick array 覆盖的 tick 区间
+    fn window(&self) -> anyhow::Result<(i32, i32)> {
+        let first = self
+            .tick_arrays
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No tick arrays loaded"))?;
+        let last = self.tick_arrays.last().unwrap();
+        Ok((
+            first.start_tick_index.max(MIN_TICK),
+            last.end_tick_index(self.pool.tick_spacing).min(MAX_TICK),
+        ))
+    }
+
+    // 交易方向上的下一个已初始化 tick；没有则返回窗口边界
+    fn next_tick(&self, tick_current: i32, zero_for_one: bool) -> anyhow::Result<(i32, Option<i128>)> {
+        let (window_start, window_end) = self.window()?;
+        let ticks = self.tick_arrays.iter().flat_map(|array| array.ticks.iter());
+        let next = if zero_for_one {
+            ticks
+                .filter(|t| t.tick <= tick_current)
+                .max_by_key(|t| t.tick)
+                .map(|t| (t.tick, Some(t.liquidity_net)))
+                .unwrap_or((window_start, None))
+        } else {
+            ticks
+                .filter(|t| t.tick > tick_current)
+                .min_by_key(|t| t.tick)
+                .map(|t| (t.tick, Some(t.liquidity_net)))
+                .unwrap_or((window_end, None))
+        };
+        Ok(next)
+    }
+
+    pub fn simulate_swap(
+        &self,
+        zero_for_one: bool,
+        amount_specified: u64,
+        is_base_input: bool,
+    ) -> anyhow::Result<ClmmSwapResult> {
+        let sqrt_price_limit_x64 = if zero_for_one {
+            MIN_SQRT_PRICE_X64 + 1
+        } else {
+            MAX_SQRT_PRICE_X64 - 1
+        };
+
+        let mut amount_remaining = amount_specified as u128;
+        let mut amount_calculated: u128 = 0;
+        let mut fee_amount: u128 = 0;
+        let mut sqrt_price_x64 = self.pool.sqrt_price_x64;
+        let mut tick_current = self.pool.tick_current;
+        let mut liquidity = self.pool.liquidity;
+
+        while amount_remaining > 0 && sqrt_price_x64 != sqrt_price_limit_x64 {
+            let (tick_next, liquidity_net) = self.next_tick(tick_current, zero_for_one)?;
+            let sqrt_price_next_x64 = get_sqrt_price_at_tick(tick_next)?;
+            let sqrt_price_target_x64 = if zero_for_one {
+                sqrt_price_next_x64.max(sqrt_price_limit_x64)
+            } else {
+                sqrt_price_next_x64.min(sqrt_price_limit_x64)
+            };
+
+            let step = compute_swap_step(
+                sqrt_price_x64,
+                sqrt_price_target_x64,
+                liquidity,
+                amount_remaining,
+                self.trade_fee_rate,
+                is_base_input,
+                zero_for_one,
+            )?;
+            sqrt_price_x64 = step.sqrt_price_next_x64;
+            fee_amount += step.fee_amount;
+            if is_base_input {
+                amount_remaining -= step.amount_in + step.fee_amount;
+                amount_calculated += step.amount_out;
+            } else {
+                amount_remaining -= step.amount_out;
+                amount_calculated += step.amount_in + step.fee_amount;
+            }
+
+            if sqrt_price_x64 == sqrt_price_next_x64 {
+                match liquidity_net {
+                    Some(liquidity_net) => {
+                        let liquidity_net = if zero_for_one { -liquidity_net } else { liquidity_net };
+                        liquidity = liquidity
+                            .checked_add_signed(liquidity_net)
+                            .ok_or_else(|| anyhow::anyhow!("Liquidity underflow at tick {}", tick_next))?;
+                    }
+                    // 到达了已获取 tick array 的边界但还没成交完，链上会因为缺少账户而失败
+                    None if amount_remaining > 0 => {
+                        return Err(anyhow::anyhow!(
+                            "Swap of {} exceeds the fetched tick array window",
+                            amount_specified
+                        ));
+                    }
+                    None => {}
+                }
+                tick_current = if zero_for_one { tick_next - 1 } else { tick_next };
+            } else {
+                tick_current = get_tick_at_sqrt_price(sqrt_price_x64, get_sqrt_price_at_tick)?;
+            }
+        }
+
+        let (amount_in, amount_out) = if is_base_input {
+            (amount_specified as u128 - amount_remaining, amount_calculated)
+        } else {
+            (amount_calculated, amount_specified as u128 - amount_remaining)
+        };
+
+        let (low, high) = if zero_for_one {
+            (tick_current, self.pool.tick_current)
+        } else {
+            (self.pool.tick_current, tick_current)
+        };
+        let crossed_tick_arrays = self
+            .tick_arrays
+            .iter()
+            .filter(|array| {
+                array.start_tick_index <= high && array.end_tick_index(self.pool.tick_spacing) > low
+            })
+            .map(|array| array.address)
+            .collect::<Vec<_>>();
+        let crossed_tick_arrays = if zero_for_one {
+            crossed_tick_arrays.into_iter().rev().collect()
+        } else {
+            crossed_tick_arrays
+        };
+
+        Ok(ClmmSwapResult {
+            amount_in: u64::try_from(amount_in)?,
+            amount_out: u64::try_from(amount_out)?,
+            fee_amount: u64::try_from(fee_amount)?,
+            sqrt_price_x64,
+            tick_current,
+            crossed_tick_arrays,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // SOL/USDC 池子（tick_spacing 1，费率 0.01%）的链上 PoolState
+    const FIXTURE: &str = include_str!("../../../reference/raydium_info.json");
+    // 按 IDL 字段顺序，PoolState 在 discriminator 之后到 tick_current 为止的字段
+    const POOL_FIELDS: [(&str, &str); 13] = [
+        ("bump", "u8"),
+        ("amm_config", "pubkey"),
+        ("owner", "pubkey"),
+        ("token_mint_0", "pubkey"),
+        ("token_mint_1", "pubkey"),
+        ("token_vault_0", "pubkey"),
+        ("token_vault_1", "pubkey"),
+        ("observation_key", "pubkey"),
+        ("mint_decimals_0", "u8"),
+        ("mint_decimals_1", "u8"),
+        ("tick_spacing", "u16"),
+        ("liquidity", "u128"),
+        ("sqrt_price_x64", "u128"),
+    ];
+    const TRADE_FEE_RATE: u32 = 100;
+
+    fn fixture_field(name: &str) -> serde_json::Value {
+        let fixture: serde_json::Value = serde_json::from_str(FIXTURE).unwrap();
+        fixture[name]["data"].clone()
+    }
+
+    // fixture 中的整数有的是数字、有的是字符串，bump 是单元素数组
+    fn fixture_number(name: &str) -> i128 {
+        match fixture_field(name) {
+            serde_json::Value::Array(values) => values[0].as_i64().unwrap() as i128,
+            serde_json::Value::String(value) => value.parse().unwrap(),
+            value => value.as_i64().unwrap() as i128,
+        }
+    }
+
+    // 把 fixture 打包成链上的 PoolState 账户数据
+    fn fixture_pool_data() -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        for (name, kind) in POOL_FIELDS {
+            match kind {
+                "pubkey" => {
+                    let pubkey = Pubkey::from_str(fixture_field(name).as_str().unwrap()).unwrap();
+                    data.extend_from_slice(pubkey.as_ref());
+                }
+                "u8" => data.push(fixture_number(name) as u8),
+                "u16" => data.extend_from_slice(&(fixture_number(name) as u16).to_le_bytes()),
+                _ => data.extend_from_slice(&(fixture_number(name) as u128).to_le_bytes()),
+            }
+        }
+        data.extend_from_slice(&(fixture_number("tick_current") as i32).to_le_bytes());
+        data.resize(1_544, 0);
+        data
+    }
+
+    fn tick_array(start_tick_index: i32, ticks: &[(i32, i128)]) -> ClmmTickArray {
+        ClmmTickArray {
+            address: Pubkey::new_unique(),
+            start_tick_index,
+            ticks: ticks
+                .iter()
+                .map(|(tick, liquidity_net)| InitializedTick {
+                    tick: *tick,
+                    liquidity_net: *liquidity_net,
+                })
+                .collect(),
+        }
+    }
+
+    // fixture 的池子状态，当前 tick -19293 位于 [-19320, -19260) 的 tick array 中
+    fn fixture_state() -> RaydiumClmmQuoteState {
+        RaydiumClmmQuoteState {
+            pool: ClmmPoolState::load(&fixture_pool_data()).unwrap(),
+            trade_fee_rate: TRADE_FEE_RATE,
+            tick_arrays: vec![
+                tick_array(-19380, &[(-19330, 50_000_000_000_000)]),
+                tick_array(-19320, &[(-19300, 20_000_000_000_000), (-19280, -30_000_000_000_000)]),
+                tick_array(-19260, &[]),
+            ],
+        }
+    }
+
+    #[test]
+    fn pool_state_offsets_match_fixture() {
+        let pool = ClmmPoolState::load(&fixture_pool_data()).unwrap();
+        assert_eq!(
+            pool.token_vault_0,
+            Pubkey::from_str("4ct7br2vTPzfdmY3S5HLtTxcGSBfn6pnw98hsS6v359A").unwrap()
+        );
+        assert_eq!(pool.tick_spacing, 1);
+        assert_eq!(pool.liquidity, 112_035_621_120_963);
+        assert_eq!(pool.sqrt_price_x64, 7_030_853_491_284_420_608);
+        assert_eq!(pool.tick_current, -19293);
+        // 链上记录的 tick 与 sqrt price 一致
+        assert_eq!(
+            get_tick_at_sqrt_price(pool.sqrt_price_x64, get_sqrt_price_at_tick).unwrap(),
+            pool.tick_current
+        );
+    }
+
+    #[test]
+    fn tick_array_skips_uninitialized_ticks() {
+        let mut data = vec![0u8; TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE as usize * TICK_STATE_LEN];
+        data[TICK_ARRAY_START_TICK_INDEX_OFFSET..][..4].copy_from_slice(&(-19320i32).to_le_bytes());
+        let offset = TICK_ARRAY_TICKS_OFFSET + 20 * TICK_STATE_LEN;
+        data[offset..][..4].copy_from_slice(&(-19300i32).to_le_bytes());
+        data[offset + 4..][..16].copy_from_slice(&(-7i128).to_le_bytes());
+        data[offset + 20..][..16].copy_from_slice(&7u128.to_le_bytes());
+
+        let array = ClmmTickArray::load(Pubkey::new_unique(), &data).unwrap();
+        assert_eq!(array.start_tick_index, -19320);
+        assert_eq!(array.end_tick_index(1), -19260);
+        assert_eq!(array.ticks.len(), 1);
+        assert_eq!(array.ticks[0].tick, -19300);
+        assert_eq!(array.ticks[0].liquidity_net, -7);
+    }
+
+    #[test]
+    fn swap_within_one_tick() {
+        let state = fixture_state();
+        let result = state.simulate_swap(true, 10_000_000_000, true).unwrap();
+        assert_eq!(result.amount_in, 10_000_000_000);
+        assert_eq!(result.amount_out, 1_452_507_711);
+        assert_eq!(result.fee_amount, 1_000_000);
+        assert_eq!(result.tick_current, -19294);
+        assert_eq!(result.crossed_tick_arrays, vec![state.tick_arrays[1].address]);
+    }
+
+    #[test]
+    fn zero_for_one_crosses_ticks_into_lower_array() {
+        let state = fixture_state();
+        // 卖出 500 SOL：穿过 -19300 和 -19330 两个 tick，流动性先后减少
+        let result = state.simulate_swap(true, 500_000_000_000, true).unwrap();
+        assert_eq!(result.amount_in, 500_000_000_000);
+        assert_eq!(result.amount_out, 72_487_663_735);
+        assert_eq!(result.fee_amount, 50_000_002);
+        assert_eq!(result.sqrt_price_x64, 7_015_927_412_165_302_015);
+        assert_eq!(result.tick_current, -19336);
+        assert_eq!(
+            result.crossed_tick_arrays,
+            vec![state.tick_arrays[1].address, state.tick_arrays[0].address]
+        );
+    }
+
+    #[test]
+    fn one_for_zero_crosses_tick_into_upper_array() {
+        let state = fixture_state();
+        // 买入：投入 10 万 USDC，穿过 -19280 时流动性减少 3e13
+        let result = state.simulate_swap(false, 100_000_000_000, true).unwrap();
+        assert_eq!(result.amount_out, 686_381_719_764);
+        assert_eq!(result.fee_amount, 10_000_001);
+        assert_eq!(result.sqrt_price_x64, 7_051_722_385_520_948_585);
+        assert_eq!(result.tick_current, -19234);
+        assert_eq!(
+            result.crossed_tick_arrays,
+            vec![state.tick_arrays[1].address, state.tick_arrays[2].address]
+        );
+    }
+
+    #[test]
+    fn swap_beyond_fetched_arrays_fails() {
+        assert!(fixture_state().simulate_swap(true, 1_000_000_000_000, true).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::dex::adapter;
+use crate::flash_loan::fee_from_ppm;
+use crate::quote::{quote_exact_in, AccountSnapshot, PoolKeys, SwapDirection};
+use std::fmt;
+
+const INV_PHI: f64 = 0.618_033_988_749_895;
+const MAX_SEARCH_ITERATIONS: usize = 64;
+// 搜索区间缩小到上限的万分之一即停止
+const SEARCH_TOLERANCE: f64 = 1e-4;
+// 用上限的百万分之一试探边际收益，足够小使利润曲线在这里近似为斜率
+const MARGINAL_PROBE_DIVISOR: u64 = 1_000_000;
+const PARTS_PER_MILLION: u64 = 1_000_000;
+
+// 恒定乘积池子在某个方向上的参数：输出 = γ·a·reserve_out / (reserve_in + γ·a)，γ = 1 - 费率
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantProduct {
+    pub reserve_in: u64,
+    pub reserve_out: u64,
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+}
+
+impl ConstantProduct {
+    fn gamma(&self) -> f64 {
+        1.0 - self.fee_numerator as f64 / self.fee_denominator as f64
+    }
+}
+
+// 交易规模的上限：开启闪电贷时整笔投入都是借来的，受借款额度限制，否则受钱包余额限制；
+// 再受 MintConfig 的 max_amount_in 限制
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeLimits {
+    pub wallet_balance: u64,
+    pub flash_loan_available: u64,
+    pub max_amount_in: Option<u64>,
+}
+
+impl SizeLimits {
+    pub fn upper_bound(&self) -> u64 {
+        let funds = if self.flash_loan_available > 0 {
+            self.flash_loan_available
+        } else {
+            self.wallet_balance
+        };
+        self.max_amount_in.map_or(funds, |max_amount_in| max_amount_in.min(funds))
+    }
+}
+
+// 一笔交易的成本：固定部分（签名费、优先费、小费）加上闪电贷按借入数量收取的手续费
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TradeCost {
+    pub fixed: u64,
+    // 百万分之几，未开启闪电贷时为 0
+    pub flash_loan_fee_ppm: u64,
+}
+
+impl TradeCost {
+    pub fn flash_loan_fee(&self, amount_in: u64) -> u64 {
+        fee_from_ppm(amount_in, self.flash_loan_fee_ppm)
+    }
+
+    pub fn total(&self, amount_in: u64) -> u64 {
+        self.fixed.saturating_add(self.flash_loan_fee(amount_in))
+    }
+
+    // 每多投入一个单位需要多换回的数量，闪电贷手续费使最优规模略小于无成本时
+    fn marginal(&self) -> f64 {
+        1.0 + self.flash_loan_fee_ppm as f64 / PARTS_PER_MILLION as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Sizing {
+    // 固定投入（仍受上限约束）
+    Fixed(u64),
+    // 在 [1, max_amount_in] 中寻找净利润最大的投入
+    Optimal { max_amount_in: u64 },
+}
+
+impl Sizing {
+    pub fn new(optimize: bool, amount_in: u64, limits: &SizeLimits) -> Self {
+        let upper_bound = limits.upper_bound();
+        if optimize {
+            Sizing::Optimal {
+                max_amount_in: upper_bound,
+            }
+        } else {
+            Sizing::Fixed(amount_in.min(upper_bound))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizingMethod {
+    Fixed,
+    ClosedForm,
+    GoldenSection,
+}
+
+impl fmt::Display for SizingMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SizingMethod::Fixed => "fixed",
+            SizingMethod::ClosedForm => "closed form",
+            SizingMethod::GoldenSection => "golden section",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// 选定规模后的报价，amounts[0] 为输入，amounts[i + 1] 为第 i 跳的输出
+#[derive(Debug, Clone)]
+pub struct SizedTrade {
+    pub amounts: Vec<u64>,
+    pub net_profit: i64,
+    pub method: SizingMethod,
+}
+
+impl SizedTrade {
+    pub fn amount_in(&self) -> u64 {
+        self.amounts[0]
+    }
+
+    pub fn amount_out(&self) -> u64 {
+        *self.amounts.last().unwrap()
+    }
+}
+
+// 依次报价每一跳，返回每一跳前后的数量
+pub fn quote_legs(
+    legs: &[(&PoolKeys, SwapDirection)],
+    snapshot: &AccountSnapshot,
+    amount_in: u64,
+) -> anyhow::Result<Vec<u64>> {
+    let mut amounts = Vec::with_capacity(legs.len() + 1);
+    amounts.push(amount_in);
+    let mut amount = amount_in;
+    for (keys, direction) in legs {
+        amount = quote_exact_in(keys, snapshot, *direction, amount)?;
+        if amount == 0 {
+            return Err(anyhow::anyhow!("{} pool {} returns nothing", keys.dex, keys.pool));
+        }
+        amounts.push(amount);
+    }
+    Ok(amounts)
+}
+
+fn trade_at(
+    legs: &[(&PoolKeys, SwapDirection)],
+    snapshot: &AccountSnapshot,
+    amount_in: u64,
+    cost: &TradeCost,
+    method: SizingMethod,
+) -> anyhow::Result<SizedTrade> {
+    let amounts = quote_legs(legs, snapshot, amount_in)?;
+    let net_profit =
+        *amounts.last().unwrap() as i64 - amount_in as i64 - cost.total(amount_in) as i64;
+    Ok(SizedTrade {
+        amounts,
+        net_profit,
+        method,
+    })
+}
+
+// 全部是恒定乘积池子时，多跳的复合输出仍是 K·a / (D + E·a)，
+// 令导数 K·D / (D + E·a)² = m（边际成本）得 a* = (√(K·D / m) - D) / E
+fn closed_form_size(
+    legs: &[(&PoolKeys, SwapDirection)],
+    snapshot: &AccountSnapshot,
+    marginal: f64,
+) -> Option<f64> {
+    let (mut k, mut d, mut e) = (1.0f64, 1.0f64, 0.0f64);
+    for (keys, direction) in legs {
+        let pool = adapter(keys.dex).constant_product(keys, snapshot, *direction)?;
+        let gamma = pool.gamma();
+        let (reserve_in, reserve_out) = (pool.reserve_in as f64, pool.reserve_out as f64);
+        e = reserve_in * e + gamma * k;
+        d *= reserve_in;
+        k *= gamma * reserve_out;
+    }
+    if e <= 0.0 {
+        return None;
+    }
+    Some(((k * d / marginal).sqrt() - d) / e)
+}
+
+// 利润曲线是凹的，用黄金分割在整数区间上找最大值
+fn golden_section(lo: u64, hi: u64, mut profit: impl FnMut(u64) -> Option<i128>) -> u64 {
+    let mut eval = |x: f64| profit(x.round() as u64).unwrap_or(i128::MIN);
+    let tolerance = (hi as f64 * SEARCH_TOLERANCE).max(1.0);
+    let (mut lo, mut hi) = (lo as f64, hi as f64);
+    let mut x1 = hi - INV_PHI * (hi - lo);
+    let mut x2 = lo + INV_PHI * (hi - lo);
+    let mut f1 = eval(x1);
+    let mut f2 = eval(x2);
+
+    for _ in 0..MAX_SEARCH_ITERATIONS {
+        if hi - lo <= tolerance {
+            break;
+        }
+        if f1 < f2 {
+            lo = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = lo + INV_PHI * (hi - lo);
+            f2 = eval(x2);
+        } else {
+            hi = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = hi - INV_PHI * (hi - lo);
+            f1 = eval(x1);
+        }
+    }
+
+    if f1 >= f2 {
+        x1.round() as u64
+    } else {
+        x2.round() as u64
+    }
+}
+
+// 为一条路径选择投入规模；返回 None 表示该路径在允许的规模内没有可用报价
+pub fn size_route(
+    legs: &[(&PoolKeys, SwapDirection)],
+    snapshot: &AccountSnapshot,
+    cost: &TradeCost,
+    sizing: &Sizing,
+) -> Option<SizedTrade> {
+    let max_amount_in = match *sizing {
+        Sizing::Fixed(0) | Sizing::Optimal { max_amount_in: 0 } => return None,
+        Sizing::Fixed(amount_in) => {
+            return trade_at(legs, snapshot, amount_in, cost, SizingMethod::Fixed).ok();
+        }
+        Sizing::Optimal { max_amount_in } => max_amount_in,
+    };
+
+    // 极小投入的边际收益不为正时，凹的利润曲线在任何规模下都不会盈利，直接返回试探结果
+    let probe = (max_amount_in / MARGINAL_PROBE_DIVISOR).max(1);
+    let probe_out = *quote_legs(legs, snapshot, probe).ok()?.last()?;
+    if probe_out as f64 <= probe as f64 * cost.marginal() {
+        return trade_at(legs, snapshot, probe, cost, SizingMethod::Fixed).ok();
+    }
+
+    if let Some(size) = closed_form_size(legs, snapshot, cost.marginal()) {
+        let amount_in = (size.max(1.0) as u64).min(max_amount_in);
+        return trade_at(legs, snapshot, amount_in, cost, SizingMethod::ClosedForm).ok();
+    }
+
+    let amount_in = golden_section(1, max_amount_in, |amount_in| {
+        quote_legs(legs, snapshot, amount_in)
+            .ok()
+            .map(|amounts| {
+                *amounts.last().unwrap() as i128
+                    - amount_in as i128
+                    - cost.flash_loan_fee(amount_in) as i128
+            })
+    });
+    trade_at(legs, snapshot, amount_in, cost, SizingMethod::GoldenSection).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::raydium::fixtures::amm_pool;
+    use std::collections::HashMap;
+
+    const SOL_RESERVE: u64 = 1_000_000_000_000;
+    const TOKEN_RESERVE: u64 = 150_000_000_000;
+
+    // 两个 SOL/token 池子，第二个池子里 token 贵 10%，先买后卖可以获利
+    fn two_pools(second_sol_reserve: u64) -> (PoolKeys, PoolKeys, AccountSnapshot) {
+        let mut accounts = HashMap::new();
+        let buy = amm_pool(&mut accounts, SOL_RESERVE, TOKEN_RESERVE);
+        let sell = amm_pool(&mut accounts, second_sol_reserve, TOKEN_RESERVE);
+        (buy, sell, AccountSnapshot::from_accounts(accounts))
+    }
+
+    // 在 [1, hi] 上等距取点，返回利润最大的投入和利润
+    fn brute_force(hi: u64, profit: impl Fn(u64) -> i128) -> (u64, i128) {
+        (1..=10_000u64)
+            .map(|i| hi * i / 10_000)
+            .map(|amount_in| (amount_in, profit(amount_in)))
+            .max_by_key(|(_, profit)| *profit)
+            .unwrap()
+    }
+
+    fn route_profit(
+        legs: &[(&PoolKeys, SwapDirection)],
+        snapshot: &AccountSnapshot,
+        marginal: f64,
+        amount_in: u64,
+    ) -> i128 {
+        let amount_out = *quote_legs(legs, snapshot, amount_in).unwrap().last().unwrap();
+        amount_out as i128 - (amount_in as f64 * marginal).round() as i128
+    }
+
+    #[test]
+    fn closed_form_matches_brute_force_on_one_pair() {
+        let (buy, _, snapshot) = two_pools(SOL_RESERVE);
+        let legs = [(&buy, SwapDirection::BaseToToken)];
+        // 以 0.1 token / lamport 的价格卖出换到的 token，池子价格 0.15，存在最优规模
+        let marginal = 0.1;
+        let size = closed_form_size(&legs, &snapshot, marginal).unwrap() as u64;
+        let (best_amount, best_profit) =
+            brute_force(SOL_RESERVE, |amount_in| route_profit(&legs, &snapshot, marginal, amount_in));
+
+        assert!(size.abs_diff(best_amount) <= SOL_RESERVE / 10_000, "{} vs {}", size, best_amount);
+        assert!(route_profit(&legs, &snapshot, marginal, size) >= best_profit - 2);
+    }
+
+    #[test]
+    fn closed_form_matches_brute_force_on_two_legs() {
+        let (buy, sell, snapshot) = two_pools(SOL_RESERVE * 11 / 10);
+        let legs = [(&buy, SwapDirection::BaseToToken), (&sell, SwapDirection::TokenToBase)];
+        // 闪电贷手续费 9 ppm
+        let marginal = TradeCost { fixed: 0, flash_loan_fee_ppm: 9 }.marginal();
+        let size = closed_form_size(&legs, &snapshot, marginal).unwrap() as u64;
+        let (best_amount, best_profit) = brute_force(SOL_RESERVE / 10, |amount_in| {
+            route_profit(&legs, &snapshot, marginal, amount_in)
+        });
+
+        assert!(size.abs_diff(best_amount) <= SOL_RESERVE / 100_000, "{} vs {}", size, best_amount);
+        assert!(route_profit(&legs, &snapshot, marginal, size) >= best_profit - 2);
+    }
+
+    #[test]
+    fn golden_section_converges_to_the_peak() {
+        let peak = 1_234_567u64;
+        let amount_in = golden_section(1, 10_000_000, |x| Some(-((x as i128 - peak as i128).pow(2))));
+        assert!(amount_in.abs_diff(peak) <= 1_000, "{}", amount_in);
+
+        // 峰值贴近下界时也能找到
+        let amount_in = golden_section(1, 10_000_000, |x| Some(-((x as i128 - 3).pow(2))));
+        assert!(amount_in <= 1_000, "{}", amount_in);
+
+        // 无法报价的点视为最差
+        let amount_in = golden_section(1, 10_000_000, |x| (x <= peak).then(|| x as i128));
+        assert!(amount_in <= peak && peak - amount_in <= 1_000, "{}", amount_in);
+    }
+
+    #[test]
+    fn size_route_finds_an_optimum_far_below_the_cap() {
+        let (buy, sell, snapshot) = two_pools(SOL_RESERVE * 11 / 10);
+        let legs = [(&buy, SwapDirection::BaseToToken), (&sell, SwapDirection::TokenToBase)];
+        let cost = TradeCost { fixed: 5_000, flash_loan_fee_ppm: 0 };
+        // 上限远大于池子，上限的千分之一已经越过利润峰值
+        let max_amount_in = SOL_RESERVE * 1_000;
+        let trade = size_route(&legs, &snapshot, &cost, &Sizing::Optimal { max_amount_in }).unwrap();
+
+        assert_eq!(trade.method, SizingMethod::ClosedForm);
+        assert!(trade.net_profit > 0);
+        assert!(trade.amount_in() < max_amount_in / 1_000);
+    }
+
+    #[test]
+    fn size_route_returns_the_probe_without_marginal_profit() {
+        let (buy, sell, snapshot) = two_pools(SOL_RESERVE);
+        let legs = [(&buy, SwapDirection::BaseToToken), (&sell, SwapDirection::TokenToBase)];
+        let max_amount_in = SOL_RESERVE / 10;
+        let trade =
+            size_route(&legs, &snapshot, &TradeCost::default(), &Sizing::Optimal { max_amount_in })
+                .unwrap();
+
+        assert_eq!(trade.method, SizingMethod::Fixed);
+        assert_eq!(trade.amount_in(), max_amount_in / MARGINAL_PROBE_DIVISOR);
+        assert!(trade.net_profit <= 0);
+    }
+
+    #[test]
+    fn upper_bound_respects_wallet_flash_loan_and_max_amount_in() {
+        let wallet = SizeLimits { wallet_balance: 5_000, flash_loan_available: 0, max_amount_in: None };
+        assert_eq!(wallet.upper_bound(), 5_000);
+
+        // 开启闪电贷后以借款额度为准，不受钱包余额限制
+        let flash_loan = SizeLimits { flash_loan_available: 80_000, ..wallet };
+        assert_eq!(flash_loan.upper_bound(), 80_000);
+
+        let capped = SizeLimits { max_amount_in: Some(20_000), ..flash_loan };
+        assert_eq!(capped.upper_bound(), 20_000);
+        let capped = SizeLimits { max_amount_in: Some(20_000), ..wallet };
+        assert_eq!(capped.upper_bound(), 5_000);
+
+        assert!(matches!(Sizing::new(false, 30_000, &capped), Sizing::Fixed(5_000)));
+        assert!(matches!(Sizing::new(true, 30_000, &capped), Sizing::Optimal { max_amount_in: 5_000 }));
+    }
+}
@@ -0,0 +1,537 @@
+use crate::dex::whirlpool::constants::whirlpool_program_id;
+use crate::quote::clmm_math::{
+    compute_swap_step, get_tick_at_sqrt_price, U256, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK,
+};
+use crate::quote::{
+    read_i128, read_i32, read_pubkey, read_u128, read_u16, AccountSnapshot, PoolKeys,
+    SwapDirection,
+};
+use solana_sdk::pubkey::Pubkey;
+
+pub const TICK_ARRAY_SIZE: i32 = 88;
+// Orca 的价格上限与 Raydium 略有不同
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+// protocol_fee_rate 的精度为 1/10000
+pub const PROTOCOL_FEE_RATE_DENOMINATOR: u128 = 10_000;
+// 一次 swap 指令固定传入 3 个 tick array
+pub const SWAP_TICK_ARRAY_COUNT: i32 = 3;
+
+// Whirlpool 账户字段偏移
+const WHIRLPOOL_TICK_SPACING_OFFSET: usize = 41;
+const WHIRLPOOL_FEE_RATE_OFFSET: usize = 45;
+const WHIRLPOOL_PROTOCOL_FEE_RATE_OFFSET: usize = 47;
+const WHIRLPOOL_LIQUIDITY_OFFSET: usize = 49;
+const WHIRLPOOL_SQRT_PRICE_OFFSET: usize = 65;
+const WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const WHIRLPOOL_TOKEN_VAULT_A_OFFSET: usize = 133;
+const WHIRLPOOL_FEE_GROWTH_GLOBAL_A_OFFSET: usize = 165;
+const WHIRLPOOL_FEE_GROWTH_GLOBAL_B_OFFSET: usize = 245;
+// TickArray: discriminator 之后是 start_tick_index，再之后是 88 个 Tick
+const TICK_ARRAY_START_TICK_INDEX_OFFSET: usize = 8;
+const TICK_ARRAY_TICKS_OFFSET: usize = 12;
+// Tick: initialized(1) + liquidity_net(16) + liquidity_gross(16) + fee/reward growth(80)
+const TICK_LEN: usize = 113;
+
+// 与 Orca 链上 sqrt_price_from_tick_index 一致：负 tick 用 Q64，正 tick 用 Q96 再右移 32 位
+pub fn sqrt_price_from_tick_index(tick: i32) -> anyhow::Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(anyhow::anyhow!("Tick {} is out of range", tick));
+    }
+    const NEGATIVE_RATIOS: [u128; 19] = [
+        18445821805675392311,
+        18444899583751176498,
+        18443055278223354162,
+        18439367220385604838,
+        18431993317065449817,
+        18417254355718160513,
+        18387811781193591352,
+        18329067761203520168,
+        18212142134806087854,
+        17980523815641551639,
+        17526086738831147013,
+        16651378430235024244,
+        15030750278693429944,
+        12247334978882834399,
+        8131365268884726200,
+        3584323654723342297,
+        696457651847595233,
+        26294789957452057,
+        37481735321082,
+    ];
+    const POSITIVE_RATIOS: [u128; 19] = [
+        79232123823359799118286999567,
+        79236085330515764027303304731,
+        79244008939048815603706035061,
+        79259858533276714757314932305,
+        79291567232598584799939703904,
+        79355022692464371645785046466,
+        79482085999252804386437311141,
+        79736823300114093921829183326,
+        80248749790819932309965073892,
+        81282483887344747381513967011,
+        83390072131320151908154831281,
+        87770609709833776024991924138,
+        97234110755111693312479820773,
+        119332217159966728226237229890,
+        179736315981702064433883588727,
+        407748233172238350107850275304,
+        2098478828474011932436660412517,
+        55581415166113811149459800483533,
+        38992368544603139932233054999993551,
+    ];
+
+    let abs_tick = tick.unsigned_abs();
+    if tick >= 0 {
+        let mut ratio = if abs_tick & 1 != 0 {
+            U256::from(POSITIVE_RATIOS[0])
+        } else {
+            U256::one() << 96
+        };
+        for (i, factor) in POSITIVE_RATIOS.iter().enumerate().skip(1) {
+            if abs_tick & (1 << i) != 0 {
+                ratio = (ratio * U256::from(*factor)) >> 96;
+            }
+        }
+        Ok((ratio >> 32).low_u128())
+    } else {
+        let mut ratio: u128 = if abs_tick & 1 != 0 {
+            NEGATIVE_RATIOS[0]
+        } else {
+            1u128 << 64
+        };
+        for (i, factor) in NEGATIVE_RATIOS.iter().enumerate().skip(1) {
+            if abs_tick & (1 << i) != 0 {
+                ratio = (ratio * factor) >> 64;
+            }
+        }
+        Ok(ratio)
+    }
+}
+
+// tick 所在 tick array 的起始 index
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    tick.div_euclid(ticks_in_array) * ticks_in_array
+}
+
+pub fn tick_array_address(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            whirlpool.as_ref(),
+            start_tick_index.to_string().as_bytes(),
+        ],
+        &whirlpool_program_id(),
+    )
+    .0
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WhirlpoolState {
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+    pub protocol_fee_rate: u16,
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub token_vault_a: Pubkey,
+    pub fee_growth_global_a: u128,
+    pub fee_growth_global_b: u128,
+}
+
+impl WhirlpoolState {
+    pub fn load(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            tick_spacing: read_u16(data, WHIRLPOOL_TICK_SPACING_OFFSET)?,
+            fee_rate: read_u16(data, WHIRLPOOL_FEE_RATE_OFFSET)?,
+            protocol_fee_rate: read_u16(data, WHIRLPOOL_PROTOCOL_FEE_RATE_OFFSET)?,
+            liquidity: read_u128(data, WHIRLPOOL_LIQUIDITY_OFFSET)?,
+            sqrt_price: read_u128(data, WHIRLPOOL_SQRT_PRICE_OFFSET)?,
+            tick_current_index: read_i32(data, WHIRLPOOL_TICK_CURRENT_INDEX_OFFSET)?,
+            token_vault_a: read_pubkey(data, WHIRLPOOL_TOKEN_VAULT_A_OFFSET)?,
+            fee_growth_global_a: read_u128(data, WHIRLPOOL_FEE_GROWTH_GLOBAL_A_OFFSET)?,
+            fee_growth_global_b: read_u128(data, WHIRLPOOL_FEE_GROWTH_GLOBAL_B_OFFSET)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WhirlpoolTick {
+    pub tick: i32,
+    pub liquidity_net: i128,
+}
+
+#[derive(Debug, Clone)]
+pub struct WhirlpoolTickArray {
+    pub address: Pubkey,
+    pub start_tick_index: i32,
+    // 只保留已初始化的 tick
+    pub ticks: Vec<WhirlpoolTick>,
+}
+
+impl WhirlpoolTickArray {
+    pub fn load(address: Pubkey, data: &[u8], tick_spacing: u16) -> anyhow::Result<Self> {
+        let start_tick_index = read_i32(data, TICK_ARRAY_START_TICK_INDEX_OFFSET)?;
+        let mut ticks = Vec::new();
+        for i in 0..TICK_ARRAY_SIZE as usize {
+            let offset = TICK_ARRAY_TICKS_OFFSET + i * TICK_LEN;
+            let initialized = *data
+                .get(offset)
+                .ok_or_else(|| anyhow::anyhow!("Tick array {} data too short", address))?;
+            if initialized == 0 {
+                continue;
+            }
+            ticks.push(WhirlpoolTick {
+                tick: start_tick_index + i as i32 * tick_spacing as i32,
+                liquidity_net: read_i128(data, offset + 1)?,
+            });
+        }
+        Ok(Self {
+            address,
+            start_tick_index,
+            ticks,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WhirlpoolSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    // 给 LP 的手续费（已扣除协议费）
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    // 交易后输入 token 一侧的 fee_growth_global
+    pub fee_growth_global: u128,
+    // swap 指令需要的 3 个 tick array（按交易方向排列）
+    pub tick_arrays: [Pubkey; 3],
+}
+
+#[derive(Debug, Clone)]
+pub struct WhirlpoolQuoteState {
+    pub address: Pubkey,
+    pub whirlpool: WhirlpoolState,
+    pub tick_arrays: Vec<WhirlpoolTickArray>,
+}
+
+impl WhirlpoolQuoteState {
+    pub fn load(keys: &PoolKeys, snapshot: &AccountSnapshot) -> anyhow::Result<Self> {
+        let whirlpool = WhirlpoolState::load(snapshot.data(&keys.pool)?)?;
+        let mut tick_arrays = Vec::new();
+        for address in &keys.extra_accounts {
+            if let Ok(data) = snapshot.data(address) {
+                tick_arrays.push(WhirlpoolTickArray::load(*address, data, whirlpool.tick_spacing)?);
+            }
+        }
+        Ok(Self {
+            address: keys.pool,
+            whirlpool,
+            tick_arrays,
+        })
+    }
+
+    pub fn a_to_b(&self, keys: &PoolKeys, direction: SwapDirection) -> bool {
+        let base_is_a = self.whirlpool.token_vault_a == keys.base_vault;
+        match direction {
+            SwapDirection::BaseToToken => base_is_a,
+            SwapDirection::TokenToBase => !base_is_a,
+        }
+    }
+
+    // 与 Orca SDK 一致：b -> a 方向先偏移一个 tick_spacing 再取起始 tick array
+    pub fn swap_tick_array_starts(&self, a_to_b: bool) -> [i32; 3] {
+        let tick_spacing = self.whirlpool.tick_spacing;
+        let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+        let shift = if a_to_b { 0 } else { tick_spacing as i32 };
+        let first = tick_array_start_index(self.whirlpool.tick_current_index + shift, tick_spacing);
+        let step = if a_to_b { -ticks_in_array } else { ticks_in_array };
+        [first, first + step, first + 2 * step]
+    }
+
+    pub fn quote_exact_in(&self, a_to_b: bool, amount_in: u64) -> anyhow::Result<WhirlpoolSwapResult> {
+        self.simulate_swap(a_to_b, amount_in, true)
+    }
+
+    pub fn quote_exact_out(&self, a_to_b: bool, amount_out: u64) -> anyhow::Result<WhirlpoolSwapResult> {
+        self.simulate_swap(a_to_b, amount_out, false)
+    }
+
+    pub fn simulate_swap(
+        &self,
+        a_to_b: bool,
+        amount_specified: u64,
+        amount_specified_is_input: bool,
+    ) -> anyhow::Result<WhirlpoolSwapResult> {
+        let pool = &self.whirlpool;
+        let starts = self.swap_tick_array_starts(a_to_b);
+        let tick_arrays = starts.map(|start| tick_array_address(&self.address, start));
+        let ticks_in_array = TICK_ARRAY_SIZE * pool.tick_spacing as i32;
+
+        // 链上只能看到指令中传入的 3 个 tick array，超出这个范围的 swap 会失败
+        let (window_start, window_end) = if a_to_b {
+            (starts[2], starts[0] + ticks_in_array - 1)
+        } else {
+            (starts[0], starts[2] + ticks_in_array - 1)
+        };
+        let ticks: Vec<WhirlpoolTick> = self
+            .tick_arrays
+            .iter()
+            .filter(|array| starts.contains(&array.start_tick_index))
+            .flat_map(|array| array.ticks.iter().copied())
+            .collect();
+
+        let sqrt_price_limit = if a_to_b {
+            MIN_SQRT_PRICE_X64
+        } else {
+            MAX_SQRT_PRICE_X64
+        };
+
+        let mut amount_remaining = amount_specified as u128;
+        let mut amount_calculated: u128 = 0;
+        let mut sqrt_price = pool.sqrt_price;
+        let mut tick_current_index = pool.tick_current_index;
+        let mut liquidity = pool.liquidity;
+        let mut fee_growth_global = if a_to_b {
+            pool.fee_growth_global_a
+        } else {
+            pool.fee_growth_global_b
+        };
+        let mut lp_fee: u128 = 0;
+        let mut protocol_fee: u128 = 0;
+
+        while amount_remaining > 0 && sqrt_price != sqrt_price_limit {
+            let next = if a_to_b {
+                ticks
+                    .iter()
+                    .filter(|t| t.tick <= tick_current_index)
+                    .max_by_key(|t| t.tick)
+            } else {
+                ticks
+                    .iter()
+                    .filter(|t| t.tick > tick_current_index)
+                    .min_by_key(|t| t.tick)
+            };
+            let (next_tick, liquidity_net) = match next {
+                Some(tick) => (tick.tick, Some(tick.liquidity_net)),
+                None if a_to_b => (window_start.max(MIN_TICK), None),
+                None => (window_end.min(MAX_TICK), None),
+            };
+
+            let next_tick_sqrt_price = sqrt_price_from_tick_index(next_tick)?;
+            let sqrt_price_target = if a_to_b {
+                next_tick_sqrt_price.max(sqrt_price_limit)
+            } else {
+                next_tick_sqrt_price.min(sqrt_price_limit)
+            };
+
+            let step = compute_swap_step(
+                sqrt_price,
+                sqrt_price_target,
+                liquidity,
+                amount_remaining,
+                pool.fee_rate as u32,
+                amount_specified_is_input,
+                a_to_b,
+            )?;
+
+            if amount_specified_is_input {
+                amount_remaining -= step.amount_in + step.fee_amount;
+                amount_calculated += step.amount_out;
+            } else {
+                amount_remaining -= step.amount_out;
+                amount_calculated += step.amount_in + step.fee_amount;
+            }
+
+            // 协议费先从手续费中划出，剩余部分计入 fee_growth_global
+            let step_protocol_fee = step.fee_amount * pool.protocol_fee_rate as u128
+                / PROTOCOL_FEE_RATE_DENOMINATOR;
+            let step_lp_fee = step.fee_amount - step_protocol_fee;
+            protocol_fee += step_protocol_fee;
+            lp_fee += step_lp_fee;
+            if liquidity > 0 {
+                fee_growth_global =
+                    fee_growth_global.wrapping_add(((U256::from(step_lp_fee) << 64) / U256::from(liquidity)).low_u128());
+            }
+
+            sqrt_price = step.sqrt_price_next_x64;
+            if sqrt_price == next_tick_sqrt_price {
+                match liquidity_net {
+                    Some(liquidity_net) => {
+                        let liquidity_net = if a_to_b { -liquidity_net } else { liquidity_net };
+                        liquidity = liquidity
+                            .checked_add_signed(liquidity_net)
+                            .ok_or_else(|| anyhow::anyhow!("Liquidity underflow at tick {}", next_tick))?;
+                    }
+                    None if amount_remaining > 0 => {
+                        return Err(anyhow::anyhow!(
+                            "Swap of {} exceeds the three tick arrays of whirlpool {}",
+                            amount_specified,
+                            self.address
+                        ));
+                    }
+                    None => {}
+                }
+                tick_current_index = if a_to_b { next_tick - 1 } else { next_tick };
+            } else {
+                tick_current_index = get_tick_at_sqrt_price(sqrt_price, sqrt_price_from_tick_index)?;
+            }
+        }
+
+        let (amount_in, amount_out) = if amount_specified_is_input {
+            (amount_specified as u128 - amount_remaining, amount_calculated)
+        } else {
+            (amount_calculated, amount_specified as u128 - amount_remaining)
+        };
+
+        Ok(WhirlpoolSwapResult {
+            amount_in: u64::try_from(amount_in)?,
+            amount_out: u64::try_from(amount_out)?,
+            lp_fee: u64::try_from(lp_fee)?,
+            protocol_fee: u64::try_from(protocol_fee)?,
+            sqrt_price,
+            tick_current_index,
+            fee_growth_global,
+            tick_arrays,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 与 Raydium fixture 相同的价格和流动性，tick_spacing 64，费率 0.03%，协议费 13%
+    const SQRT_PRICE: u128 = 7_030_853_491_284_420_608;
+    const LIQUIDITY: u128 = 112_035_621_120_963;
+
+    fn quote_state(tick_spacing: u16, ticks: &[(i32, i128)]) -> WhirlpoolQuoteState {
+        let whirlpool = WhirlpoolState {
+            tick_spacing,
+            fee_rate: 300,
+            protocol_fee_rate: 1_300,
+            liquidity: LIQUIDITY,
+            sqrt_price: SQRT_PRICE,
+            tick_current_index: -19293,
+            token_vault_a: Pubkey::new_unique(),
+            fee_growth_global_a: 0,
+            fee_growth_global_b: 0,
+        };
+        let start = tick_array_start_index(whirlpool.tick_current_index, tick_spacing);
+        WhirlpoolQuoteState {
+            address: Pubkey::new_unique(),
+            whirlpool,
+            tick_arrays: vec![WhirlpoolTickArray {
+                address: Pubkey::new_unique(),
+                start_tick_index: start,
+                ticks: ticks
+                    .iter()
+                    .map(|(tick, liquidity_net)| WhirlpoolTick {
+                        tick: *tick,
+                        liquidity_net: *liquidity_net,
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    fn crossing_state() -> WhirlpoolQuoteState {
+        quote_state(
+            64,
+            &[
+                (-19392, 30_000_000_000_000),
+                (-19328, 40_000_000_000_000),
+                (-19200, -20_000_000_000_000),
+            ],
+        )
+    }
+
+    #[test]
+    fn sqrt_price_matches_orca_bounds() {
+        assert_eq!(sqrt_price_from_tick_index(0).unwrap(), 1u128 << 64);
+        assert_eq!(sqrt_price_from_tick_index(1).unwrap(), 18_447_666_387_855_959_850);
+        assert_eq!(sqrt_price_from_tick_index(-1).unwrap(), 18_445_821_805_675_392_311);
+        assert_eq!(sqrt_price_from_tick_index(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_from_tick_index(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+    }
+
+    #[test]
+    fn swap_tick_arrays_follow_swap_direction() {
+        let state = crossing_state();
+        assert_eq!(state.swap_tick_array_starts(true), [-22528, -28160, -33792]);
+        assert_eq!(state.swap_tick_array_starts(false), [-22528, -16896, -11264]);
+
+        // 当前 tick 在起始边界下方一格：a -> b 从下一个 array 开始，b -> a 偏移后回到边界上的 array
+        let mut edge = state.clone();
+        edge.whirlpool.tick_current_index = -22529;
+        assert_eq!(edge.swap_tick_array_starts(true), [-28160, -33792, -39424]);
+        assert_eq!(edge.swap_tick_array_starts(false), [-22528, -16896, -11264]);
+
+        let result = state.quote_exact_in(true, 1_000_000_000).unwrap();
+        let expected = state
+            .swap_tick_array_starts(true)
+            .map(|start| tick_array_address(&state.address, start));
+        assert_eq!(result.tick_arrays, expected);
+    }
+
+    #[test]
+    fn exact_in_splits_protocol_fee() {
+        let result = crossing_state().quote_exact_in(true, 10_000_000_000).unwrap();
+        assert_eq!(result.amount_in, 10_000_000_000);
+        assert_eq!(result.amount_out, 1_452_217_191);
+        assert_eq!(result.lp_fee, 2_610_000);
+        assert_eq!(result.protocol_fee, 390_000);
+        assert_eq!(result.sqrt_price, 7_030_614_382_699_145_597);
+        assert_eq!(result.tick_current_index, -19294);
+    }
+
+    #[test]
+    fn a_to_b_crosses_initialized_ticks() {
+        let result = crossing_state().quote_exact_in(true, 1_000_000_000_000).unwrap();
+        assert_eq!(result.amount_out, 144_672_208_571);
+        assert_eq!(result.lp_fee, 261_000_002);
+        assert_eq!(result.protocol_fee, 38_999_999);
+        assert_eq!(result.sqrt_price, 7_000_717_681_630_425_687);
+        assert_eq!(result.tick_current_index, -19379);
+    }
+
+    #[test]
+    fn b_to_a_crosses_initialized_tick() {
+        let result = crossing_state().quote_exact_in(false, 200_000_000_000).unwrap();
+        assert_eq!(result.amount_out, 1_369_917_033_141);
+        assert_eq!(result.lp_fee, 52_200_002);
+        assert_eq!(result.protocol_fee, 7_799_999);
+        assert_eq!(result.sqrt_price, 7_063_840_610_636_013_941);
+        assert_eq!(result.tick_current_index, -19199);
+    }
+
+    #[test]
+    fn exact_out_needs_no_more_than_exact_in() {
+        let state = crossing_state();
+        let exact_in = state.quote_exact_in(true, 1_000_000_000_000).unwrap();
+        let exact_out = state.quote_exact_out(true, exact_in.amount_out).unwrap();
+        assert_eq!(exact_out.amount_out, exact_in.amount_out);
+        assert!(exact_out.amount_in <= exact_in.amount_in);
+    }
+
+    #[test]
+    fn swap_beyond_three_tick_arrays_fails() {
+        // tick_spacing 1 时 3 个 tick array 只覆盖 264 个 tick
+        assert!(quote_state(1, &[]).quote_exact_in(true, 5_000_000_000_000).is_err());
+    }
+
+    #[test]
+    fn tick_array_derives_tick_from_position() {
+        let mut data = vec![0u8; TICK_ARRAY_TICKS_OFFSET + TICK_ARRAY_SIZE as usize * TICK_LEN];
+        data[TICK_ARRAY_START_TICK_INDEX_OFFSET..][..4].copy_from_slice(&(-22528i32).to_le_bytes());
+        let offset = TICK_ARRAY_TICKS_OFFSET + 50 * TICK_LEN;
+        data[offset] = 1;
+        data[offset + 1..][..16].copy_from_slice(&40_000_000_000_000i128.to_le_bytes());
+
+        let array = WhirlpoolTickArray::load(Pubkey::new_unique(), &data, 64).unwrap();
+        assert_eq!(array.ticks.len(), 1);
+        assert_eq!(array.ticks[0].tick, -22528 + 50 * 64);
+        assert_eq!(array.ticks[0].liquidity_net, 40_000_000_000_000);
+    }
+}
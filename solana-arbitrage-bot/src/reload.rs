@@ -0,0 +1,272 @@
+use crate::config::{Config, MintConfig, ReloadConfig};
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, SystemTime};
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(2_000);
+// 热加载只处理 [routing] 中的 mint_config_list，其余部分变化时提示需要重启
+const RELOADABLE_SECTION: &str = "routing";
+
+// 重新读取的配置文件，raw 用于比较不会热加载的部分
+pub struct LoadedConfig {
+    pub config: Config,
+    pub raw: toml::Value,
+}
+
+impl LoadedConfig {
+    // 与启动时相同的检查，有问题时不应用新配置
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let config = Config::load(path)?;
+        let contents = fs::read_to_string(path)?;
+        let raw = toml::from_str(&contents)?;
+        Ok(Self { config, raw })
+    }
+}
+
+// mint_config_list 的变化，按 MintConfig::key 匹配新旧条目
+#[derive(Debug, Default)]
+pub struct RoutingChanges {
+    pub added: Vec<MintConfig>,
+    pub removed: Vec<MintConfig>,
+    // 有变化的 mint 的新配置
+    pub changed: Vec<MintConfig>,
+}
+
+impl RoutingChanges {
+    pub fn diff(old: &[MintConfig], new: &[MintConfig]) -> Self {
+        let mut changes = Self::default();
+        for new_config in new {
+            match old
+                .iter()
+                .find(|old_config| old_config.key() == new_config.key())
+            {
+                Some(old_config) if old_config != new_config => {
+                    changes.changed.push(new_config.clone())
+                }
+                Some(_) => {}
+                None => changes.added.push(new_config.clone()),
+            }
+        }
+        for old_config in old {
+            if new
+                .iter()
+                .all(|new_config| new_config.key() != old_config.key())
+            {
+                changes.removed.push(old_config.clone());
+            }
+        }
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// 各 DEX 的池子列表是否有变化
+pub fn pools_changed(old: &MintConfig, new: &MintConfig) -> bool {
+    old.pools != new.pools
+}
+
+// 池子列表以外的字段是否有变化，有变化时需要重启报价任务
+pub fn settings_changed(old: &MintConfig, new: &MintConfig) -> bool {
+    let without_pools = |mint_config: &MintConfig| MintConfig {
+        pools: HashMap::new(),
+        ..mint_config.clone()
+    };
+    without_pools(old) != without_pools(new)
+}
+
+// 不会热加载的部分中发生变化的，如 "wallet"、"rpc"、"routing.cycle"
+pub fn non_reloadable_changes(old: &toml::Value, new: &toml::Value) -> Vec<String> {
+    let empty = toml::value::Table::new();
+    let old_table = old.as_table().unwrap_or(&empty);
+    let new_table = new.as_table().unwrap_or(&empty);
+
+    let mut changed: Vec<String> = old_table
+        .keys()
+        .chain(new_table.keys().filter(|key| !old_table.contains_key(*key)))
+        .filter(|key| key.as_str() != RELOADABLE_SECTION)
+        .filter(|key| old_table.get(*key) != new_table.get(*key))
+        .cloned()
+        .collect();
+    let cycle = |table: &toml::value::Table| {
+        table
+            .get(RELOADABLE_SECTION)
+            .and_then(|routing| routing.get("cycle"))
+            .cloned()
+    };
+    if cycle(old_table) != cycle(new_table) {
+        changed.push(format!("{}.cycle", RELOADABLE_SECTION));
+    }
+    changed
+}
+
+// 热加载的触发：SIGHUP，或开启 watch 时配置文件的修改时间变化
+pub struct ReloadTrigger {
+    path: String,
+    hangup: Signal,
+    poll_interval: Option<Duration>,
+    modified: Option<SystemTime>,
+}
+
+impl ReloadTrigger {
+    pub fn new(path: &str, reload_config: Option<&ReloadConfig>) -> anyhow::Result<Self> {
+        let poll_interval = reload_config
+            .filter(|reload_config| reload_config.watch)
+            .map(|reload_config| {
+                reload_config
+                    .poll_interval_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or(DEFAULT_POLL_INTERVAL)
+            });
+        Ok(Self {
+            path: path.to_string(),
+            hangup: signal(SignalKind::hangup())?,
+            poll_interval,
+            modified: modified_time(path),
+        })
+    }
+
+    // 返回触发的来源
+    pub async fn wait(&mut self) -> &'static str {
+        loop {
+            tokio::select! {
+                _ = self.hangup.recv() => {
+                    self.modified = modified_time(&self.path);
+                    return "SIGHUP";
+                }
+                _ = tokio::time::sleep(self.poll_interval.unwrap_or_default()), if self.poll_interval.is_some() => {
+                    let modified = modified_time(&self.path);
+                    if modified != self.modified {
+                        self.modified = modified;
+                        return "config file change";
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn mint_config(mint: &str, base_mint: Option<&str>, pools: &[&str]) -> MintConfig {
+        MintConfig {
+            mint: mint.to_string(),
+            base_mint: base_mint.map(str::to_string),
+            base_per_sol: None,
+            amount_in: None,
+            min_profit: None,
+            max_amount_in: None,
+            max_flash_loan: None,
+            compute_unit_price: None,
+            lookup_table_list: None,
+            process_delay: 400,
+            pools: HashMap::from([(
+                "raydium_pool_list".to_string(),
+                pools.iter().map(|pool| pool.to_string()).collect(),
+            )]),
+        }
+    }
+
+    fn keys(configs: &[MintConfig]) -> Vec<String> {
+        configs.iter().map(MintConfig::key).collect()
+    }
+
+    #[test]
+    fn diff_matches_entries_by_mint_and_base_mint() {
+        let old = vec![
+            mint_config("mint-a", None, &["pool-1"]),
+            mint_config("mint-a", Some(USDC), &["pool-2"]),
+            mint_config("mint-b", None, &["pool-3"]),
+        ];
+        let new = vec![
+            // 池子变化
+            mint_config("mint-a", None, &["pool-1", "pool-4"]),
+            mint_config("mint-a", Some(USDC), &["pool-2"]),
+            mint_config("mint-c", None, &["pool-5"]),
+        ];
+        let changes = RoutingChanges::diff(&old, &new);
+
+        assert_eq!(keys(&changes.added), vec!["mint-c"]);
+        assert_eq!(keys(&changes.removed), vec!["mint-b"]);
+        assert_eq!(keys(&changes.changed), vec!["mint-a"]);
+        assert_eq!(changes.changed[0].pools["raydium_pool_list"].len(), 2);
+        assert!(!changes.is_empty());
+        assert!(RoutingChanges::diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn base_mint_change_is_a_remove_and_an_add() {
+        let old = vec![mint_config("mint-a", None, &["pool-1"])];
+        let new = vec![mint_config("mint-a", Some(USDC), &["pool-1"])];
+        let changes = RoutingChanges::diff(&old, &new);
+
+        assert_eq!(keys(&changes.added), vec![format!("mint-a/{}", USDC)]);
+        assert_eq!(keys(&changes.removed), vec!["mint-a"]);
+        assert!(changes.changed.is_empty());
+    }
+
+    #[test]
+    fn pools_and_settings_changes_are_separate() {
+        let old = mint_config("mint-a", None, &["pool-1"]);
+        let more_pools = mint_config("mint-a", None, &["pool-1", "pool-2"]);
+        let slower = MintConfig {
+            process_delay: 1_000,
+            ..old.clone()
+        };
+
+        assert!(pools_changed(&old, &more_pools));
+        assert!(!settings_changed(&old, &more_pools));
+        assert!(!pools_changed(&old, &slower));
+        assert!(settings_changed(&old, &slower));
+    }
+
+    #[test]
+    fn non_reloadable_changes_ignore_mint_config_list() {
+        let old: toml::Value = toml::from_str(
+            r#"
+[rpc]
+url = "http://a"
+
+[[routing.mint_config_list]]
+mint = "mint-a"
+process_delay = 400
+"#,
+        )
+        .unwrap();
+        let new: toml::Value = toml::from_str(
+            r#"
+[rpc]
+url = "http://b"
+
+[jito]
+enabled = true
+
+[[routing.mint_config_list]]
+mint = "mint-b"
+process_delay = 400
+
+[routing.cycle]
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        let mut changed = non_reloadable_changes(&old, &new);
+        changed.sort();
+        assert_eq!(changed, vec!["jito", "routing.cycle", "rpc"]);
+        assert!(non_reloadable_changes(&new, &new).is_empty());
+    }
+}
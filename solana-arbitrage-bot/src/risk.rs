@@ -0,0 +1,655 @@
+use crate::config::RiskConfig;
+use crate::confirmation::{OutcomeRecord, TransactionOutcome};
+use crate::constants::sol_mint;
+use crate::journal::unix_timestamp;
+use crate::quote::base_in_lamports;
+use crate::supervisor::Shutdown;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+const SECONDS_PER_HOUR: u64 = 3_600;
+const SECONDS_PER_DAY: u64 = 86_400;
+const DEFAULT_FEE_WINDOW: Duration = Duration::from_secs(SECONDS_PER_HOUR);
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(60);
+
+// 暂停发送交易的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HaltReason {
+    KillSwitch { source: String },
+    LowBalance { balance: u64, min_balance: u64 },
+    HourlyLoss { loss: i64, limit: u64 },
+    DailyLoss { loss: i64, limit: u64 },
+    FeeBudget { fees: u64, limit: u64, window: Duration },
+    ConsecutiveFailures { failures: u32, limit: u32 },
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HaltReason::KillSwitch { source } => write!(f, "kill switch engaged by {}", source),
+            HaltReason::LowBalance { balance, min_balance } => write!(
+                f,
+                "wallet balance {} lamports is below the minimum {}",
+                balance, min_balance
+            ),
+            HaltReason::HourlyLoss { loss, limit } => write!(
+                f,
+                "loss {} lamports in the last hour exceeds the limit {}",
+                loss, limit
+            ),
+            HaltReason::DailyLoss { loss, limit } => write!(
+                f,
+                "loss {} lamports in the last day exceeds the limit {}",
+                loss, limit
+            ),
+            HaltReason::FeeBudget { fees, limit, window } => write!(
+                f,
+                "fees {} lamports in the last {:?} exceed the limit {}",
+                fees, window, limit
+            ),
+            HaltReason::ConsecutiveFailures { failures, limit } => write!(
+                f,
+                "{} consecutive failed transactions (limit {})",
+                failures, limit
+            ),
+        }
+    }
+}
+
+// 当前的风控状态，由 RiskGuard::status 提供
+#[derive(Debug, Clone, Default)]
+pub struct RiskStatus {
+    pub halted: Option<HaltReason>,
+    pub hourly_profit: i64,
+    pub daily_profit: i64,
+    pub window_fees: u64,
+    pub consecutive_failures: u32,
+    pub sol_balance: Option<u64>,
+}
+
+impl fmt::Display for RiskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.halted {
+            Some(reason) => write!(f, "paused ({})", reason)?,
+            None => write!(f, "running")?,
+        }
+        write!(
+            f,
+            ", profit 1h {} lamports, profit 24h {} lamports, fees {} lamports, consecutive failures {}",
+            self.hourly_profit, self.daily_profit, self.window_fees, self.consecutive_failures
+        )?;
+        if let Some(balance) = self.sol_balance {
+            write!(f, ", SOL balance {}", balance)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ResolvedTransaction {
+    timestamp: u64,
+    // 换算成 lamports 的损益，不同 base mint 的交易可以放在同一个窗口中累计
+    profit: i64,
+    fee: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskLimits {
+    // 亏损以 lamports 计，base 不是 SOL 的交易按 base_per_sol 换算
+    pub max_hourly_loss: Option<u64>,
+    pub max_daily_loss: Option<u64>,
+    pub max_consecutive_failures: Option<u32>,
+    // None 时连续失败触发的暂停不会自动解除
+    pub failure_cooldown: Option<Duration>,
+    pub min_sol_balance: Option<u64>,
+    pub max_fees_per_window: Option<u64>,
+    pub fee_window: Duration,
+    // base mint -> 1 SOL 折合的 base token 数量，取自 MintConfig 和 [routing.cycle]
+    pub base_per_sol: HashMap<Pubkey, u64>,
+}
+
+impl RiskLimits {
+    pub fn from_config(risk_config: &RiskConfig, base_per_sol: HashMap<Pubkey, u64>) -> Self {
+        Self {
+            max_hourly_loss: risk_config.max_hourly_loss,
+            max_daily_loss: risk_config.max_daily_loss,
+            max_consecutive_failures: risk_config.max_consecutive_failures,
+            failure_cooldown: risk_config.failure_cooldown_ms.map(Duration::from_millis),
+            min_sol_balance: risk_config.min_sol_balance,
+            max_fees_per_window: risk_config.max_fees_per_window,
+            fee_window: risk_config
+                .fee_window_ms
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_FEE_WINDOW),
+            base_per_sol,
+        }
+    }
+
+    // 把一笔交易的 base token 损益换算成扣除手续费后的 lamports
+    // base 为 SOL 时余额变化已经包含手续费；无法换算时为 None
+    fn profit_in_lamports(&self, base_mint: &Pubkey, profit: i64, fee: u64) -> Option<i64> {
+        if *base_mint == sol_mint() {
+            return Some(profit);
+        }
+        let profit = base_in_lamports(profit, base_mint, self.base_per_sol.get(base_mint).copied())?;
+        Some(profit - fee as i64)
+    }
+}
+
+#[derive(Debug, Default)]
+struct RiskState {
+    // 最近一天内已确认的交易，用于滚动窗口内的损益和手续费
+    resolved: VecDeque<ResolvedTransaction>,
+    consecutive_failures: u32,
+    // 连续失败达到上限的时间，配置了冷却时间时冷却结束后清零重新计数
+    failures_tripped_at: Option<u64>,
+    sol_balance: Option<u64>,
+    kill_file: bool,
+    kill_signal: bool,
+    halted: Option<HaltReason>,
+}
+
+impl RiskState {
+    fn sums_since(&self, since: u64) -> (i64, u64) {
+        self.resolved
+            .iter()
+            .filter(|transaction| transaction.timestamp >= since)
+            .fold((0, 0), |(profit, fees), transaction| {
+                (profit + transaction.profit, fees + transaction.fee)
+            })
+    }
+
+    fn status(&self, limits: &RiskLimits, now: u64) -> RiskStatus {
+        RiskStatus {
+            halted: self.halted.clone(),
+            hourly_profit: self.sums_since(now.saturating_sub(SECONDS_PER_HOUR)).0,
+            daily_profit: self.sums_since(now.saturating_sub(SECONDS_PER_DAY)).0,
+            window_fees: self.sums_since(now.saturating_sub(limits.fee_window.as_secs())).1,
+            consecutive_failures: self.consecutive_failures,
+            sol_balance: self.sol_balance,
+        }
+    }
+
+    // 按顺序检查各项限制，返回第一个触发的原因
+    fn evaluate(&mut self, limits: &RiskLimits, now: u64) -> Option<HaltReason> {
+        let retain_since = now.saturating_sub(SECONDS_PER_DAY.max(limits.fee_window.as_secs()));
+        while self
+            .resolved
+            .front()
+            .map_or(false, |transaction| transaction.timestamp < retain_since)
+        {
+            self.resolved.pop_front();
+        }
+
+        if self.kill_file {
+            return Some(HaltReason::KillSwitch {
+                source: "kill switch file".to_string(),
+            });
+        }
+        if self.kill_signal {
+            return Some(HaltReason::KillSwitch {
+                source: "SIGUSR1".to_string(),
+            });
+        }
+        if let (Some(min_balance), Some(balance)) = (limits.min_sol_balance, self.sol_balance) {
+            if balance < min_balance {
+                return Some(HaltReason::LowBalance { balance, min_balance });
+            }
+        }
+        let status = self.status(limits, now);
+        if let Some(limit) = limits.max_hourly_loss {
+            if -status.hourly_profit > limit as i64 {
+                return Some(HaltReason::HourlyLoss {
+                    loss: -status.hourly_profit,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = limits.max_daily_loss {
+            if -status.daily_profit > limit as i64 {
+                return Some(HaltReason::DailyLoss {
+                    loss: -status.daily_profit,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = limits.max_fees_per_window {
+            if status.window_fees > limit {
+                return Some(HaltReason::FeeBudget {
+                    fees: status.window_fees,
+                    limit,
+                    window: limits.fee_window,
+                });
+            }
+        }
+        if let Some(limit) = limits.max_consecutive_failures {
+            if let (Some(tripped_at), Some(cooldown)) = (self.failures_tripped_at, limits.failure_cooldown) {
+                if now >= tripped_at + cooldown.as_secs() {
+                    self.consecutive_failures = 0;
+                    self.failures_tripped_at = None;
+                }
+            }
+            if self.consecutive_failures >= limit {
+                self.failures_tripped_at.get_or_insert(now);
+                return Some(HaltReason::ConsecutiveFailures {
+                    failures: self.consecutive_failures,
+                    limit,
+                });
+            }
+        }
+        None
+    }
+}
+
+// 全局风控，各 mint 任务在每轮开始前检查，触发限制时暂停发送，条件解除后自动恢复
+// 连续失败触发的暂停默认需要 SIGUSR2 手动恢复
+#[derive(Clone)]
+pub struct RiskGuard {
+    limits: Arc<RiskLimits>,
+    state: Arc<Mutex<RiskState>>,
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits: Arc::new(limits),
+            state: Arc::new(Mutex::new(RiskState::default())),
+        }
+    }
+
+    pub async fn is_halted(&self) -> bool {
+        self.state.lock().await.halted.is_some()
+    }
+
+    pub async fn status(&self) -> RiskStatus {
+        self.state.lock().await.status(&self.limits, unix_timestamp())
+    }
+
+    // 重新检查所有限制，状态变化时输出原因
+    pub async fn evaluate(&self) -> Option<HaltReason> {
+        let mut state = self.state.lock().await;
+        let halted = state.evaluate(&self.limits, unix_timestamp());
+        if halted != state.halted {
+            match &halted {
+                Some(reason) => warn!("Trading paused: {}", reason),
+                None => info!("Trading resumed, risk limits cleared"),
+            }
+            state.halted = halted.clone();
+        }
+        halted
+    }
+
+    // 由 ConfirmationTracker 在每笔交易确认后调用
+    pub async fn record_outcome(&self, record: &OutcomeRecord) {
+        {
+            let mut state = self.state.lock().await;
+            match &record.outcome {
+                TransactionOutcome::Landed { .. } => state.consecutive_failures = 0,
+                TransactionOutcome::Failed { .. } => state.consecutive_failures += 1,
+                // 过期的交易和丢弃的 bundle 没有上链，不产生费用，也不算失败
+                TransactionOutcome::Expired | TransactionOutcome::Dropped { .. } => {}
+            }
+            if let Some(details) = record.outcome.details() {
+                let base_mint = &record.transaction.base_mint;
+                // 无法换算时只计手续费，避免不同 base token 的数量直接相加
+                let profit = self
+                    .limits
+                    .profit_in_lamports(base_mint, details.profit, details.fee)
+                    .unwrap_or_else(|| {
+                        warn!(
+                            "No base_per_sol for base mint {}, counting only the fee of {} in risk limits",
+                            base_mint, record.transaction.signature
+                        );
+                        -(details.fee as i64)
+                    });
+                state.resolved.push_back(ResolvedTransaction {
+                    timestamp: unix_timestamp(),
+                    profit,
+                    fee: details.fee,
+                });
+            }
+        }
+        self.evaluate().await;
+    }
+
+    async fn set_kill_file(&self, engaged: bool) {
+        self.state.lock().await.kill_file = engaged;
+    }
+
+    async fn set_kill_signal(&self, engaged: bool) {
+        self.state.lock().await.kill_signal = engaged;
+    }
+
+    // SIGUSR2：解除手动暂停，同时清零连续失败计数
+    async fn release(&self) {
+        let mut state = self.state.lock().await;
+        state.kill_signal = false;
+        state.consecutive_failures = 0;
+        state.failures_tripped_at = None;
+    }
+
+    async fn set_sol_balance(&self, balance: u64) {
+        self.state.lock().await.sol_balance = Some(balance);
+    }
+}
+
+// 定时检查钱包 SOL 余额和 kill switch 文件，并按 status_interval_ms 输出风控状态
+// SIGUSR1 手动暂停，SIGUSR2 解除手动暂停和连续失败触发的暂停
+pub async fn run_risk_monitor(
+    guard: RiskGuard,
+    rpc_client: Arc<NonblockingRpcClient>,
+    wallet: Pubkey,
+    risk_config: RiskConfig,
+    mut shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    let check_interval = risk_config
+        .check_interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_CHECK_INTERVAL);
+    let status_interval = risk_config
+        .status_interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_STATUS_INTERVAL);
+    let mut last_status: Option<Instant> = None;
+    let mut kill_signal = signal(SignalKind::user_defined1())?;
+    let mut resume_signal = signal(SignalKind::user_defined2())?;
+
+    while !shutdown.is_triggered() {
+        if guard.limits.min_sol_balance.is_some() {
+            match rpc_client.get_balance(&wallet).await {
+                Ok(balance) => guard.set_sol_balance(balance).await,
+                Err(e) => error!("Failed to get wallet SOL balance for risk checks: {}", e),
+            }
+        }
+        if let Some(kill_switch_file) = &risk_config.kill_switch_file {
+            guard.set_kill_file(Path::new(kill_switch_file).exists()).await;
+        }
+        guard.evaluate().await;
+        if last_status.map_or(true, |last_status| last_status.elapsed() >= status_interval) {
+            info!("Risk status: {}", guard.status().await);
+            last_status = Some(Instant::now());
+        } else {
+            debug!("Risk status: {}", guard.status().await);
+        }
+
+        tokio::select! {
+            _ = shutdown.sleep(check_interval) => {}
+            _ = kill_signal.recv() => {
+                warn!("Received SIGUSR1, engaging kill switch");
+                guard.set_kill_signal(true).await;
+            }
+            _ = resume_signal.recv() => {
+                info!("Received SIGUSR2, releasing kill switch and resetting consecutive failures");
+                guard.release().await;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confirmation::{LandedDetails, SentTransaction};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::Signature;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn limits() -> RiskLimits {
+        RiskLimits {
+            max_hourly_loss: None,
+            max_daily_loss: None,
+            max_consecutive_failures: None,
+            failure_cooldown: None,
+            min_sol_balance: None,
+            max_fees_per_window: None,
+            fee_window: DEFAULT_FEE_WINDOW,
+            base_per_sol: HashMap::new(),
+        }
+    }
+
+    fn resolved(age: u64, profit: i64, fee: u64) -> ResolvedTransaction {
+        ResolvedTransaction {
+            timestamp: NOW - age,
+            profit,
+            fee,
+        }
+    }
+
+    fn state(transactions: &[ResolvedTransaction]) -> RiskState {
+        RiskState {
+            resolved: transactions.iter().copied().collect(),
+            ..RiskState::default()
+        }
+    }
+
+    fn outcome(base_mint: Pubkey, outcome: TransactionOutcome) -> OutcomeRecord {
+        OutcomeRecord {
+            transaction: SentTransaction {
+                signature: Signature::default(),
+                rpcs: vec![],
+                route: "test".to_string(),
+                mint: Pubkey::new_unique().to_string(),
+                base_mint,
+                expected_profit: None,
+                blockhash: Hash::default(),
+            },
+            outcome,
+        }
+    }
+
+    fn failed(fee: u64) -> TransactionOutcome {
+        TransactionOutcome::Failed {
+            slot: 1,
+            error: "custom program error".to_string(),
+            details: Some(LandedDetails {
+                profit: -(fee as i64),
+                fee,
+                compute_units_consumed: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn hourly_loss_only_counts_the_last_hour() {
+        let limits = RiskLimits {
+            max_hourly_loss: Some(10_000),
+            ..limits()
+        };
+        // 一小时前的亏损不计入小时窗口
+        let mut state = state(&[resolved(SECONDS_PER_HOUR + 1, -50_000, 0), resolved(10, -10_000, 0)]);
+        assert_eq!(state.evaluate(&limits, NOW), None);
+
+        state.resolved.push_back(resolved(5, -1, 0));
+        assert_eq!(
+            state.evaluate(&limits, NOW),
+            Some(HaltReason::HourlyLoss {
+                loss: 10_001,
+                limit: 10_000
+            })
+        );
+    }
+
+    #[test]
+    fn daily_loss_nets_profits_within_the_day() {
+        let limits = RiskLimits {
+            max_daily_loss: Some(20_000),
+            ..limits()
+        };
+        let mut state = state(&[
+            resolved(SECONDS_PER_DAY + 1, -100_000, 0),
+            resolved(SECONDS_PER_HOUR * 5, -30_000, 0),
+            resolved(SECONDS_PER_HOUR * 2, 15_000, 0),
+        ]);
+        assert_eq!(state.evaluate(&limits, NOW), None);
+        assert_eq!(state.status(&limits, NOW).daily_profit, -15_000);
+
+        state.resolved.push_back(resolved(1, -5_001, 0));
+        assert_eq!(
+            state.evaluate(&limits, NOW),
+            Some(HaltReason::DailyLoss {
+                loss: 20_001,
+                limit: 20_000
+            })
+        );
+    }
+
+    #[test]
+    fn fee_budget_uses_the_configured_window() {
+        let limits = RiskLimits {
+            max_fees_per_window: Some(10_000),
+            fee_window: Duration::from_secs(600),
+            ..limits()
+        };
+        let mut state = state(&[resolved(601, 0, 50_000), resolved(300, 0, 6_000), resolved(1, 0, 4_000)]);
+        assert_eq!(state.evaluate(&limits, NOW), None);
+        assert_eq!(state.status(&limits, NOW).window_fees, 10_000);
+
+        state.resolved.push_back(resolved(0, 0, 1));
+        assert_eq!(
+            state.evaluate(&limits, NOW),
+            Some(HaltReason::FeeBudget {
+                fees: 10_001,
+                limit: 10_000,
+                window: Duration::from_secs(600)
+            })
+        );
+    }
+
+    #[test]
+    fn evaluate_prunes_transactions_older_than_every_window() {
+        let mut state = state(&[resolved(SECONDS_PER_DAY + 1, -1, 1), resolved(SECONDS_PER_DAY, -1, 1)]);
+        state.evaluate(&limits(), NOW);
+        assert_eq!(state.resolved.len(), 1);
+
+        // 手续费窗口比一天长时按手续费窗口保留
+        let limits = RiskLimits {
+            fee_window: Duration::from_secs(SECONDS_PER_DAY * 2),
+            ..limits()
+        };
+        let mut state = self::state(&[resolved(SECONDS_PER_DAY + 1, -1, 1)]);
+        state.evaluate(&limits, NOW);
+        assert_eq!(state.resolved.len(), 1);
+    }
+
+    #[test]
+    fn kill_switches_take_precedence_over_other_limits() {
+        let limits = RiskLimits {
+            min_sol_balance: Some(1_000_000),
+            max_hourly_loss: Some(0),
+            ..limits()
+        };
+        let mut state = state(&[resolved(1, -1, 0)]);
+        state.sol_balance = Some(0);
+        state.kill_file = true;
+        state.kill_signal = true;
+        let reason = |source: &str| {
+            Some(HaltReason::KillSwitch {
+                source: source.to_string(),
+            })
+        };
+
+        assert_eq!(state.evaluate(&limits, NOW), reason("kill switch file"));
+        state.kill_file = false;
+        assert_eq!(state.evaluate(&limits, NOW), reason("SIGUSR1"));
+        state.kill_signal = false;
+        assert_eq!(
+            state.evaluate(&limits, NOW),
+            Some(HaltReason::LowBalance {
+                balance: 0,
+                min_balance: 1_000_000
+            })
+        );
+        state.sol_balance = Some(1_000_000);
+        assert!(matches!(state.evaluate(&limits, NOW), Some(HaltReason::HourlyLoss { .. })));
+    }
+
+    #[test]
+    fn consecutive_failures_stay_halted_without_a_cooldown() {
+        let limits = RiskLimits {
+            max_consecutive_failures: Some(3),
+            ..limits()
+        };
+        let mut state = RiskState {
+            consecutive_failures: 3,
+            ..RiskState::default()
+        };
+        let halted = Some(HaltReason::ConsecutiveFailures { failures: 3, limit: 3 });
+        assert_eq!(state.evaluate(&limits, NOW), halted);
+        assert_eq!(state.evaluate(&limits, NOW + SECONDS_PER_DAY), halted);
+    }
+
+    #[test]
+    fn consecutive_failures_resume_after_the_configured_cooldown() {
+        let limits = RiskLimits {
+            max_consecutive_failures: Some(3),
+            failure_cooldown: Some(Duration::from_secs(300)),
+            ..limits()
+        };
+        let mut state = RiskState {
+            consecutive_failures: 3,
+            ..RiskState::default()
+        };
+        assert!(state.evaluate(&limits, NOW).is_some());
+        assert!(state.evaluate(&limits, NOW + 299).is_some());
+        assert_eq!(state.evaluate(&limits, NOW + 300), None);
+        assert_eq!(state.consecutive_failures, 0);
+        assert_eq!(state.failures_tripped_at, None);
+    }
+
+    #[test]
+    fn profit_in_lamports_converts_non_sol_bases_and_subtracts_the_fee() {
+        let usdc = Pubkey::new_unique();
+        let limits = RiskLimits {
+            // 150 USDC / SOL
+            base_per_sol: HashMap::from([(usdc, 150_000_000)]),
+            ..limits()
+        };
+        // SOL 的余额变化已经包含手续费
+        assert_eq!(limits.profit_in_lamports(&sol_mint(), -5_000, 5_000), Some(-5_000));
+        // 1.5 USDC = 0.01 SOL
+        assert_eq!(limits.profit_in_lamports(&usdc, 1_500_000, 5_000), Some(10_000_000 - 5_000));
+        assert_eq!(limits.profit_in_lamports(&usdc, -1_500_000, 5_000), Some(-10_000_000 - 5_000));
+        assert_eq!(limits.profit_in_lamports(&Pubkey::new_unique(), 1_500_000, 5_000), None);
+    }
+
+    #[tokio::test]
+    async fn record_outcome_counts_failures_and_converts_losses() {
+        let usdc = Pubkey::new_unique();
+        let guard = RiskGuard::new(RiskLimits {
+            max_consecutive_failures: Some(2),
+            base_per_sol: HashMap::from([(usdc, 150_000_000)]),
+            ..limits()
+        });
+
+        guard.record_outcome(&outcome(usdc, failed(5_000))).await;
+        guard.record_outcome(&outcome(sol_mint(), TransactionOutcome::Expired)).await;
+        let status = guard.status().await;
+        assert_eq!(status.consecutive_failures, 1);
+        // 失败交易在 USDC 余额上没有变化，只计手续费
+        assert_eq!(status.hourly_profit, -5_000 - lamports_of_usdc(5_000));
+        assert!(!guard.is_halted().await);
+
+        guard.record_outcome(&outcome(Pubkey::new_unique(), failed(5_000))).await;
+        assert!(guard.is_halted().await);
+
+        guard.release().await;
+        assert_eq!(guard.evaluate().await, None);
+        assert!(!guard.is_halted().await);
+    }
+
+    // failed() 的损益为 -fee 个 base token，按 150 USDC / SOL 换算
+    fn lamports_of_usdc(amount: u64) -> i64 {
+        (amount as i64) * 1_000_000_000 / 150_000_000
+    }
+}
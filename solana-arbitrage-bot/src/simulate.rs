@@ -0,0 +1,276 @@
+use crate::accounts::{fetch_base_balance, wallet_token_account};
+use crate::constants::sol_mint;
+use crate::quote::read_u64;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use std::fmt;
+use tracing::{info, warn};
+
+// SPL token 账户中 amount 的偏移：mint(32) + owner(32)
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+// 一次模拟的结果，profit 为模拟前后钱包 base token 余额的变化（SOL 已扣除交易费）
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub profit: Option<i64>,
+    pub logs: Vec<String>,
+}
+
+impl SimulationReport {
+    // 模拟成功且余额增加才允许发送
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.profit.map_or(false, |profit| profit > 0)
+    }
+
+    // 日志中最后一条程序报错，通常比 TransactionError 更能说明失败原因
+    pub fn failure_log(&self) -> Option<&str> {
+        self.logs
+            .iter()
+            .rev()
+            .find(|log| log.contains("failed") || log.contains("Error"))
+            .map(String::as_str)
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "error {}", error)?,
+            None => write!(f, "ok")?,
+        }
+        match self.units_consumed {
+            Some(units) => write!(f, ", {} CU", units)?,
+            None => write!(f, ", unknown CU")?,
+        }
+        match self.profit {
+            Some(profit) => write!(f, ", profit {}", profit)?,
+            None => write!(f, ", unknown profit")?,
+        }
+        if let Some(log) = self.failure_log() {
+            write!(f, ", log: {}", log)?;
+        }
+        Ok(())
+    }
+}
+
+// 钱包持有 base token 的账户：SOL 为钱包本身，其他代币为 ATA
+fn base_account(wallet: &Pubkey, base_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    if *base_mint == sol_mint() {
+        *wallet
+    } else {
+        wallet_token_account(wallet, base_mint, token_program)
+    }
+}
+
+fn base_balance(account: &Account, base_mint: &Pubkey) -> anyhow::Result<u64> {
+    if *base_mint == sol_mint() {
+        Ok(account.lamports)
+    } else {
+        read_u64(&account.data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+    }
+}
+
+// 调用 simulateTransaction，并读取模拟后的 base token 账户计算利润
+pub async fn simulate_transaction(
+    rpc_client: &NonblockingRpcClient,
+    transaction: &VersionedTransaction,
+    wallet: &Pubkey,
+    base_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> anyhow::Result<SimulationReport> {
+    let account = base_account(wallet, base_mint, token_program);
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        commitment: Some(CommitmentConfig::processed()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: vec![account.to_string()],
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+
+    let (pre_balance, simulation) = tokio::join!(
+        fetch_base_balance(rpc_client, wallet, base_mint, token_program),
+        rpc_client.simulate_transaction_with_config(transaction, config)
+    );
+    simulation_report(simulation?.value, pre_balance, &account, base_mint)
+}
+
+// 由模拟结果和模拟前的余额得到报告，模拟返回的第一个账户为 base token 账户
+fn simulation_report(
+    result: RpcSimulateTransactionResult,
+    pre_balance: anyhow::Result<u64>,
+    account: &Pubkey,
+    base_mint: &Pubkey,
+) -> anyhow::Result<SimulationReport> {
+    let post_balance = result
+        .accounts
+        .as_ref()
+        .and_then(|accounts| accounts.first().cloned().flatten())
+        .and_then(|account| account.decode::<Account>())
+        .map(|account| base_balance(&account, base_mint))
+        .transpose()?;
+    let profit = match (pre_balance, post_balance) {
+        (Ok(pre_balance), Some(post_balance)) => Some(post_balance as i64 - pre_balance as i64),
+        (Err(e), _) => {
+            warn!("Failed to fetch pre-simulation balance of {}: {}", account, e);
+            None
+        }
+        (_, None) => None,
+    };
+
+    Ok(SimulationReport {
+        error: result.err.map(|error| format!("{:?}", error)),
+        units_consumed: result.units_consumed,
+        profit,
+        logs: result.logs.unwrap_or_default(),
+    })
+}
+
+// 发送前的模拟检查，每次尝试都输出模拟结果；调用方根据 passed() 决定是否发送
+pub async fn simulation_gate(
+    rpc_client: &NonblockingRpcClient,
+    transaction: &VersionedTransaction,
+    wallet: &Pubkey,
+    base_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> anyhow::Result<SimulationReport> {
+    let report = simulate_transaction(rpc_client, transaction, wallet, base_mint, token_program).await?;
+    let signature = transaction.signatures.first().copied().unwrap_or_default();
+    if report.passed() {
+        info!("Simulation passed for {}: {}", signature, report);
+    } else {
+        warn!("Simulation rejected {}: {}", signature, report);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_account_decoder::UiAccount;
+
+    const LOGS: [&str; 3] = [
+        "Program ComputeBudget111111111111111111111111111111 success",
+        "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz consumed 182344 of 1399700 compute units",
+        "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz success",
+    ];
+
+    // simulateTransaction 返回的 result，accounts 中是模拟后的 base token 账户
+    fn canned_result(account: &Pubkey, post: Option<Account>, err: serde_json::Value, logs: &[&str]) -> RpcSimulateTransactionResult {
+        let accounts = post.map(|post| {
+            vec![serde_json::to_value(UiAccount::encode(account, &post, UiAccountEncoding::Base64, None, None))
+                .unwrap()]
+        });
+        serde_json::from_value(json!({
+            "err": err,
+            "logs": logs,
+            "accounts": accounts,
+            "unitsConsumed": 182_344,
+            "returnData": null,
+        }))
+        .unwrap()
+    }
+
+    fn lamports_account(lamports: u64) -> Account {
+        Account {
+            lamports,
+            data: vec![],
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn token_account(amount: u64) -> Account {
+        let mut data = vec![0u8; 165];
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..][..8].copy_from_slice(&amount.to_le_bytes());
+        Account {
+            data,
+            owner: spl_token::id(),
+            ..lamports_account(2_039_280)
+        }
+    }
+
+    #[test]
+    fn sol_profit_is_the_wallet_lamport_change() {
+        let wallet = Pubkey::new_unique();
+        let account = base_account(&wallet, &sol_mint(), &spl_token::id());
+        assert_eq!(account, wallet);
+        let result = canned_result(&account, Some(lamports_account(1_000_120_000)), json!(null), &LOGS);
+        let report = simulation_report(result, Ok(1_000_000_000), &account, &sol_mint()).unwrap();
+
+        assert_eq!(report.profit, Some(120_000));
+        assert_eq!(report.units_consumed, Some(182_344));
+        assert_eq!(report.logs.len(), 3);
+        assert!(report.passed());
+        assert_eq!(report.to_string(), "ok, 182344 CU, profit 120000");
+    }
+
+    #[test]
+    fn token_profit_reads_the_post_ata_amount() {
+        let (wallet, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = base_account(&wallet, &usdc, &spl_token::id());
+        assert_eq!(account, spl_associated_token_account::get_associated_token_address(&wallet, &usdc));
+        let result = canned_result(&account, Some(token_account(49_990_000)), json!(null), &LOGS);
+        let report = simulation_report(result, Ok(50_000_000), &account, &usdc).unwrap();
+
+        assert_eq!(report.profit, Some(-10_000));
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn failed_simulation_keeps_the_program_log() {
+        let wallet = Pubkey::new_unique();
+        let logs = [
+            LOGS[1],
+            "Program log: Error: no profit",
+            "Program MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz failed: custom program error: 0x1771",
+        ];
+        let result = canned_result(
+            &wallet,
+            Some(lamports_account(999_995_000)),
+            json!({ "InstructionError": [2, { "Custom": 6001 }] }),
+            &logs,
+        );
+        let report = simulation_report(result, Ok(1_000_000_000), &wallet, &sol_mint()).unwrap();
+
+        assert!(!report.passed());
+        assert_eq!(report.error.as_deref(), Some("InstructionError(2, Custom(6001))"));
+        assert_eq!(report.failure_log(), Some(logs[2]));
+        assert!(report.to_string().ends_with(logs[2]));
+    }
+
+    #[test]
+    fn unknown_balances_leave_profit_empty() {
+        let wallet = Pubkey::new_unique();
+        let result = canned_result(&wallet, None, json!(null), &LOGS);
+        let report = simulation_report(result, Ok(1_000_000_000), &wallet, &sol_mint()).unwrap();
+        assert_eq!(report.profit, None);
+        assert!(!report.passed());
+
+        let result = canned_result(&wallet, Some(lamports_account(1_000_120_000)), json!(null), &LOGS);
+        let pre_balance = Err(anyhow::anyhow!("rpc unavailable"));
+        let report = simulation_report(result, pre_balance, &wallet, &sol_mint()).unwrap();
+        assert_eq!(report.profit, None);
+        assert_eq!(report.to_string(), "ok, 182344 CU, unknown profit");
+    }
+
+    #[test]
+    fn token_2022_base_uses_its_own_ata() {
+        let (wallet, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_ne!(
+            base_account(&wallet, &mint, &spl_token_2022::id()),
+            base_account(&wallet, &mint, &spl_token::id())
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::config::StreamConfig;
+use crate::quote::AccountSnapshot;
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// 由 stream 持续更新的账户快照，报价时加读锁
+pub type LiveSnapshot = Arc<RwLock<AccountSnapshot>>;
+
+// 单个账户在某个 slot 的最新数据
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    pub slot: u64,
+    pub account: Account,
+}
+
+// 账户更新的来源：线上使用 websocket 订阅，测试或回放时可以换成本地数据源
+pub trait AccountUpdateSource: Send + Sync {
+    // 订阅给定账户并把更新写入 updates，订阅断开时返回
+    fn subscribe(
+        &self,
+        pubkeys: Vec<Pubkey>,
+        updates: mpsc::UnboundedSender<AccountUpdate>,
+    ) -> BoxFuture<'static, anyhow::Result<()>>;
+}
+
+// 通过 accountSubscribe 逐个订阅账户
+pub struct WebsocketAccountSource {
+    ws_url: String,
+    commitment: CommitmentConfig,
+}
+
+impl WebsocketAccountSource {
+    pub fn new(ws_url: &str) -> Self {
+        Self {
+            ws_url: ws_url.to_string(),
+            commitment: CommitmentConfig::processed(),
+        }
+    }
+}
+
+impl AccountUpdateSource for WebsocketAccountSource {
+    fn subscribe(
+        &self,
+        pubkeys: Vec<Pubkey>,
+        updates: mpsc::UnboundedSender<AccountUpdate>,
+    ) -> BoxFuture<'static, anyhow::Result<()>> {
+        let ws_url = self.ws_url.clone();
+        let commitment = self.commitment;
+        async move {
+            let client = PubsubClient::new(&ws_url).await?;
+            let config = RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(commitment),
+                ..RpcAccountInfoConfig::default()
+            };
+
+            let mut subscriptions = Vec::with_capacity(pubkeys.len());
+            for pubkey in pubkeys {
+                let (notifications, _unsubscribe) =
+                    client.account_subscribe(&pubkey, Some(config.clone())).await?;
+                subscriptions.push(notifications.map(move |response| (pubkey, response)));
+            }
+            info!("Subscribed to {} accounts via {}", subscriptions.len(), ws_url);
+
+            let mut notifications = stream::select_all(subscriptions);
+            while let Some((pubkey, response)) = notifications.next().await {
+                let Some(account) = response.value.decode::<Account>() else {
+                    warn!("Failed to decode account notification for {}", pubkey);
+                    continue;
+                };
+                let update = AccountUpdate {
+                    pubkey,
+                    slot: response.context.slot,
+                    account,
+                };
+                // 接收端已经退出，不需要继续订阅
+                if updates.send(update).is_err() {
+                    return Ok(());
+                }
+            }
+
+            Err(anyhow::anyhow!("Account subscription to {} closed", ws_url))
+        }
+        .boxed()
+    }
+}
+
+// 本地数据源：通过 push 手动推送更新，只转发被订阅的账户
+pub struct LocalAccountSource {
+    sender: broadcast::Sender<AccountUpdate>,
+}
+
+impl LocalAccountSource {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn push(&self, update: AccountUpdate) {
+        // 没有订阅者时直接丢弃
+        let _ = self.sender.send(update);
+    }
+}
+
+impl AccountUpdateSource for LocalAccountSource {
+    fn subscribe(
+        &self,
+        pubkeys: Vec<Pubkey>,
+        updates: mpsc::UnboundedSender<AccountUpdate>,
+    ) -> BoxFuture<'static, anyhow::Result<()>> {
+        let mut receiver = self.sender.subscribe();
+        let pubkeys: HashSet<Pubkey> = pubkeys.into_iter().collect();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(update) if pubkeys.contains(&update.pubkey) => {
+                        if updates.send(update).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Local account source lagged, {} updates skipped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+pub fn account_source(stream_config: &StreamConfig) -> Arc<dyn AccountUpdateSource> {
+    Arc::new(WebsocketAccountSource::new(&stream_config.ws_url))
+}
+
+// 后台任务：订阅 pubkeys 中的账户，按 slot 原地更新快照
+// 订阅断开后自动重连；账户列表变化（如 array 窗口移动）时重新订阅
+pub fn spawn_account_stream(
+    source: Arc<dyn AccountUpdateSource>,
+    snapshot: LiveSnapshot,
+    mut pubkeys: watch::Receiver<Vec<Pubkey>>,
+    reconnect_delay: Option<Duration>,
+) -> JoinHandle<()> {
+    let reconnect_delay = reconnect_delay.unwrap_or(DEFAULT_RECONNECT_DELAY);
+    tokio::spawn(async move {
+        // 发送端退出后账户列表不会再变化，不再监听
+        let mut watching = true;
+        'subscribe: loop {
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let subscription = tokio::spawn(source.subscribe(pubkeys.borrow_and_update().clone(), sender));
+
+            let mut applied = 0u64;
+            loop {
+                tokio::select! {
+                    update = receiver.recv() => match update {
+                        Some(update) => {
+                            if snapshot
+                                .write()
+                                .await
+                                .apply(update.pubkey, update.slot, update.account)
+                            {
+                                applied += 1;
+                            }
+                        }
+                        None => break,
+                    },
+                    changed = pubkeys.changed(), if watching => {
+                        if changed.is_err() {
+                            watching = false;
+                            continue;
+                        }
+                        info!("Subscribed accounts changed after {} updates, resubscribing", applied);
+                        subscription.abort();
+                        continue 'subscribe;
+                    }
+                }
+            }
+
+            match subscription.await {
+                Ok(Ok(())) => {
+                    info!("Account stream finished after {} updates", applied);
+                    return;
+                }
+                Ok(Err(e)) => error!("Account stream failed after {} updates: {}", applied, e),
+                Err(e) => error!("Account stream task panicked: {}", e),
+            }
+            tokio::time::sleep(reconnect_delay).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn update(pubkey: Pubkey, slot: u64, data: u8) -> AccountUpdate {
+        AccountUpdate {
+            pubkey,
+            slot,
+            account: Account {
+                lamports: 1,
+                data: vec![data],
+                owner: Pubkey::default(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        }
+    }
+
+    async fn data_of(snapshot: &LiveSnapshot, pubkey: &Pubkey) -> Option<u8> {
+        snapshot.read().await.data(pubkey).ok().map(|data| data[0])
+    }
+
+    // 订阅在后台任务中建立，推送前不知道是否已经订阅上，重复推送直到快照中出现这条更新
+    async fn push_until_applied(source: &LocalAccountSource, snapshot: &LiveSnapshot, update: AccountUpdate) {
+        let expected = update.account.data[0];
+        for _ in 0..100 {
+            source.push(update.clone());
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            if data_of(snapshot, &update.pubkey).await == Some(expected) {
+                return;
+            }
+        }
+        panic!("update for {} was never applied", update.pubkey);
+    }
+
+    #[tokio::test]
+    async fn stream_applies_subscribed_updates_and_resubscribes() {
+        let pool = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let tick_array = Pubkey::new_unique();
+
+        let mut accounts = HashMap::new();
+        let initial = update(pool, 10, 1);
+        accounts.insert(pool, (initial.slot, initial.account));
+        let snapshot: LiveSnapshot = Arc::new(RwLock::new(AccountSnapshot::from_accounts(accounts)));
+
+        let source = Arc::new(LocalAccountSource::new(64));
+        let (subscriptions, receiver) = watch::channel(vec![pool, vault]);
+        let handle = spawn_account_stream(
+            source.clone(),
+            snapshot.clone(),
+            receiver,
+            Some(Duration::from_millis(10)),
+        );
+
+        push_until_applied(&source, &snapshot, update(pool, 11, 2)).await;
+
+        // 早于快照的 slot 不覆盖；未订阅的 tick array 不写入快照
+        source.push(update(pool, 5, 3));
+        source.push(update(tick_array, 20, 4));
+        // 同一个接收端按顺序处理，vault 的更新生效时前面两条已经处理过
+        push_until_applied(&source, &snapshot, update(vault, 12, 5)).await;
+        assert_eq!(data_of(&snapshot, &pool).await, Some(2));
+        assert_eq!(snapshot.read().await.slot(&[pool]), 11);
+        assert!(!snapshot.read().await.contains(&tick_array));
+
+        // 窗口移动后订阅列表加入 tick array，重新订阅后它的更新开始写入
+        subscriptions.send(vec![pool, vault, tick_array]).unwrap();
+        push_until_applied(&source, &snapshot, update(tick_array, 21, 6)).await;
+        assert_eq!(snapshot.read().await.slot(&[tick_array]), 21);
+
+        handle.abort();
+    }
+}
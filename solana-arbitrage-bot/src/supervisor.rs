@@ -0,0 +1,389 @@
+use crate::config::SupervisorConfig;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tracing::{error, info, warn};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(90);
+// 连续运行超过这个时间后再失败，重启间隔从头开始计算
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+// 关闭信号，各任务在每轮循环开始时检查，触发后不再发送新交易
+// 可以单独停止的任务另有自己的信号，全局关闭时同样触发
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+    parent: Option<watch::Receiver<bool>>,
+}
+
+impl Shutdown {
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+            || self
+                .parent
+                .as_ref()
+                .map_or(false, |parent| *parent.borrow())
+    }
+
+    pub async fn wait(&mut self) {
+        // 发送端被丢弃同样视为关闭
+        match &mut self.parent {
+            Some(parent) => {
+                tokio::select! {
+                    _ = self.receiver.wait_for(|triggered| *triggered) => {}
+                    _ = parent.wait_for(|triggered| *triggered) => {}
+                }
+            }
+            None => {
+                let _ = self.receiver.wait_for(|triggered| *triggered).await;
+            }
+        }
+    }
+
+    // 可被关闭信号打断的 sleep，返回 true 表示已经触发关闭
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => self.is_triggered(),
+            _ = self.wait() => true,
+        }
+    }
+}
+
+// 等待 SIGINT（Ctrl-C）或 SIGTERM
+pub async fn wait_for_signal() -> anyhow::Result<&'static str> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            Ok("SIGINT")
+        }
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+fn log_exit(name: &str, result: Result<anyhow::Result<()>, JoinError>) {
+    match result {
+        Ok(Ok(())) => warn!("Task {} exited", name),
+        Ok(Err(e)) => error!("Task {} failed: {}", name, e),
+        Err(e) if e.is_panic() => {
+            error!(
+                "Task {} panicked: {}",
+                name,
+                panic_message(&*e.into_panic())
+            )
+        }
+        Err(e) => warn!("Task {} was cancelled: {}", name, e),
+    }
+}
+
+// 外层的监管任务被终止时，一并终止正在运行的那一次
+struct AbortOnDrop(JoinHandle<anyhow::Result<()>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// 持有所有后台任务的 JoinHandle：被监管的任务失败或 panic 后按退避间隔重启，
+// 其他后台任务（账户订阅、array 窗口、优先费刷新）在关闭时直接终止
+pub struct Supervisor {
+    shutdown_sender: watch::Sender<bool>,
+    shutdown: Shutdown,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    shutdown_timeout: Duration,
+    supervised: Vec<(String, JoinHandle<()>)>,
+    // supervise_stoppable 启动的任务各自的关闭信号，按任务名索引
+    stoppable: HashMap<String, watch::Sender<bool>>,
+    background: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    pub fn new(supervisor_config: Option<&SupervisorConfig>) -> Self {
+        let (shutdown_sender, receiver) = watch::channel(false);
+        let millis = |value: Option<u64>, default: Duration| {
+            value.map(Duration::from_millis).unwrap_or(default)
+        };
+        Self {
+            shutdown_sender,
+            shutdown: Shutdown {
+                receiver,
+                parent: None,
+            },
+            initial_backoff: millis(
+                supervisor_config.and_then(|config| config.initial_backoff_ms),
+                DEFAULT_INITIAL_BACKOFF,
+            ),
+            max_backoff: millis(
+                supervisor_config.and_then(|config| config.max_backoff_ms),
+                DEFAULT_MAX_BACKOFF,
+            ),
+            shutdown_timeout: millis(
+                supervisor_config.and_then(|config| config.shutdown_timeout_ms),
+                DEFAULT_SHUTDOWN_TIMEOUT,
+            ),
+            supervised: Vec::new(),
+            stoppable: HashMap::new(),
+            background: Vec::new(),
+        }
+    }
+
+    // 未被监管的后台任务用来检查全局关闭信号
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    // task 每次重启都会被重新调用，应当只捕获可以 clone 的共享状态
+    pub fn supervise<F, Fut>(&mut self, name: impl Into<String>, task: F)
+    where
+        F: FnMut(Shutdown) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let shutdown = self.shutdown.clone();
+        self.spawn_supervised(name.into(), shutdown, task);
+    }
+
+    // 与 supervise 相同，但可以用 stop_task 按名字单独停止，如热加载时移除的 mint
+    pub fn supervise_stoppable<F, Fut>(&mut self, name: impl Into<String>, task: F)
+    where
+        F: FnMut(Shutdown) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let (sender, receiver) = watch::channel(false);
+        let shutdown = Shutdown {
+            receiver,
+            parent: Some(self.shutdown.receiver.clone()),
+        };
+        self.stoppable.insert(name.clone(), sender);
+        self.spawn_supervised(name, shutdown, task);
+    }
+
+    fn spawn_supervised<F, Fut>(&mut self, name: String, mut shutdown: Shutdown, mut task: F)
+    where
+        F: FnMut(Shutdown) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let task_name = name.clone();
+        let initial_backoff = self.initial_backoff;
+        let max_backoff = self.max_backoff;
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = initial_backoff;
+            loop {
+                let started = Instant::now();
+                // 单独 spawn 一层，panic 只影响这一次运行
+                let mut running = AbortOnDrop(tokio::spawn(task(shutdown.clone())));
+                let result = (&mut running.0).await;
+                if shutdown.is_triggered() {
+                    if !matches!(result, Ok(Ok(()))) {
+                        log_exit(&task_name, result);
+                    }
+                    break;
+                }
+                log_exit(&task_name, result);
+
+                if started.elapsed() >= STABLE_RUN {
+                    backoff = initial_backoff;
+                }
+                warn!("Restarting task {} in {:?}", task_name, backoff);
+                if shutdown.sleep(backoff).await {
+                    break;
+                }
+                backoff = (backoff * 2).min(max_backoff);
+            }
+            info!("Task {} stopped", task_name);
+        });
+        self.supervised.retain(|(_, handle)| !handle.is_finished());
+        self.supervised.push((name, handle));
+    }
+
+    // 返回的 AbortHandle 用于单独终止该任务
+    pub fn track(&mut self, handle: JoinHandle<()>) -> AbortHandle {
+        let abort_handle = handle.abort_handle();
+        self.background.retain(|handle| !handle.is_finished());
+        self.background.push(handle);
+        abort_handle
+    }
+
+    // 停止 supervise_stoppable 启动的任务，等待它结束当前一轮，超时后强制终止
+    pub async fn stop_task(&mut self, name: &str) {
+        let Some(sender) = self.stoppable.remove(name) else {
+            return;
+        };
+        let _ = sender.send(true);
+        let Some(index) = self
+            .supervised
+            .iter()
+            .position(|(task_name, _)| task_name == name)
+        else {
+            return;
+        };
+        let (name, mut handle) = self.supervised.remove(index);
+        if tokio::time::timeout(self.shutdown_timeout, &mut handle)
+            .await
+            .is_err()
+        {
+            warn!("Task {} did not stop in time, aborting", name);
+            handle.abort();
+        }
+    }
+
+    // 触发关闭，等待被监管的任务结束当前一轮，超时后强制终止
+    pub async fn stop(self) {
+        let _ = self.shutdown_sender.send(true);
+        for handle in &self.background {
+            handle.abort();
+        }
+
+        let deadline = tokio::time::Instant::now() + self.shutdown_timeout;
+        for (name, mut handle) in self.supervised {
+            if tokio::time::timeout_at(deadline, &mut handle)
+                .await
+                .is_err()
+            {
+                warn!("Task {} did not stop in time, aborting", name);
+                handle.abort();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    fn supervisor(shutdown_timeout_ms: u64) -> Supervisor {
+        Supervisor::new(Some(&SupervisorConfig {
+            initial_backoff_ms: Some(20),
+            max_backoff_ms: Some(80),
+            shutdown_timeout_ms: Some(shutdown_timeout_ms),
+        }))
+    }
+
+    #[tokio::test]
+    async fn failed_and_panicked_tasks_restart_with_growing_backoff() {
+        let mut supervisor = supervisor(1_000);
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let task_starts = starts.clone();
+        supervisor.supervise("flaky", move |_| {
+            let starts = task_starts.clone();
+            async move {
+                let run = {
+                    let mut starts = starts.lock().unwrap();
+                    starts.push(Instant::now());
+                    starts.len()
+                };
+                if run % 2 == 0 {
+                    panic!("run {} panicked", run);
+                }
+                Err(anyhow::anyhow!("run {} failed", run))
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        supervisor.stop().await;
+
+        let starts = starts.lock().unwrap();
+        assert!(starts.len() >= 4, "{} runs", starts.len());
+        let gaps: Vec<Duration> = starts.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        // 20, 40, 80, 80 ...
+        assert!(gaps[0] >= Duration::from_millis(20));
+        assert!(gaps[1] >= Duration::from_millis(40));
+        assert!(gaps[2] >= Duration::from_millis(80));
+        assert!(gaps[2] < Duration::from_millis(160));
+    }
+
+    #[tokio::test]
+    async fn stop_lets_supervised_tasks_finish_their_round_and_aborts_background() {
+        let mut supervisor = supervisor(1_000);
+        let finished = Arc::new(AtomicUsize::new(0));
+        let task_finished = finished.clone();
+        supervisor.supervise("worker", move |mut shutdown| {
+            let finished = task_finished.clone();
+            async move {
+                shutdown.wait().await;
+                // 收到关闭信号后完成当前一轮
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        let background = supervisor.track(tokio::spawn(std::future::pending::<()>()));
+
+        supervisor.stop().await;
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+        tokio::task::yield_now().await;
+        assert!(background.is_finished());
+    }
+
+    #[tokio::test]
+    async fn stop_aborts_tasks_that_ignore_shutdown_after_the_timeout() {
+        let mut supervisor = supervisor(50);
+        let finished = Arc::new(AtomicUsize::new(0));
+        let task_finished = finished.clone();
+        supervisor.supervise("stuck", move |_| {
+            let finished = task_finished.clone();
+            async move {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        let started = Instant::now();
+        supervisor.stop().await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn stop_task_only_stops_the_named_task() {
+        let mut supervisor = supervisor(1_000);
+        let rounds = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+        for index in 0..2 {
+            let rounds = rounds.clone();
+            supervisor.supervise_stoppable(format!("mint {}", index), move |mut shutdown| {
+                let rounds = rounds.clone();
+                async move {
+                    while !shutdown.sleep(Duration::from_millis(10)).await {
+                        rounds[index].fetch_add(1, Ordering::SeqCst);
+                    }
+                    Ok(())
+                }
+            });
+        }
+
+        supervisor.stop_task("mint 0").await;
+        let stopped_rounds = rounds[0].load(Ordering::SeqCst);
+        let running_rounds = rounds[1].load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(rounds[0].load(Ordering::SeqCst), stopped_rounds);
+        assert!(rounds[1].load(Ordering::SeqCst) > running_rounds);
+
+        supervisor.stop().await;
+    }
+}
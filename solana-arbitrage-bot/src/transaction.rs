@@ -0,0 +1,189 @@
+use crate::accounts::wallet_token_account;
+use crate::compute_budget::ComputeBudget;
+use crate::config::Config;
+use crate::dex::{adapter, DexType};
+use crate::flash_loan::FlashLoanProvider;
+use crate::pools::MintPoolData;
+use crate::priority_fee::static_compute_unit_price;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_program;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+// 套利合约及其手续费账户
+pub const EXECUTOR_PROGRAM_ID: &str = "MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz";
+pub const FEE_COLLECTOR: &str = "6AGB9kqgSp2mQXwYpdrV4QVV8urvCaDS35U1wsLssy6H";
+const EXECUTE_DISCRIMINATOR: u8 = 28;
+// 合约的 no-failure 模式：开启时没有利润也不让交易失败；这里关闭，无利润的交易在链上回滚
+const NO_FAILURE_MODE: bool = false;
+// DLMM 单次 swap 最多经过的 bin 数量
+const MAX_BIN_TO_PROCESS: u64 = 20;
+const DEFAULT_MAX_RETRIES: u64 = 3;
+
+pub fn executor_program_id() -> Pubkey {
+    Pubkey::from_str(EXECUTOR_PROGRAM_ID).unwrap()
+}
+
+// 构建一笔套利交易需要的参数
+pub struct TransactionParams<'a> {
+    pub base_mint: Pubkey,
+    // base mint 的 token program，决定钱包 base ATA 的地址
+    pub base_token_program: Pubkey,
+    // 路径需要的账户，见 route_accounts
+    pub route_accounts: Vec<AccountMeta>,
+    // 路径依次经过的 DEX，用于没有模拟结果时查默认 CU
+    pub dexes: Vec<DexType>,
+    pub compute_unit_limit: u32,
+    pub compute_unit_price: u64,
+    pub lookup_tables: &'a [AddressLookupTableAccount],
+    // 开启闪电贷时的来源和借入数量，借款、还款指令包在套利指令前后
+    pub flash_loan: Option<(Arc<dyn FlashLoanProvider>, u64)>,
+}
+
+impl<'a> TransactionParams<'a> {
+    // CU limit 先按路径上的 DEX 查默认表，模拟后由调用方换成模拟消耗；CU 价格默认使用 [spam] 中的固定值，开启 [priority_fee] 时由调用方替换为估计值
+    pub fn new(
+        config: &Config,
+        compute_budget: &ComputeBudget,
+        base_mint: Pubkey,
+        base_token_program: Pubkey,
+        route_accounts: Vec<AccountMeta>,
+        dexes: Vec<DexType>,
+        lookup_tables: &'a [AddressLookupTableAccount],
+    ) -> Self {
+        Self {
+            base_mint,
+            base_token_program,
+            route_accounts,
+            compute_unit_limit: compute_budget.default_limit(&dexes),
+            dexes,
+            compute_unit_price: static_compute_unit_price(config),
+            lookup_tables,
+            flash_loan: None,
+        }
+    }
+}
+
+// 单 mint 路径的账户：mint 和钱包的 token 账户（按 mint 的 token program 推导），然后是各池子的 swap 账户
+pub fn route_accounts(pool_data: &MintPoolData) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(pool_data.mint, false),
+        AccountMeta::new(
+            wallet_token_account(&pool_data.wallet_account, &pool_data.mint, &pool_data.token_program),
+            false,
+        ),
+    ];
+    for keys in &pool_data.pools {
+        accounts.extend(adapter(keys.dex).swap_accounts(keys));
+    }
+    accounts
+}
+
+fn executor_instruction(wallet: &Pubkey, params: &TransactionParams) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*wallet, true),
+        AccountMeta::new_readonly(params.base_mint, false),
+        AccountMeta::new(Pubkey::from_str(FEE_COLLECTOR).unwrap(), false),
+        AccountMeta::new(
+            wallet_token_account(wallet, &params.base_mint, &params.base_token_program),
+            false,
+        ),
+        AccountMeta::new_readonly(params.base_token_program, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    accounts.extend(params.route_accounts.iter().cloned());
+
+    let mut data = vec![EXECUTE_DISCRIMINATOR];
+    // 最小利润由链下报价和发送前的模拟检查，合约内不再限制
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&params.compute_unit_limit.to_le_bytes());
+    data.push(NO_FAILURE_MODE as u8);
+    data.extend_from_slice(&MAX_BIN_TO_PROCESS.to_le_bytes());
+
+    Instruction {
+        program_id: executor_program_id(),
+        accounts,
+        data,
+    }
+}
+
+// 构建并签名交易，不发送；模拟和发送使用同一笔交易
+pub fn build_transaction(
+    wallet: &Keypair,
+    params: &TransactionParams,
+    blockhash: Hash,
+) -> anyhow::Result<VersionedTransaction> {
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(params.compute_unit_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(params.compute_unit_price),
+        executor_instruction(&wallet.pubkey(), params),
+    ];
+    if let Some((provider, amount)) = &params.flash_loan {
+        instructions = provider.wrap_instructions(&wallet.pubkey(), *amount, instructions)?;
+    }
+
+    let message = v0::Message::try_compile(
+        &wallet.pubkey(),
+        &instructions,
+        params.lookup_tables,
+        blockhash,
+    )?;
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[wallet])?;
+    debug!(
+        "Built transaction {} with {} route accounts",
+        transaction.signatures[0],
+        params.route_accounts.len()
+    );
+    Ok(transaction)
+}
+
+// 同一笔交易发送到所有 RPC，各 RPC 返回的签名相同；返回签名和接受了交易的 RPC 下标
+pub async fn send_transaction(
+    config: &Config,
+    transaction: &VersionedTransaction,
+    rpc_clients: &[Arc<RpcClient>],
+) -> anyhow::Result<(Signature, Vec<usize>)> {
+    let max_retries = config
+        .spam
+        .as_ref()
+        .and_then(|spam_config| spam_config.max_retries)
+        .unwrap_or(DEFAULT_MAX_RETRIES);
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(max_retries as usize),
+        ..RpcSendTransactionConfig::default()
+    };
+
+    // RpcClient 是阻塞的，每个 RPC 放到单独的线程中并发发送
+    let sends = rpc_clients.iter().enumerate().map(|(index, rpc_client)| {
+        let rpc_client = rpc_client.clone();
+        let transaction = transaction.clone();
+        tokio::task::spawn_blocking(move || {
+            (index, rpc_client.send_transaction_with_config(&transaction, send_config))
+        })
+    });
+
+    let mut accepted = Vec::new();
+    for result in futures::future::join_all(sends).await {
+        match result? {
+            (index, Ok(_)) => accepted.push(index),
+            (index, Err(e)) => error!("RPC {} rejected transaction: {}", index, e),
+        }
+    }
+    if accepted.is_empty() {
+        return Err(anyhow::anyhow!("No RPC accepted the transaction"));
+    }
+    Ok((transaction.signatures[0], accepted))
+}
@@ -0,0 +1,521 @@
+use crate::accounts::{fetch_multiple_accounts, FetchPolicy};
+use crate::config::{Config, MintConfig};
+use crate::constants::sol_mint;
+use crate::dex::{registry, DexPool};
+use crate::pools::MintPoolData;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::{env, fs};
+
+// 通过 serde_string_or_env 读取、可以写成 $ENV_NAME 的字段
+const ENV_FIELDS: [&str; 4] = ["rpc.url", "wallet.private_key", "stream.ws_url", "jito.uuid"];
+
+// 配置中的一个问题，line 为配置文件中的行号（从 1 开始），无法定位时为 None
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+// 一次收集所有问题，而不是在第一个错误处返回
+struct Validator<'a> {
+    contents: &'a str,
+    issues: Vec<ConfigIssue>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(contents: &'a str) -> Self {
+        Self {
+            contents,
+            issues: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, field: impl Into<String>, line: Option<usize>, message: impl Into<String>) {
+        self.issues.push(ConfigIssue {
+            field: field.into(),
+            line,
+            message: message.into(),
+        });
+    }
+
+    // 值在配置文件中第一次出现的行，用于定位字符串字段
+    fn line_of(&self, value: &str) -> Option<usize> {
+        self.nth_line_of(value, None, 0)
+    }
+
+    // 从 from_line 起值第 n 次（从 0 开始）出现的行，用于定位重复条目本身而不是第一次出现的位置
+    fn nth_line_of(&self, value: &str, from_line: Option<usize>, n: usize) -> Option<usize> {
+        let quoted = format!("\"{}\"", value);
+        let skip = from_line.map_or(0, |line| line - 1);
+        self.contents
+            .lines()
+            .enumerate()
+            .skip(skip)
+            .filter(|(_, line)| line.contains(&quoted))
+            .nth(n)
+            .map(|(index, _)| index + 1)
+    }
+
+    fn check_pubkey(&mut self, field: impl Into<String>, value: &str) -> Option<Pubkey> {
+        match Pubkey::from_str(value) {
+            Ok(pubkey) => Some(pubkey),
+            Err(e) => {
+                let line = self.line_of(value);
+                self.push(field, line, format!("invalid pubkey {}: {}", value, e));
+                None
+            }
+        }
+    }
+
+    // base 不是 SOL 时交易成本按 base_per_sol 换算，缺少时报价无法扣除成本
+    fn check_base_per_sol(&mut self, prefix: &str, base_mint: &str, base_per_sol: Option<u64>) {
+        let Some(pubkey) = self.check_pubkey(format!("{}.base_mint", prefix), base_mint) else {
+            return;
+        };
+        if pubkey == sol_mint() {
+            return;
+        }
+        let line = self.line_of(base_mint);
+        match base_per_sol {
+            None => self.push(
+                format!("{}.base_per_sol", prefix),
+                line,
+                format!(
+                    "base mint {} is not SOL, base_per_sol is required to convert transaction costs",
+                    base_mint
+                ),
+            ),
+            Some(0) => self.push(format!("{}.base_per_sol", prefix), line, "must be greater than 0"),
+            Some(_) => {}
+        }
+    }
+
+    fn check_env_vars(&mut self, value: &toml::Value) -> Vec<String> {
+        let mut missing = Vec::new();
+        for field in ENV_FIELDS {
+            let mut current = Some(value);
+            for key in field.split('.') {
+                current = current.and_then(|value| value.get(key));
+            }
+            let Some(name) = current
+                .and_then(|value| value.as_str())
+                .and_then(|value| value.strip_prefix('$'))
+            else {
+                continue;
+            };
+            if env::var(name).is_err() {
+                let line = self.line_of(&format!("${}", name));
+                self.push(field, line, format!("environment variable {} is not set", name));
+                missing.push(field.to_string());
+            }
+        }
+        missing
+    }
+
+    fn check_config(&mut self, config: &Config) {
+        let mint_config_list = &config.routing.mint_config_list;
+        if mint_config_list.is_empty() {
+            self.push("routing.mint_config_list", None, "no mints configured");
+        }
+        let mut mint_keys = Vec::with_capacity(mint_config_list.len());
+        for (index, mint_config) in mint_config_list.iter().enumerate() {
+            // 同一个 mint 可以搭配不同的 base mint 出现多次，按出现次数定位本条目
+            let occurrence = mint_config_list[..index]
+                .iter()
+                .filter(|previous| previous.mint == mint_config.mint)
+                .count();
+            let line = self.nth_line_of(&mint_config.mint, None, occurrence);
+            self.check_mint_config(index, mint_config, line);
+            // 热加载按 mint 和 base mint 匹配新旧配置，两者相同的条目只能有一个
+            let key = mint_config.key();
+            if mint_keys.contains(&key) {
+                self.push(
+                    format!("routing.mint_config_list[{}]", index),
+                    line,
+                    format!("mint {} is listed more than once", key),
+                );
+            }
+            mint_keys.push(key);
+        }
+
+        if let Some(cycle_config) = config
+            .routing
+            .cycle
+            .as_ref()
+            .filter(|cycle_config| cycle_config.enabled)
+        {
+            if let Some(base_mint) = &cycle_config.base_mint {
+                self.check_base_per_sol("routing.cycle", base_mint, cycle_config.base_per_sol);
+            }
+            if cycle_config.process_delay == 0 {
+                self.push("routing.cycle.process_delay", None, "must be greater than 0");
+            }
+        }
+
+        if let Some(spam_config) = &config.spam {
+            if spam_config.enabled && spam_config.sending_rpc_urls.is_empty() {
+                self.push(
+                    "spam.sending_rpc_urls",
+                    None,
+                    "spam is enabled but no sending RPC URLs are configured",
+                );
+            }
+        }
+
+        if let Some(jito_config) = config.jito.as_ref().filter(|jito_config| jito_config.enabled) {
+            if jito_config.block_engine_urls.is_empty() {
+                self.push(
+                    "jito.block_engine_urls",
+                    None,
+                    "Jito is enabled but no block engine URLs are configured",
+                );
+            }
+        }
+
+        if let Some(flashloan_config) = &config.kamino_flashloan {
+            for (base_mint, reserve) in flashloan_config.reserves.iter().flatten() {
+                self.check_pubkey("kamino_flashloan.reserves", base_mint);
+                self.check_pubkey(format!("kamino_flashloan.reserves.{}", base_mint), reserve);
+            }
+        }
+        for (index, provider_config) in config.flash_loan_providers.iter().flatten().enumerate() {
+            self.check_pubkey(
+                format!("flash_loan_providers[{}].reserve", index),
+                &provider_config.reserve,
+            );
+        }
+    }
+
+    fn check_mint_config(&mut self, index: usize, mint_config: &MintConfig, mint_line: Option<usize>) {
+        let prefix = format!("routing.mint_config_list[{}]", index);
+        self.check_pubkey(format!("{}.mint", prefix), &mint_config.mint);
+        if let Some(base_mint) = &mint_config.base_mint {
+            self.check_base_per_sol(&prefix, base_mint, mint_config.base_per_sol);
+        }
+        if mint_config.process_delay == 0 {
+            self.push(format!("{}.process_delay", prefix), mint_line, "must be greater than 0");
+        }
+
+        // pools 表中没有 DEX 使用的键，其中的池子不会被加载
+        for key in mint_config.pools.keys() {
+            if registry().iter().all(|adapter| adapter.pool_list_key() != key) {
+                self.push(
+                    format!("{}.pools.{}", prefix, key),
+                    mint_line,
+                    "unknown pool list, no DEX uses this key",
+                );
+            }
+        }
+
+        // 同一个池子在一个 mint 的多个列表中出现时，路径会把它当成两个池子
+        // 池子 -> 第一次出现的列表和已出现的次数
+        let mut seen: HashMap<Pubkey, (&str, usize)> = HashMap::new();
+        let mut pool_count = 0;
+        for adapter in registry() {
+            let key = adapter.pool_list_key();
+            for (pool_index, pool) in mint_config.pools.get(key).into_iter().flatten().enumerate() {
+                pool_count += 1;
+                let field = format!("{}.pools.{}[{}]", prefix, key, pool_index);
+                let Some(pubkey) = self.check_pubkey(field.clone(), pool) else {
+                    continue;
+                };
+                match seen.get_mut(&pubkey) {
+                    Some((first, count)) => {
+                        let first = *first;
+                        let line = self.nth_line_of(pool, mint_line, *count);
+                        *count += 1;
+                        self.push(field, line, format!("pool {} is already listed in {}", pool, first));
+                    }
+                    None => {
+                        seen.insert(pubkey, (key, 1));
+                    }
+                }
+            }
+        }
+        if pool_count == 0 {
+            self.push(prefix.clone(), mint_line, "no pools configured for this mint");
+        }
+
+        for (table_index, lookup_table) in mint_config.lookup_table_list.iter().flatten().enumerate() {
+            self.check_pubkey(
+                format!("{}.lookup_table_list[{}]", prefix, table_index),
+                lookup_table,
+            );
+        }
+    }
+}
+
+fn read_config(path: &str) -> anyhow::Result<String> {
+    fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", path, e))
+}
+
+// 解析并检查配置文件，返回能解析出的配置和全部问题
+pub fn load(path: &str) -> anyhow::Result<(Option<Config>, Vec<ConfigIssue>)> {
+    Ok(check_contents(&read_config(path)?))
+}
+
+fn check_contents(contents: &str) -> (Option<Config>, Vec<ConfigIssue>) {
+    let mut validator = Validator::new(contents);
+
+    let value: toml::Value = match toml::from_str(contents) {
+        Ok(value) => value,
+        Err(e) => {
+            let line = e.line_col().map(|(line, _)| line + 1);
+            validator.push("config", line, e.to_string());
+            return (None, validator.issues);
+        }
+    };
+
+    // 环境变量缺失时先用空字符串代替，继续检查其余字段
+    let missing_env_fields = validator.check_env_vars(&value);
+    let parsed = if missing_env_fields.is_empty() {
+        toml::from_str::<Config>(contents).map_err(|e| (e.line_col().map(|(line, _)| line + 1), e))
+    } else {
+        let mut value = value;
+        for field in &missing_env_fields {
+            let keys: Vec<&str> = field.split('.').collect();
+            let (last, parents) = keys.split_last().unwrap();
+            let mut table = &mut value;
+            for key in parents {
+                table = table.get_mut(*key).unwrap();
+            }
+            table[*last] = toml::Value::String(String::new());
+        }
+        value.try_into::<Config>().map_err(|e| (None, e))
+    };
+
+    match parsed {
+        Ok(config) => {
+            validator.check_config(&config);
+            (Some(config), validator.issues)
+        }
+        Err((line, e)) => {
+            validator.push("config", line, e.to_string());
+            (None, validator.issues)
+        }
+    }
+}
+
+// 需要 RPC 的检查：池子账户存在、属于对应的 DEX 程序，并且包含配置的 mint 和 base mint
+// contents 为配置文件内容，用于定位池子所在的行
+pub async fn check_pools(
+    rpc_client: &NonblockingRpcClient,
+    fetch_policy: &FetchPolicy,
+    config: &Config,
+    contents: &str,
+) -> anyhow::Result<Vec<ConfigIssue>> {
+    let mut validator = Validator::new(contents);
+    let mint_config_list = &config.routing.mint_config_list;
+    for (index, mint_config) in mint_config_list.iter().enumerate() {
+        let (Ok(base_mint), Ok(_)) = (mint_config.base_mint(), Pubkey::from_str(&mint_config.mint))
+        else {
+            continue;
+        };
+        let occurrence = mint_config_list[..index]
+            .iter()
+            .filter(|previous| previous.mint == mint_config.mint)
+            .count();
+        let mint_line = validator.nth_line_of(&mint_config.mint, None, occurrence);
+        // (DEX, 池子, 字段, 行)
+        let mut pools: Vec<(&dyn DexPool, Pubkey, String, Option<usize>)> = Vec::new();
+        for adapter in registry() {
+            let key = adapter.pool_list_key();
+            for (pool_index, pool) in mint_config.pools.get(key).into_iter().flatten().enumerate() {
+                if let Ok(pubkey) = Pubkey::from_str(pool) {
+                    let field = format!("routing.mint_config_list[{}].pools.{}[{}]", index, key, pool_index);
+                    let repeated = pools.iter().filter(|(_, previous, _, _)| *previous == pubkey).count();
+                    let line = validator.nth_line_of(pool, mint_line, repeated);
+                    pools.push((*adapter, pubkey, field, line));
+                }
+            }
+        }
+
+        let pool_pubkeys: Vec<Pubkey> = pools.iter().map(|(_, pubkey, _, _)| *pubkey).collect();
+        let accounts = fetch_multiple_accounts(rpc_client, &pool_pubkeys, fetch_policy).await?;
+        // 只用于检查，钱包地址不影响池子解析
+        let mut pool_data = MintPoolData::new(&mint_config.mint, &Pubkey::default().to_string())?;
+        for (adapter, pool_pubkey, field, line) in pools {
+            let result = match accounts.get(&pool_pubkey) {
+                Some(account) if account.owner != adapter.program_id() => Err(anyhow::anyhow!(
+                    "{} pool account {} is not owned by the {} program (owner {})",
+                    adapter.name(),
+                    pool_pubkey,
+                    adapter.name(),
+                    account.owner
+                )),
+                Some(account) => adapter.add_pool(&mut pool_data, &pool_pubkey, account, &base_mint),
+                None => Err(anyhow::anyhow!(
+                    "{} pool account {} does not exist",
+                    adapter.name(),
+                    pool_pubkey
+                )),
+            };
+            if let Err(e) = result {
+                validator.push(field, line, e.to_string());
+            }
+        }
+    }
+    Ok(validator.issues)
+}
+
+pub fn format_issues(path: &str, issues: &[ConfigIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| match issue.line {
+            Some(line) => format!("{}:{}: {}: {}", path, line, issue.field, issue.message),
+            None => format!("{}: {}: {}", path, issue.field, issue.message),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// validate 子命令：输出所有问题，online 时再用 RPC 检查池子
+pub async fn run(path: &str, online: bool) -> anyhow::Result<()> {
+    let contents = read_config(path)?;
+    let (config, mut issues) = check_contents(&contents);
+    if let (Some(config), true) = (&config, online && issues.is_empty()) {
+        let rpc_client = NonblockingRpcClient::new(config.rpc.url.clone());
+        let fetch_policy = FetchPolicy::from_config(&config.rpc);
+        issues.extend(check_pools(&rpc_client, &fetch_policy, config, &contents).await?);
+    }
+    if issues.is_empty() {
+        println!("{}: OK", path);
+        return Ok(());
+    }
+    println!("{}", format_issues(path, &issues));
+    Err(anyhow::anyhow!("Found {} problems in {}", issues.len(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    // 第 9 行起为 mint 条目，pools 中的池子依次在第 13、14 行
+    fn sample(mint_entry: &str) -> String {
+        format!(
+            r#"[bot]
+compute_unit_limit = 400000
+
+[rpc]
+url = "http://127.0.0.1:8899"
+
+[wallet]
+private_key = "test-key"
+{}"#,
+            mint_entry
+        )
+    }
+
+    fn mint_entry(mint: &str, pools: [&str; 2]) -> String {
+        format!(
+            r#"[[routing.mint_config_list]]
+mint = "{}"
+process_delay = 400
+[routing.mint_config_list.pools]
+raydium_pool_list = ["{}"]
+raydium_cp_pool_list = ["{}"]
+"#,
+            mint, pools[0], pools[1]
+        )
+    }
+
+    fn pool() -> String {
+        Pubkey::new_unique().to_string()
+    }
+
+    #[test]
+    fn valid_sample_has_no_issues() {
+        let (config, issues) = check_contents(&sample(&mint_entry(USDC, [&pool(), &pool()])));
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(config.unwrap().routing.mint_config_list.len(), 1);
+    }
+
+    #[test]
+    fn load_reads_the_file() {
+        let path = env::temp_dir().join(format!("validate-{}.toml", Pubkey::new_unique()));
+        fs::write(&path, sample(&mint_entry(USDC, [&pool(), &pool()]))).unwrap();
+        let (config, issues) = load(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(config.is_some() && issues.is_empty(), "{:?}", issues);
+
+        assert!(load("/nonexistent/config.toml").is_err());
+    }
+
+    #[test]
+    fn duplicate_pool_is_reported_at_its_own_line() {
+        let duplicated = pool();
+        let (config, issues) = check_contents(&sample(&mint_entry(USDC, [&duplicated, &duplicated])));
+        assert!(config.is_some());
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].field, "routing.mint_config_list[0].pools.raydium_cp_pool_list[0]");
+        assert_eq!(issues[0].line, Some(14));
+    }
+
+    #[test]
+    fn invalid_values_are_located_and_all_collected() {
+        let mut contents = sample(&mint_entry("not-a-mint", ["not-a-pool", &pool()]));
+        contents.push_str("[routing.cycle]\nenabled = true\nbase_mint = \"");
+        contents.push_str(USDC);
+        contents.push_str("\"\namount_in = 1\nmin_profit = 1\nprocess_delay = 0\n");
+        let (config, issues) = check_contents(&contents);
+        assert!(config.is_some());
+
+        let found: Vec<(&str, Option<usize>)> =
+            issues.iter().map(|issue| (issue.field.as_str(), issue.line)).collect();
+        assert!(found.contains(&("routing.mint_config_list[0].mint", Some(10))), "{:?}", found);
+        assert!(found.contains(&("routing.mint_config_list[0].pools.raydium_pool_list[0]", Some(13))));
+        assert!(found.contains(&("routing.cycle.base_per_sol", Some(17))), "{:?}", found);
+        assert!(found.contains(&("routing.cycle.process_delay", None)));
+    }
+
+    #[test]
+    fn missing_env_var_is_reported_and_parsing_continues() {
+        let contents = sample(&mint_entry(USDC, [&pool(), &pool()]))
+            .replace("\"test-key\"", "\"$VALIDATE_TEST_UNSET_PRIVATE_KEY\"");
+        let (config, issues) = check_contents(&contents);
+        assert!(config.is_some());
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].field, "wallet.private_key");
+        assert_eq!(issues[0].line, Some(8));
+    }
+
+    #[test]
+    fn syntax_and_schema_errors_stop_at_the_config() {
+        let (config, issues) = check_contents("[bot]\ncompute_unit_limit = \n");
+        assert!(config.is_none());
+        assert_eq!(issues[0].line, Some(2));
+
+        // 拼错的字段名不会被忽略
+        let contents = sample(&mint_entry(USDC, [&pool(), &pool()])).replace("process_delay", "proces_delay");
+        let (config, issues) = check_contents(&contents);
+        assert!(config.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "config");
+    }
+
+    #[test]
+    fn format_issues_prefixes_path_and_line() {
+        let issues = [
+            ConfigIssue {
+                field: "rpc.url".to_string(),
+                line: Some(5),
+                message: "bad".to_string(),
+            },
+            ConfigIssue {
+                field: "routing.mint_config_list".to_string(),
+                line: None,
+                message: "no mints configured".to_string(),
+            },
+        ];
+        assert_eq!(
+            format_issues("config.toml", &issues),
+            "config.toml:5: rpc.url: bad\nconfig.toml: routing.mint_config_list: no mints configured"
+        );
+    }
+}
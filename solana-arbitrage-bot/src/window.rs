@@ -0,0 +1,243 @@
+use crate::accounts::{fetch_multiple_accounts_with_slot, FetchPolicy};
+use crate::config::ArrayWindowConfig;
+use crate::dex::{collect_pool_accounts, collect_pool_keys, registry};
+use crate::pools::MintPoolData;
+use crate::quote::AccountSnapshot;
+use crate::stream::LiveSnapshot;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+// 每种池子在当前 array 两侧各保留的 array 数量
+#[derive(Debug, Clone, Copy)]
+pub struct ArrayWindow {
+    pub raydium_clmm: u16,
+    // whirlpool 单次 swap 最多跨 3 个 tick array，两侧各保留 2 个
+    pub whirlpool: u16,
+    pub dlmm: u16,
+    pub refresh_interval: Duration,
+}
+
+impl Default for ArrayWindow {
+    fn default() -> Self {
+        Self {
+            raydium_clmm: 1,
+            whirlpool: 2,
+            dlmm: 1,
+            refresh_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ArrayWindow {
+    pub fn from_config(window_config: Option<&ArrayWindowConfig>) -> Self {
+        let default = Self::default();
+        let Some(window_config) = window_config else {
+            return default;
+        };
+        Self {
+            raydium_clmm: window_config.raydium_clmm.unwrap_or(default.raydium_clmm),
+            whirlpool: window_config.whirlpool.unwrap_or(default.whirlpool),
+            dlmm: window_config.dlmm.unwrap_or(default.dlmm),
+            refresh_interval: window_config
+                .refresh_interval_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.refresh_interval),
+        }
+    }
+
+    // 相对当前 array 的偏移，从小到大排列
+    pub fn offsets(width: u16) -> Vec<i32> {
+        (-(width as i32)..=width as i32).collect()
+    }
+}
+
+// 按快照重新计算所有池子的 array，有变化时返回替换后的 MintPoolData
+pub fn recompute_arrays(
+    pool_data: &MintPoolData,
+    snapshot: &AccountSnapshot,
+    window: &ArrayWindow,
+) -> Option<MintPoolData> {
+    let mut refreshed = pool_data.clone();
+    let mut changed = false;
+    for adapter in registry() {
+        changed |= adapter.refresh_arrays(&mut refreshed, snapshot, window);
+    }
+    changed.then_some(refreshed)
+}
+
+async fn refresh_once(
+    rpc_client: &NonblockingRpcClient,
+    pool_data: &Mutex<MintPoolData>,
+    live_snapshot: Option<&LiveSnapshot>,
+    subscriptions: Option<&watch::Sender<Vec<Pubkey>>>,
+    window: &ArrayWindow,
+    fetch_policy: &FetchPolicy,
+) -> anyhow::Result<()> {
+    let current = pool_data.lock().await.clone();
+    let refreshed = match live_snapshot {
+        Some(snapshot) => recompute_arrays(&current, &*snapshot.read().await, window),
+        // 没有订阅时只拉取池子账户本身，判断当前 tick / active bin
+        None => {
+            let pools: Vec<Pubkey> = collect_pool_keys(&current).iter().map(|keys| keys.pool).collect();
+            let snapshot = AccountSnapshot::fetch(rpc_client, &pools, fetch_policy).await?;
+            recompute_arrays(&current, &snapshot, window)
+        }
+    };
+    let Some(refreshed) = refreshed else {
+        return Ok(());
+    };
+
+    let accounts = collect_pool_accounts(&refreshed);
+    // 先把新的 array 补进快照，再替换池子数据，报价不会读到缺失的账户
+    if let Some(snapshot) = live_snapshot {
+        let missing: Vec<Pubkey> = {
+            let snapshot = snapshot.read().await;
+            accounts.iter().filter(|pubkey| !snapshot.contains(pubkey)).copied().collect()
+        };
+        let fetched = fetch_multiple_accounts_with_slot(rpc_client, &missing, fetch_policy).await?;
+        let mut snapshot = snapshot.write().await;
+        for (pubkey, (slot, account)) in fetched {
+            snapshot.apply(pubkey, slot, account);
+        }
+    }
+
+    info!("Array window moved for mint {}", refreshed.mint);
+    *pool_data.lock().await = refreshed;
+    if let Some(subscriptions) = subscriptions {
+        // stream 已退出时没有接收端，忽略即可
+        let _ = subscriptions.send(accounts);
+    }
+    Ok(())
+}
+
+// 后台任务：价格跨过 array 边界时替换 MintPoolData 中的 tick array / bin array，并通知 stream 重新订阅
+// subscriptions 与热加载共用，池子列表变化时同样通过它重新订阅
+pub fn spawn_array_window_refresher(
+    rpc_client: Arc<NonblockingRpcClient>,
+    pool_data: Arc<Mutex<MintPoolData>>,
+    live_snapshot: Option<LiveSnapshot>,
+    subscriptions: Option<Arc<watch::Sender<Vec<Pubkey>>>>,
+    window: ArrayWindow,
+    fetch_policy: FetchPolicy,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(window.refresh_interval).await;
+            if let Err(e) = refresh_once(
+                &rpc_client,
+                &pool_data,
+                live_snapshot.as_ref(),
+                subscriptions.as_deref(),
+                &window,
+                &fetch_policy,
+            )
+            .await
+            {
+                error!("Failed to refresh array window: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::DexType;
+    use crate::quote::dlmm::{bin_array_address, MAX_BIN_PER_ARRAY};
+    use crate::quote::PoolKeys;
+    use solana_sdk::account::Account;
+    use std::collections::HashMap;
+
+    // LbPair 中 active_id 的偏移，其余字段在这里不需要
+    const ACTIVE_ID_OFFSET: usize = 76;
+    const LB_PAIR_LEN: usize = 904;
+
+    fn lb_pair(active_id: i32) -> Account {
+        let mut data = vec![0u8; LB_PAIR_LEN];
+        data[ACTIVE_ID_OFFSET..ACTIVE_ID_OFFSET + 4].copy_from_slice(&active_id.to_le_bytes());
+        Account {
+            lamports: 1,
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn dlmm_pool_data(pool: Pubkey, bin_arrays: Vec<Pubkey>) -> MintPoolData {
+        let mut pool_data =
+            MintPoolData::new(&Pubkey::new_unique().to_string(), &Pubkey::new_unique().to_string()).unwrap();
+        pool_data.pools.push(PoolKeys {
+            dex: DexType::Dlmm,
+            pool,
+            token_vault: Pubkey::new_unique(),
+            base_vault: Pubkey::new_unique(),
+            extra_accounts: bin_arrays,
+            instruction_accounts: vec![],
+        });
+        pool_data
+    }
+
+    fn snapshot(pool: Pubkey, active_id: i32) -> AccountSnapshot {
+        AccountSnapshot::from_accounts(HashMap::from([(pool, (1, lb_pair(active_id)))]))
+    }
+
+    fn bin_arrays(pool: &Pubkey, indexes: &[i64]) -> Vec<Pubkey> {
+        indexes.iter().map(|index| bin_array_address(pool, *index)).collect()
+    }
+
+    #[test]
+    fn from_config_falls_back_to_defaults() {
+        let window = ArrayWindow::from_config(None);
+        assert_eq!((window.raydium_clmm, window.whirlpool, window.dlmm), (1, 2, 1));
+        assert_eq!(window.refresh_interval, Duration::from_secs(1));
+
+        let window = ArrayWindow::from_config(Some(&ArrayWindowConfig {
+            raydium_clmm: None,
+            whirlpool: Some(3),
+            dlmm: Some(0),
+            refresh_interval_ms: Some(250),
+        }));
+        assert_eq!((window.raydium_clmm, window.whirlpool, window.dlmm), (1, 3, 0));
+        assert_eq!(window.refresh_interval, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn offsets_are_symmetric_around_the_current_array() {
+        assert_eq!(ArrayWindow::offsets(0), vec![0]);
+        assert_eq!(ArrayWindow::offsets(2), vec![-2, -1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn arrays_only_move_when_the_active_bin_crosses_a_boundary() {
+        let pool = Pubkey::new_unique();
+        let window = ArrayWindow::default();
+        // bin -1960 是 index -28 的第一个 bin
+        let first_bin = -28 * MAX_BIN_PER_ARRAY;
+        let pool_data = dlmm_pool_data(pool, bin_arrays(&pool, &[-29, -28, -27]));
+
+        assert!(recompute_arrays(&pool_data, &snapshot(pool, first_bin), &window).is_none());
+        let last_bin = first_bin + MAX_BIN_PER_ARRAY - 1;
+        assert!(recompute_arrays(&pool_data, &snapshot(pool, last_bin), &window).is_none());
+
+        let moved = recompute_arrays(&pool_data, &snapshot(pool, first_bin - 1), &window).unwrap();
+        assert_eq!(moved.pools[0].extra_accounts, bin_arrays(&pool, &[-30, -29, -28]));
+        let moved = recompute_arrays(&pool_data, &snapshot(pool, last_bin + 1), &window).unwrap();
+        assert_eq!(moved.pools[0].extra_accounts, bin_arrays(&pool, &[-28, -27, -26]));
+        // 原数据不变
+        assert_eq!(pool_data.pools[0].extra_accounts, bin_arrays(&pool, &[-29, -28, -27]));
+    }
+
+    #[test]
+    fn pools_missing_from_the_snapshot_are_left_alone() {
+        let pool = Pubkey::new_unique();
+        let pool_data = dlmm_pool_data(pool, vec![]);
+        let snapshot = snapshot(Pubkey::new_unique(), 0);
+        assert!(recompute_arrays(&pool_data, &snapshot, &ArrayWindow::default()).is_none());
+    }
+}